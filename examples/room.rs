@@ -10,25 +10,22 @@ fn main() -> Result<(), ExitFailure> {
         .set_pattern(Pattern::solid(colour(1.0, 0.9, 0.9)))
         .set_specular(0.0);
 
-    let mut floor = unit_sphere();
-    floor.set_object_to_world_spc(scaling(10.0, 0.01, 10.0));
+    let mut floor = rect(20.0, 20.0);
     floor.set_material(mat);
 
-    let mut left_wall = unit_sphere();
+    let mut left_wall = rect(20.0, 20.0);
     left_wall.set_object_to_world_spc(
         translation(0.0, 0.0, 5.0)
             * rotation_y(-FRAC_PI_4)
-            * rotation_x(FRAC_PI_2)
-            * scaling(10.0, 0.01, 10.0),
+            * rotation_x(FRAC_PI_2),
     );
     left_wall.set_material(mat);
 
-    let mut right_wall = unit_sphere();
+    let mut right_wall = rect(20.0, 20.0);
     right_wall.set_object_to_world_spc(
         translation(0.0, 0.0, 5.0)
             * rotation_y(FRAC_PI_4)
-            * rotation_x(FRAC_PI_2)
-            * scaling(10.0, 0.01, 10.0),
+            * rotation_x(FRAC_PI_2),
     );
     right_wall.set_material(mat);
 
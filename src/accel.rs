@@ -0,0 +1,214 @@
+use crate::*;
+use crate::partition::{sah_split, SahSplit};
+use crate::shape::{append_local_intersects, intersect_bounding_box};
+use std::ops::Range;
+
+/// A read-only structure for quickly finding the intersections of a
+/// ray with the objects in a world.
+///
+/// Groups are compiled away.  Every object within them becomes a
+/// primitive, which refers to the object where it is in the world, and
+/// holds the transform from world space into the object's own space and
+/// its bounding box in world space.  The primitives are arranged in a
+/// bounding volume hierarchy.
+#[derive(Debug)]
+pub(crate) struct Accel {
+    bvh: Bvh,
+    /// The primitives with finite bounds, in the order the hierarchy
+    /// refers to them.
+    primitives: Vec<Primitive>,
+    /// Primitives with infinite bounds, such as planes, which are
+    /// tested against every ray.
+    unbounded: Vec<Primitive>,
+    /// Where each primitive's object is in the world: how many groups
+    /// deep it is, then the index of the object among the world's
+    /// objects, followed by its index among the children of each group
+    /// it is in.  Primitives refer to the start of their entry.
+    paths: Vec<usize>,
+}
+
+/// An object from the world, after taking it out of any groups.
+#[derive(Debug)]
+struct Primitive {
+    /// Where the object's entry in `Accel::paths` starts.
+    path: usize,
+    /// The transform from world space into the object's own space,
+    /// through the spaces of any groups containing it.
+    to_object_spc: Matrix,
+    /// The product of the world to object transforms of the groups
+    /// containing the object, or None if the object wasn't in a group.
+    to_group_spc: Option<Matrix>,
+    bounds: Bounds,
+}
+
+impl Accel {
+    /// Compiles the objects.  The accelerator refers to them by index,
+    /// so the same objects must be given to `intersect`.
+    pub(crate) fn build(objects: &[Object]) -> Accel {
+        let mut all = vec![];
+        let mut paths = vec![];
+        for (n, o) in objects.iter().enumerate() {
+            collect_primitives(o, &mut vec![n], None, &mut all, &mut paths);
+        }
+
+        let (bounded, unbounded): (Vec<Primitive>, Vec<Primitive>) =
+            all.into_iter().partition(|p| p.bounds.is_finite());
+        let (bvh, primitives) = Bvh::build(bounded, |p| p.bounds);
+        Accel { bvh, primitives, unbounded, paths }
+    }
+
+    /// Appends the intersections of the ray with every primitive.  They
+    /// are not sorted.
+    pub(crate) fn intersect<'a>(&'a self, objects: &'a [Object], r: &Ray, vec: &mut Vec<Intersection<'a>>) {
+        for prim in self.unbounded.iter() {
+            append_primitive_intersects(r, self.object_of(objects, prim), prim, vec);
+        }
+        self.bvh.traverse(r, |range| {
+            for prim in self.primitives[range].iter() {
+                append_primitive_intersects(r, self.object_of(objects, prim), prim, vec);
+            }
+        });
+    }
+
+    /// Finds the primitive's object among the world's objects.
+    fn object_of<'a>(&self, objects: &'a [Object], prim: &Primitive) -> &'a Object {
+        let depth = self.paths[prim.path];
+        let groups = &self.paths[prim.path + 2..prim.path + 2 + depth];
+        groups.iter().fold(&objects[self.paths[prim.path + 1]], |o, &n| &o.children()[n])
+    }
+}
+
+fn append_primitive_intersects<'a>(r: &Ray, obj: &'a Object, prim: &'a Primitive, vec: &mut Vec<Intersection<'a>>) {
+    match &prim.to_group_spc {
+        None => append_intersects(r, obj, vec),
+        Some(m) => {
+            let initial = vec.len();
+            append_local_intersects(&r.transform(&prim.to_object_spc), obj, vec);
+            for i in vec[initial..].iter_mut() {
+                i.set_flattened_space(m);
+            }
+        },
+    }
+}
+
+/// A bounding volume hierarchy over a list of items, built with the
+/// surface area heuristic.  The nodes are stored in a single `Vec` and
+/// refer to each other, and to the items, by index.  The items
+/// themselves are kept by the owner of the hierarchy.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Bvh {
+    nodes: Vec<Node>,
+}
+
+/// A node of the hierarchy.  A leaf holds `count` items starting at
+/// `first`.  An interior node has no items; its first child is the
+/// node immediately after it, and `first` is the index of its second
+/// child.
+#[derive(Debug, PartialEq)]
+struct Node {
+    bounds: Bounds,
+    first: usize,
+    count: usize,
+}
+
+impl Bvh {
+    /// Arranges the items into a hierarchy.  The items are returned
+    /// reordered, so that each leaf refers to a run of them.
+    pub(crate) fn build<T, F>(items: Vec<T>, bounds_of: F) -> (Bvh, Vec<T>)
+        where F: Fn(&T) -> Bounds
+    {
+        let mut bvh = Bvh { nodes: vec![] };
+        let mut ordered = Vec::with_capacity(items.len());
+        if !items.is_empty() {
+            bvh.build_node(items, &bounds_of, &mut ordered);
+        }
+        (bvh, ordered)
+    }
+
+    fn build_node<T, F>(&mut self, items: Vec<T>, bounds_of: &F, ordered: &mut Vec<T>)
+        where F: Fn(&T) -> Bounds
+    {
+        let bounds = items.iter().fold(Bounds::empty(), |b, i| b.union(&bounds_of(i)));
+        let index = self.nodes.len();
+        self.nodes.push(Node { bounds, first: 0, count: 0 });
+
+        match sah_split(items, bounds_of) {
+            SahSplit::Leaf(items) => {
+                self.nodes[index].first = ordered.len();
+                self.nodes[index].count = items.len();
+                ordered.extend(items);
+            },
+            SahSplit::Split(left, right) => {
+                self.build_node(left, bounds_of, ordered);
+                self.nodes[index].first = self.nodes.len();
+                self.build_node(right, bounds_of, ordered);
+            },
+        }
+    }
+
+    /// The bounds of all the items, or empty bounds if there are none.
+    pub(crate) fn bounds(&self) -> Bounds {
+        self.nodes.first().map(|n| n.bounds).unwrap_or_else(Bounds::empty)
+    }
+
+    /// Calls `visit` with the range of items held by each leaf whose
+    /// bounds the ray passes through.
+    pub(crate) fn traverse<F>(&self, r: &Ray, mut visit: F)
+        where F: FnMut(Range<usize>)
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if intersect_bounding_box(r, node.bounds).is_none() {
+                continue;
+            }
+            if node.count > 0 {
+                visit(node.first..node.first + node.count);
+            } else {
+                stack.push(node.first);
+                stack.push(n + 1);
+            }
+        }
+    }
+}
+
+/// Takes the objects out of any groups, noting the transform from
+/// world space into the space of the group containing them.  `path` is
+/// where `obj` is in the world, which is added to `paths` for each
+/// primitive.
+fn collect_primitives(obj: &Object, path: &mut Vec<usize>, to_group_spc: Option<Matrix>, acc: &mut Vec<Primitive>, paths: &mut Vec<usize>) {
+    if obj.is_group() {
+        let inner = match to_group_spc {
+            None => obj.world_to_object_spc(),
+            Some(m) => obj.world_to_object_spc() * m,
+        };
+        for (n, c) in obj.children().iter().enumerate() {
+            path.push(n);
+            collect_primitives(c, path, Some(inner), acc, paths);
+            path.pop();
+        }
+    } else {
+        let local = obj.bounds();
+        let bounds = match to_group_spc {
+            Some(m) if local.is_finite() => {
+                let to_world = m.inverse();
+                local.all_corners().iter().fold(Bounds::empty(), |b, c| {
+                    let p = to_world.mult(*c);
+                    b.union(&Bounds::new(p, p))
+                })
+            },
+            _ => local,
+        };
+        let to_object_spc = match to_group_spc {
+            None => obj.world_to_object_spc(),
+            Some(m) => obj.world_to_object_spc() * m,
+        };
+        acc.push(Primitive { path: paths.len(), to_object_spc, to_group_spc, bounds });
+        paths.push(path.len() - 1);
+        paths.extend_from_slice(path);
+    }
+}
@@ -1,8 +1,8 @@
-use std::fs::File;
+use std::path::Path;
+use std::time::Instant;
 
 use exitfailure::ExitFailure;
 
-use bucktracer::wavefront;
 use bucktracer::*;
 
 use structopt::StructOpt;
@@ -21,7 +21,12 @@ struct CmdOptions {
     #[structopt(long="depth", default_value="0")]
     depth: usize,
 
-    /// The input obj file
+    /// Partition with a bounding volume hierarchy instead, reporting
+    /// how long it took to build and its estimated traversal cost.
+    #[structopt(long="bvh")]
+    bvh: bool,
+
+    /// The input obj file, or stl or ply file if it has the extension .stl or .ply
     #[structopt(parse(from_os_str))]
     objfile: std::ffi::OsString,
 
@@ -29,22 +34,49 @@ struct CmdOptions {
 
 fn main() -> Result<(), ExitFailure> {
     let args: CmdOptions = CmdOptions::from_args();
-    let mut f = File::open(&args.objfile)?;
-    let objects = wavefront::read_object_vec(&mut f)?;
+    let objects = read_model(Path::new(&args.objfile))?;
     if args.before_partitioning {
         let root = group(objects);
         print_bounding_box_info(0, &root);
+    } else if args.bvh {
+        let start = Instant::now();
+        let root = bvh_partition(objects);
+        let elapsed = start.elapsed();
+        print_summary(&root, elapsed.as_secs_f64());
     } else {
+        let start = Instant::now();
         let map = bbox_map(args.depth, objects);
+        let elapsed = start.elapsed();
         for (ind, (k, v)) in map.iter().enumerate() {
             println!("{}\t{}\t{}", ind, v.len(), k);
         }
+        print_summary(&map.groups(), elapsed.as_secs_f64());
 //        let root = map.groups();
 //        print_bounding_box_info(0, &root);
     }
     Ok(())
 }
 
+fn print_summary(root: &Object, build_secs: f64) {
+    let (groups, leaves, depth) = count_nodes(root);
+    println!("groups\t{}", groups);
+    println!("objects\t{}", leaves);
+    println!("max depth\t{}", depth);
+    println!("build time\t{:.3}s", build_secs);
+    println!("traversal cost\t{:.3}", traversal_cost(root));
+}
+
+/// Counts the groups and the non-group objects in the tree, and
+/// how deeply groups are nested.
+fn count_nodes(node: &Object) -> (usize, usize, usize) {
+    if !node.is_group() {
+        return (0, 1, 0);
+    }
+    node.children().iter()
+        .map(count_nodes)
+        .fold((1, 0, 1), |(g, l, d), (cg, cl, cd)| (g + cg, l + cl, d.max(cd + 1)))
+}
+
 fn print_bounding_box_info(lvl: usize, node: &Object) -> usize {
     let children = node.children();
     let mut c = 0;
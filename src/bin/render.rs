@@ -0,0 +1,63 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use exitfailure::ExitFailure;
+
+use bucktracer::hdr;
+use bucktracer::pfm;
+use bucktracer::png;
+use bucktracer::ppm;
+use bucktracer::scene;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "render",
+    about = "Renders a scene described by a TOML scene file to an image",
+    rename_all = "kebab-case",
+)]
+struct CmdOptions {
+    /// The file to write the image to.  A file ending in .hdr or .pfm is
+    /// written as a high dynamic range image of that kind, one ending
+    /// in .ppm as a binary ppm image, anything else as png.  A png
+    /// image is written to stdout if omitted.
+    #[structopt(short="o", long="output", parse(from_os_str))]
+    output: Option<PathBuf>,
+
+    /// The number of threads to render with.  Defaults to the number of available cores.
+    #[structopt(long="threads")]
+    threads: Option<usize>,
+
+    /// The input scene file
+    #[structopt(parse(from_os_str))]
+    scenefile: PathBuf,
+}
+
+fn main() -> Result<(), ExitFailure> {
+    let args: CmdOptions = CmdOptions::from_args();
+
+    let (world, cam) = scene::load_scene(&args.scenefile)?;
+    let threads = args.threads.unwrap_or_else(|| {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    });
+    let canvas = cam.render_parallel(&world, threads, |p, total|{
+        eprintln!("{}/{}", p, total);
+    });
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    };
+    let extension = args.output.as_ref()
+        .and_then(|path| path.extension())
+        .map(|e| e.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("hdr") => hdr::encode(&canvas, &mut out)?,
+        Some("pfm") => pfm::encode(&canvas, &mut out)?,
+        Some("ppm") => ppm::encode_binary(&canvas, &mut out)?,
+        _ => png::encode(&canvas, &mut out)?,
+    }
+    Ok(())
+}
@@ -1,21 +1,25 @@
 use std::fs::File;
+use std::path::Path;
 use exitfailure::ExitFailure;
 
+use bucktracer::*;
 use bucktracer::wavefront;
+use bucktracer::wavefront::ParseHandler;
 
 use structopt::StructOpt;
-use bucktracer::wavefront::ParseError;
+use bucktracer::wavefront::Reason;
 use bucktracer::wavefront::PolygonPoint;
 
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "scanobj",
-    about = "A tool to output statistics about a wavefront obj file",
+    about = "A tool to output statistics about a wavefront obj, stl or ply file",
     rename_all = "kebab-case",
 )]
 struct CmdOptions {
     #[structopt(parse(from_os_str))]
-    /// List of files to scan
+    /// List of files to scan.  Those with the extension .stl or .ply
+    /// are read as stl or ply files, the rest as obj files.
     objfiles: Vec<std::ffi::OsString>,
 }
 
@@ -25,8 +29,11 @@ struct Counter {
     max_bound: (f64, f64, f64),
     vertices: usize,
     normals: usize,
+    texture_coords: usize,
     polygons: usize,
     groups: usize,
+    material_libraries: usize,
+    materials: usize,
 }
 
 impl Counter {
@@ -38,8 +45,11 @@ impl Counter {
             max_bound: (min, min, min),
             vertices: 0,
             normals: 0,
+            texture_coords: 0,
             polygons: 0,
             groups: 0,
+            material_libraries: 0,
+            materials: 0,
         }
     }
 
@@ -53,7 +63,7 @@ impl Counter {
 }
 
 impl wavefront::ParseHandler for Counter {
-    fn handle_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<(), ParseError> {
+    fn handle_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<(), Reason> {
         fn max(x: f64, y: f64) -> f64 { if y > x {y} else {x} }
         fn min(x: f64, y: f64) -> f64 { if x < y {x} else {y} }
         self.max_bound = (
@@ -70,38 +80,79 @@ impl wavefront::ParseHandler for Counter {
         Ok(())
     }
 
-    fn handle_normal(&mut self, _x: f64, _y: f64, _z: f64) -> Result<(), ParseError> {
+    fn handle_normal(&mut self, _x: f64, _y: f64, _z: f64) -> Result<(), Reason> {
         self.normals += 1;
         Ok(())
     }
 
-    fn handle_polygon(&mut self, _points: &[PolygonPoint]) -> Result<(), ParseError> {
+    fn handle_polygon(&mut self, _points: &[PolygonPoint]) -> Result<(), Reason> {
         self.polygons += 1;
         Ok(())
     }
 
-    fn declare_group(&mut self, _name: &str) -> Result<(), ParseError> {
+    fn declare_group(&mut self, _name: &str) -> Result<(), Reason> {
         self.groups += 1;
         Ok(())
     }
+
+    fn handle_texture_coord(&mut self, _u: f64, _v: f64) -> Result<(), Reason> {
+        self.texture_coords += 1;
+        Ok(())
+    }
+
+    fn use_material_library(&mut self, _name: &str) -> Result<(), Reason> {
+        self.material_libraries += 1;
+        Ok(())
+    }
+
+    fn use_material(&mut self, _name: &str) -> Result<(), Reason> {
+        self.materials += 1;
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ExitFailure> {
     let args: CmdOptions = CmdOptions::from_args();
 
     for file in &args.objfiles {
-        let mut f = File::open(&file)?;
+        let path = Path::new(file);
         let mut c = Counter::new();
-        wavefront::parse(&mut c, &mut f)?;
+        if ModelFormat::of(path) == ModelFormat::Obj {
+            let mut f = File::open(path)?;
+            wavefront::parse(&mut c, &mut f).map_err(|e| model_error(path, e))?;
+        } else {
+            count_triangles(&mut c, &read_model(path)?);
+        }
         println!("filepath    {}", file.to_string_lossy());
         println!("min_bound   {:.6} {:.6} {:.6}", c.min_bound.0, c.min_bound.1, c.min_bound.2);
         println!("max_bound   {:.6} {:.6} {:.6}", c.max_bound.0, c.max_bound.1, c.max_bound.2);
         println!("centre      {:.6} {:.6} {:.6}", c.centre().0, c.centre().1, c.centre().2);
         println!("vertices    {}", c.vertices);
+        println!("normals     {}", c.normals);
+        println!("texcoords   {}", c.texture_coords);
         println!("polygons    {}", c.polygons);
         println!("groups      {}", c.groups);
+        println!("mtllibs     {}", c.material_libraries);
+        println!("usemtls     {}", c.materials);
     }
     Ok(())
 }
 
-
+/// Counts the triangles read from an stl or ply file as an obj file
+/// would have them, where every triangle has its own three vertices.
+fn count_triangles(c: &mut Counter, triangles: &[Object]) {
+    for tri in triangles {
+        let (p1, p2, p3) = match tri.shape() {
+            Shape::Triangle { p1, p2, p3, .. } => (p1, p2, p3),
+            Shape::SmoothTri { p1, p2, p3, .. } => {
+                c.normals += 3;
+                (p1, p2, p3)
+            },
+            _ => continue,
+        };
+        for p in [p1, p2, p3].iter() {
+            c.handle_vertex(p.x(), p.y(), p.z()).unwrap();
+        }
+        c.polygons += 1;
+    }
+}
@@ -1,8 +1,7 @@
-use std::fs::File;
+use std::path::Path;
 
 use exitfailure::ExitFailure;
 
-use bucktracer::wavefront;
 use bucktracer::*;
 use bucktracer::math::*;
 
@@ -11,14 +10,21 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "viewobj",
-    about = "Shows the model represented by a wavefront obj file",
+    about = "Shows the model represented by a wavefront obj, stl or ply file",
     rename_all = "kebab-case",
 )]
 struct CmdOptions {
 
-    /// Binary space partitioning depth.
-    #[structopt(long="depth", default_value="2")]
-    depth: usize,
+    /// Binary space partitioning depth.  Without it, the objects are
+    /// left for the world to arrange into a bounding volume hierarchy,
+    /// which needs no depth.
+    #[structopt(long="depth")]
+    depth: Option<usize>,
+
+    /// Load the file into a single mesh, which takes far less memory
+    /// for a large model.  Groups and materials in the file are ignored.
+    #[structopt(long="mesh")]
+    mesh: bool,
 
     /// The position of the camera.
     #[structopt(long="from", default_value="(0.0, 0.0, -1.0)", parse(try_from_str))]
@@ -36,7 +42,26 @@ struct CmdOptions {
     #[structopt(long="light-pos", default_value="(10.0, 10.0, -10.0)", parse(try_from_str))]
     light_pos: Tuple4,
 
-    /// The position of the light source
+    /// The kind of light source: point, spot or directional.
+    #[structopt(long="light-kind", default_value="point")]
+    light_kind: LightChoice,
+
+    /// The direction a spot or directional light shines in.  Defaults to
+    /// pointing from the light's position towards the camera's target.
+    #[structopt(long="light-dir", parse(try_from_str))]
+    light_dir: Option<Tuple4>,
+
+    /// The angle, in degrees, from the axis of a spot light within which
+    /// the light is at full strength.
+    #[structopt(long="spot-inner", default_value="20.0")]
+    spot_inner: f64,
+
+    /// The angle, in degrees, from the axis of a spot light beyond which
+    /// no light reaches.
+    #[structopt(long="spot-outer", default_value="30.0")]
+    spot_outer: f64,
+
+    /// The colour of the light source
     #[structopt(long="light-colour", default_value="(1.0, 1.0, 1.0)", parse(try_from_str))]
     light_colour: Tuple4,
 
@@ -52,28 +77,94 @@ struct CmdOptions {
     #[structopt(short="v", long="vsize", default_value="600")]
     vsize: u32,
 
-    /// The input obj file
+    /// How many rays to fire through each pixel: centre, grid:N, jitter:N or random:N.
+    /// The grid and jitter strategies fire N x N rays.
+    #[structopt(long="samples", default_value="centre")]
+    samples: Sampling,
+
+    /// The number of threads to render with.  Defaults to the number of available cores.
+    #[structopt(long="threads")]
+    threads: Option<usize>,
+
+    /// Brightens the image by this many stops, each doubling the brightness.
+    #[structopt(long="exposure", default_value="0.0")]
+    exposure: f64,
+
+    /// How colours too bright to show are brought into range: clamp,
+    /// reinhard or aces.
+    #[structopt(long="tonemap", default_value="clamp")]
+    tonemap: ToneOperator,
+
+    /// Encode the image with the sRGB transfer curve, as displays expect.
+    #[structopt(long="srgb")]
+    srgb: bool,
+
+    /// The input obj file, or stl or ply file if it has the extension .stl or .ply
     #[structopt(parse(from_os_str))]
     objfile: std::ffi::OsString,
 }
 
+#[derive(Debug)]
+enum LightChoice {
+    Point,
+    Spot,
+    Directional,
+}
+
+impl std::str::FromStr for LightChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "point" => Ok(LightChoice::Point),
+            "spot" => Ok(LightChoice::Spot),
+            "directional" => Ok(LightChoice::Directional),
+            _ => Err(format!("unknown light kind '{}', expected point, spot or directional", s)),
+        }
+    }
+}
+
 fn main() -> Result<(), ExitFailure> {
     let args: CmdOptions = CmdOptions::from_args();
 
-    let mut f = File::open(&args.objfile)?;
-    let objects = wavefront::read_object_vec(&mut f)?;
-
-    let light = point_light(
-        args.light_pos,
-        colour(args.light_colour.x(), args.light_colour.y(), args.light_colour.z())
-    );
-    let world = World::with(vec![light], vec![binary_partition(args.depth, objects)]);
+    let file = Path::new(&args.objfile);
+    let objects = if args.mesh { read_model_mesh(file)? } else { read_model(file)? };
+
+    let intensity = colour(args.light_colour.x(), args.light_colour.y(), args.light_colour.z());
+    let light_dir = args.light_dir
+        .map(|d| vector(d.x(), d.y(), d.z()))
+        .unwrap_or_else(|| args.to - args.light_pos);
+    let light = match args.light_kind {
+        LightChoice::Point => point_light(args.light_pos, intensity),
+        LightChoice::Spot => spot_light(
+            args.light_pos,
+            light_dir,
+            args.spot_inner.to_radians(),
+            args.spot_outer.to_radians(),
+            intensity,
+        ),
+        LightChoice::Directional => directional_light(light_dir, intensity),
+    };
+    let objects = match args.depth {
+        Some(depth) => vec![binary_partition(depth, objects)],
+        None => objects,
+    };
+    let world = World::with(vec![light], objects);
     let mut cam = Camera::new(args.hsize, args.vsize, args.fov_degrees.to_radians());
     cam.orient(args.from, args.to, vector(args.up.x(), args.up.y(), args.up.z()));
+    cam.set_sampling(args.samples);
 
-    let canvas = cam.render(&world, |p, total|{
+    let threads = args.threads.unwrap_or_else(|| {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    });
+    let mut canvas = cam.render_parallel(&world, threads, |p, total|{
         eprintln!("{}/{}", p, total);
     });
+    ToneMapping::default()
+        .set_exposure(args.exposure)
+        .set_operator(args.tonemap)
+        .set_srgb(args.srgb)
+        .apply(&mut canvas);
     let mut stdout = std::io::stdout();
 
     png::encode(&canvas, &mut stdout)?;
@@ -0,0 +1,179 @@
+//! Radiance high dynamic range images, which keep colour values above
+//! 1.0 by storing each pixel as a shared exponent and three 8 bit
+//! mantissas (RGBE).
+use std::io;
+use crate::{canvas, colour, Canvas, RGB};
+
+/// Encode the canvas pixel information to the writer, according to the
+/// Radiance hdr format.  Unlike `png::encode`, nothing is clamped
+/// except negative values, which become 0.  The pixels are written
+/// without run length encoding.
+pub fn encode(c: &Canvas, w: &mut dyn io::Write) -> io::Result<()> {
+    write!(w, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", c.height(), c.width())?;
+    let mut row = Vec::with_capacity(c.width() * 4);
+    for y in 0..c.height() {
+        row.clear();
+        for x in 0..c.width() {
+            row.extend_from_slice(&to_rgbe(c.colour_at(x, y)));
+        }
+        w.write_all(&row)?;
+    }
+    Ok(())
+}
+
+/// Decode a Radiance hdr image from the reader into a canvas, the
+/// reverse of `encode`.  Both flat and run length encoded scanlines
+/// can be read, but only in the usual top to bottom, left to right
+/// order.
+pub fn decode(r: &mut dyn io::Read) -> io::Result<Canvas> {
+    let mut bytes = vec![];
+    r.read_to_end(&mut bytes)?;
+
+    let (width, height, mut pos) = read_header(&bytes)?;
+    let mut canv = canvas(width, height);
+    let mut scanline = vec![[0u8; 4]; width];
+    // the size check doesn't limit the height of an empty image, but
+    // then there are no pixels to read
+    let rows = if width == 0 { 0 } else { height };
+    for y in 0..rows {
+        pos = read_scanline(&bytes, pos, &mut scanline)?;
+        for (x, rgbe) in scanline.iter().enumerate() {
+            canv.set_colour_at(x, y, from_rgbe(*rgbe));
+        }
+    }
+    Ok(canv)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Reads the header lines, returning the size of the image and where
+/// its pixels start.
+fn read_header(bytes: &[u8]) -> io::Result<(usize, usize, usize)> {
+    let mut pos = 0;
+    let mut next_line = || {
+        let end = bytes[pos..].iter().position(|b| *b == b'\n').map(|i| pos + i)
+            .ok_or_else(|| invalid("hdr header is incomplete"))?;
+        let line = String::from_utf8_lossy(&bytes[pos..end]).into_owned();
+        pos = end + 1;
+        Ok::<String, io::Error>(line)
+    };
+
+    if !next_line()?.starts_with("#?") {
+        return Err(invalid("not a Radiance hdr image"));
+    }
+    // the header variables end at a blank line
+    loop {
+        let line = next_line()?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid("only 32-bit_rle_rgbe hdr images are supported"));
+            }
+        }
+    }
+
+    let size = next_line()?;
+    match size.split_whitespace().collect::<Vec<&str>>()[..] {
+        ["-Y", h, "+X", w] => {
+            let h: usize = h.parse().map_err(|_| invalid("bad height in hdr image"))?;
+            let w: usize = w.parse().map_err(|_| invalid("bad width in hdr image"))?;
+            // refuse sizes the pixels that follow couldn't fill, before
+            // the canvas is allocated
+            match min_scanline_len(w).and_then(|n| n.checked_mul(h)) {
+                Some(n) if n <= bytes.len() - pos => Ok((w, h, pos)),
+                Some(_) => Err(invalid("hdr image is truncated")),
+                None => Err(invalid("hdr image is too large")),
+            }
+        },
+        _ => Err(invalid("only top to bottom, left to right hdr images are supported")),
+    }
+}
+
+/// The fewest bytes a scanline of the given width can be encoded in.
+/// Run length encoding takes at least a four byte header and, for
+/// each channel, two bytes per run of up to 128 pixels.
+fn min_scanline_len(width: usize) -> Option<usize> {
+    if is_rle_width(width) {
+        Some(4 + 8 * width.div_ceil(128))
+    } else {
+        width.checked_mul(4)
+    }
+}
+
+fn is_rle_width(width: usize) -> bool {
+    (8..0x8000).contains(&width)
+}
+
+/// Reads one scanline starting at `pos`, returning where the next one
+/// starts.
+fn read_scanline(bytes: &[u8], mut pos: usize, scanline: &mut [[u8; 4]]) -> io::Result<usize> {
+    let truncated = || invalid("hdr image is truncated");
+    let width = scanline.len();
+    let header = bytes.get(pos..pos + 4).ok_or_else(truncated)?;
+    let is_rle = header[0] == 2 && header[1] == 2 && (header[2] & 0x80) == 0
+        && is_rle_width(width);
+
+    if !is_rle {
+        for px in scanline.iter_mut() {
+            px.copy_from_slice(bytes.get(pos..pos + 4).ok_or_else(truncated)?);
+            pos += 4;
+        }
+        return Ok(pos);
+    }
+
+    if ((header[2] as usize) << 8 | header[3] as usize) != width {
+        return Err(invalid("hdr scanline has the wrong width"));
+    }
+    pos += 4;
+    // each of the four channels is run length encoded in turn
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *bytes.get(pos).ok_or_else(truncated)? as usize;
+            pos += 1;
+            let (run, count) = if count > 128 { (true, count - 128) } else { (false, count) };
+            if count == 0 || x + count > width {
+                return Err(invalid("hdr scanline has a bad run length"));
+            }
+            for px in scanline[x..x + count].iter_mut() {
+                px[channel] = *bytes.get(pos).ok_or_else(truncated)?;
+                if !run {
+                    pos += 1;
+                }
+            }
+            if run {
+                pos += 1;
+            }
+            x += count;
+        }
+    }
+    Ok(pos)
+}
+
+fn to_rgbe(c: RGB) -> [u8; 4] {
+    let (r, g, b) = (c.red().max(0.0), c.green().max(0.0), c.blue().max(0.0));
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // v = m * 2^e with m in [0.5, 1)
+    let mut e = v.log2().floor() as i32 + 1;
+    if v / 2f64.powi(e) >= 1.0 {
+        e += 1;
+    }
+    let scale = 256.0 / 2f64.powi(e);
+    [(r * scale) as u8, (g * scale) as u8, (b * scale) as u8, (e + 128) as u8]
+}
+
+fn from_rgbe([r, g, b, e]: [u8; 4]) -> RGB {
+    if e == 0 {
+        return colour(0.0, 0.0, 0.0);
+    }
+    // values are taken from the middle of the range each byte covers
+    let f = 2f64.powi(e as i32 - (128 + 8));
+    colour((r as f64 + 0.5) * f, (g as f64 + 0.5) * f, (b as f64 + 0.5) * f)
+}
@@ -1,22 +1,47 @@
 use std::cmp::Ordering;
+use std::fmt;
 use std::ops::{Add, Mul};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
+use std::sync::mpsc;
+use std::sync::OnceLock;
+use std::thread;
 use std::vec;
 #[macro_use]
 extern crate lazy_static;
 
 use serde::Deserialize;
 
+pub mod hdr;
 pub mod math;
+pub mod pfm;
+pub mod ply;
 pub mod png;
 pub mod ppm;
+pub mod rng;
+pub mod scene;
+pub mod stl;
 mod shape;
+mod light;
+mod texture;
+mod tonemap;
+mod model;
 pub mod wavefront;
 mod partition;
+mod accel;
 
 use crate::math::*;
+use crate::rng::Rng;
+use crate::accel::Accel;
 pub use crate::shape::*;
+pub use crate::light::*;
+pub use crate::texture::*;
+pub use crate::tonemap::*;
+pub use crate::model::*;
 
 pub use partition::binary_partition;
+pub use partition::bvh_partition;
+pub use partition::traversal_cost;
 pub use partition::flatten;
 // TODO for testing.  Remove once debugged
 pub use partition::bbox_map;
@@ -99,7 +124,7 @@ impl Mul<f64> for RGB {
 /// A structure used to record pixel colour values
 /// indexed by 2D coordinates.  (0,0) represents the
 /// top-left pixel of the canvas.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Canvas {
     width: usize,
     height: usize,
@@ -161,32 +186,129 @@ impl Ray {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Intersection {
+/// Records where along a ray an object was hit.
+///
+/// The intersection borrows the object that was hit, rather than
+/// copying it, so that the many candidate intersections found while
+/// tracing a ray are cheap to make.  Two intersections refer to the
+/// same object only if they borrow the very same `Object`; see
+/// `same_object`.
+#[derive(Debug, Clone)]
+pub struct Intersection<'a> {
     t_value: f64,
-    intersected: Object,
+    intersected: &'a Object,
     to_group_spc: Matrix,
+    /// The transform into the space of the groups the world compiled
+    /// away, if the object was in any, which is applied before
+    /// `to_group_spc`.  It is borrowed from the world, so that hits on
+    /// grouped objects needn't each compose their own.
+    flattened_spc: Option<&'a Matrix>,
     uv: Option<(f64, f64)>,
+    /// The index of the face hit, for shapes made of many faces.
+    face: Option<usize>,
+    /// The outermost CSG object containing the object hit, if any.
+    solid: Option<&'a Object>,
+    /// The outermost instance containing the solid hit, if any.  Copies
+    /// of one prototype placed by different instances are different
+    /// solids, though they are made of the same objects.
+    placement: Option<&'a Object>,
+    /// True if the object hit was placed by an instance, whether or not
+    /// it is still the placement, as within a CSG object.
+    instanced: bool,
+    /// The material of an instance that replaces the materials of the
+    /// objects within it, if any.
+    material: Option<&'a Material>,
+    /// The material of the solid behind the surface, where that isn't
+    /// the material of the surface hit.  See `medium`.
+    medium: Option<&'a Material>,
 }
 
-impl Intersection {
+impl<'a> Intersection<'a> {
     pub fn t_value(&self) -> f64 {
         self.t_value
     }
 
+    /// A copy of the object that was hit.  Prefer `object` unless an
+    /// owned copy is needed.
     pub fn intersected(&self) -> Object {
         self.intersected.clone()
     }
 
+    /// The object that was hit.
+    pub fn object(&self) -> &'a Object {
+        self.intersected
+    }
+
+    /// True if both intersections are with the very same object, as
+    /// opposed to two objects that happen to be alike.
+    pub fn same_object(&self, other: &Intersection) -> bool {
+        std::ptr::eq(self.intersected, other.intersected)
+    }
+
     pub fn normal_at(&self, world_point: Tuple4) -> Tuple4 {
         // TODO can we opt out of the matrix multiplications?
-        let p = self.to_group_spc.mult(world_point);
+        let to_group_spc = self.to_group_space();
+        let p = to_group_spc.mult(world_point);
         let tmp = self.intersected.normal_at(p, self);
-        self.to_group_spc.inverse().mult(tmp)
+        // normals go back through the transpose, as in Object::normal_at
+        let n = to_group_spc.transpose().mult(tmp);
+        vector(n.x(), n.y(), n.z()).normalize()
+    }
+
+    /// The object the ray enters or leaves at this intersection, when
+    /// working out which objects the ray is inside, with the instance
+    /// that placed it.  It is the object hit, unless that is part of a
+    /// CSG object, when it is the whole CSG object.
+    fn container(&self) -> (&'a Object, Option<&'a Object>) {
+        (self.solid.unwrap_or(self.intersected), self.placement)
+    }
+
+    fn set_solid(&mut self, solid: &'a Object) -> &mut Self {
+        self.solid = Some(solid);
+        // any instances within it are part of the one solid
+        self.placement = None;
+        self
+    }
+
+    fn set_placement(&mut self, inst: &'a Object) -> &mut Self {
+        self.placement = Some(inst);
+        self.instanced = true;
+        self
+    }
+
+    /// The material of the surface hit.  It is the object's own
+    /// material, unless an instance containing the object replaces it.
+    pub fn material(&self) -> Material {
+        *self.material_ref()
+    }
+
+    /// The material of the surface hit, without copying it.
+    fn material_ref(&self) -> &'a Material {
+        self.material.unwrap_or_else(|| self.intersected.material_ref())
+    }
+
+    fn set_material_override(&mut self, m: &'a Material) -> &mut Self {
+        self.material = Some(m);
+        self
+    }
+
+    /// The material of the solid the ray is in on entering it here.  It
+    /// is the material of the surface hit, except where a CSG difference
+    /// cuts into its left operand, when it is the left operand's.
+    fn medium(&self) -> &'a Material {
+        self.medium.unwrap_or_else(|| self.material_ref())
+    }
+
+    fn set_medium(&mut self, m: &'a Material) -> &mut Self {
+        self.medium = Some(m);
+        self
     }
 
     fn to_group_space(&self) -> Matrix {
-        self.to_group_spc
+        match self.flattened_spc {
+            None => self.to_group_spc,
+            Some(m) => self.to_group_spc * *m,
+        }
     }
 
     fn set_to_group_space(&mut self, matr: Matrix) -> &mut Self {
@@ -194,6 +316,16 @@ impl Intersection {
         self
     }
 
+    fn set_flattened_space(&mut self, matr: &'a Matrix) -> &mut Self {
+        self.flattened_spc = Some(matr);
+        self
+    }
+
+    /// The index of the face hit, where the object is a mesh.
+    pub fn face(&self) -> Option<usize> {
+        self.face
+    }
+
     // TODO we want this to be private, it only has relevance for smooth triangles.
     pub fn u(&self) -> Option<f64> {
         match self.uv {
@@ -211,21 +343,69 @@ impl Intersection {
     }
 }
 
-pub fn intersection(t: f64, s: &Object) -> Intersection {
+impl PartialEq for Intersection<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        // the transform into group space may be split differently
+        self.t_value == other.t_value
+            && self.intersected == other.intersected
+            && self.to_group_space() == other.to_group_space()
+            && self.uv == other.uv
+            && self.face == other.face
+            && self.solid == other.solid
+            && self.placement == other.placement
+            && self.instanced == other.instanced
+            && self.material == other.material
+            && self.medium == other.medium
+    }
+}
+
+pub fn intersection(t: f64, s: &Object) -> Intersection<'_> {
     Intersection {
         t_value: t,
-        intersected: s.clone(),
+        intersected: s,
         to_group_spc: identity(),
+        flattened_spc: None,
         uv: None,
+        face: None,
+        solid: None,
+        placement: None,
+        instanced: false,
+        material: None,
+        medium: None,
+    }
+}
+
+pub fn intersection_with_uv(t: f64, s: &Object, u: f64, v: f64) -> Intersection<'_> {
+    Intersection {
+        t_value: t,
+        intersected: s,
+        to_group_spc: identity(),
+        flattened_spc: None,
+        uv: Some((u, v)),
+        face: None,
+        solid: None,
+        placement: None,
+        instanced: false,
+        material: None,
+        medium: None,
     }
 }
 
-pub fn intersection_with_uv(t: f64, s: &Object, u: f64, v: f64) -> Intersection {
+/// An intersection with one face of a mesh, at barycentric
+/// co-ordinates (u, v) on the face.
+pub fn intersection_with_face(t: f64, s: &Object, face: usize, u: f64, v: f64) -> Intersection<'_> {
     Intersection {
         t_value: t,
-        intersected: s.clone(),
+        intersected: s,
         to_group_spc: identity(),
+        flattened_spc: None,
         uv: Some((u, v)),
+        face: Some(face),
+        solid: None,
+        placement: None,
+        instanced: false,
+        material: None,
+        medium: None,
     }
 }
 
@@ -238,10 +418,10 @@ pub fn index_of_hit(intersects: &[Intersection]) -> Option<usize> {
         .map(|(ind, _)| ind)
 }
 
-fn nearer_intersect<'a>(
-    nearest: Option<(usize, &'a Intersection)>,
-    x: (usize, &'a Intersection),
-) -> Option<(usize, &'a Intersection)> {
+fn nearer_intersect<'a, 'b>(
+    nearest: Option<(usize, &'a Intersection<'b>)>,
+    x: (usize, &'a Intersection<'b>),
+) -> Option<(usize, &'a Intersection<'b>)> {
     match nearest {
         None => Some(x),
         Some((_, c)) => {
@@ -254,30 +434,6 @@ fn nearer_intersect<'a>(
     }
 }
 
-/// A point that emits light in all directions.
-#[derive(Debug, Copy, Clone)]
-pub struct RadialLightSource {
-    position: Tuple4,
-    intensity: RGB,
-}
-
-pub fn point_light(p: Tuple4, i: RGB) -> RadialLightSource {
-    RadialLightSource {
-        position: p,
-        intensity: i,
-    }
-}
-
-impl RadialLightSource {
-    pub fn position(self: &Self) -> Tuple4 {
-        self.position
-    }
-
-    pub fn intensity(self: &Self) -> RGB {
-        self.intensity
-    }
-}
-
 pub fn reflect(v: Tuple4, norm: Tuple4) -> Tuple4 {
     v - norm.scale(2.0).scale(v.dot(norm))
 }
@@ -390,15 +546,28 @@ impl Material {
 }
 
 pub fn lighting(
-    light: &RadialLightSource,
+    light: &LightSource,
     pos: Tuple4,
     normalv: Tuple4,
     obj: &Object,
     eyev: Tuple4,
     light_allowance: f64,
 ) -> RGB {
-    let mat = obj.material();
-    let matrl_colr: Tuple4 = obj.material_colour_at(pos).into();
+    let matrl_colr = obj.material_colour_at(pos);
+    lighting_with_colour(light, pos, normalv, obj.material_ref(), matrl_colr, eyev, light_allowance)
+}
+
+/// Lighting, for a surface whose colour at `pos` is already known.
+fn lighting_with_colour(
+    light: &LightSource,
+    pos: Tuple4,
+    normalv: Tuple4,
+    mat: &Material,
+    matrl_colr: RGB,
+    eyev: Tuple4,
+    light_allowance: f64,
+) -> RGB {
+    let matrl_colr: Tuple4 = matrl_colr.into();
     let light_intens: Tuple4 = light.intensity().into();
     let effective_colour: Tuple4 = matrl_colr.mult_pairwise(light_intens);
     let ambient = effective_colour.scale(mat.ambient());
@@ -407,28 +576,36 @@ pub fn lighting(
         return RGB::from(ambient);
     }
 
-    let lightv = (light.position() - pos).normalize();
-    let light_dot_normal = lightv.dot(normalv);
-
+    // Area lights are shaded by averaging the light arriving from
+    // several points on the light's surface.
+    let samples = light.sample_points(pos);
     let black: Tuple4 = colour(0.0, 0.0, 0.0).into();
-    let (diffuse, specular) = if light_dot_normal < 0.0 {
-        // the light is behind the surface
-        (black, black)
-    } else {
-        let d = effective_colour.scale(mat.diffuse() * light_dot_normal);
-        let reflectv = reflect(-lightv, normalv);
-        let reflect_dot_eye = reflectv.dot(eyev);
-
-        if reflect_dot_eye <= 0.0 {
-            (d, black)
+    let mut total = black;
+    for sample in samples.iter() {
+        let (lightv, distance) = light.towards(pos, *sample);
+        let light_dot_normal = lightv.dot(normalv);
+        let falloff = light.cone_factor(lightv) * light.attenuation().factor(distance);
+
+        let (diffuse, specular) = if light_dot_normal < 0.0 || falloff == 0.0 {
+            // the light is behind the surface
+            (black, black)
         } else {
-            let factor = reflect_dot_eye.powf(mat.shininess());
-            let s = light_intens.scale(mat.specular() * factor);
-            (d, s)
-        }
-    };
+            let d = effective_colour.scale(mat.diffuse() * light_dot_normal);
+            let reflectv = reflect(-lightv, normalv);
+            let reflect_dot_eye = reflectv.dot(eyev);
+
+            if reflect_dot_eye <= 0.0 {
+                (d, black)
+            } else {
+                let factor = reflect_dot_eye.powf(mat.shininess());
+                let s = light_intens.scale(mat.specular() * factor);
+                (d, s)
+            }
+        };
+        total = total + (diffuse + specular).scale(falloff);
+    }
 
-    let l: Tuple4 = (diffuse + specular).scale(light_allowance);
+    let l: Tuple4 = total.scale(light_allowance / samples.len() as f64);
     RGB::from(ambient + l)
 }
 
@@ -436,8 +613,10 @@ pub fn lighting(
 /// objects
 #[derive(Debug)]
 pub struct World {
-    lights: Vec<RadialLightSource>,
+    lights: Vec<LightSource>,
     objects: Vec<Object>,
+    /// Built from the objects when the first ray is traced.
+    accel: OnceLock<Accel>,
 }
 
 impl World {
@@ -445,6 +624,7 @@ impl World {
         World {
             objects: vec![],
             lights: vec![],
+            accel: OnceLock::new(),
         }
     }
 
@@ -464,14 +644,15 @@ impl World {
         World {
             objects: vec![outer, inner],
             lights: vec![light],
+            accel: OnceLock::new(),
         }
     }
 
-    pub fn with(lights: Vec<RadialLightSource>, objects: Vec<Object>) -> World {
-        World { objects, lights }
+    pub fn with(lights: Vec<LightSource>, objects: Vec<Object>) -> World {
+        World { objects, lights, accel: OnceLock::new() }
     }
 
-    pub fn light_sources(self: &Self) -> &[RadialLightSource] {
+    pub fn light_sources(self: &Self) -> &[LightSource] {
         &self.lights
     }
 
@@ -479,11 +660,22 @@ impl World {
         &self.objects
     }
 
-    pub fn intersect(self: &Self, r: &Ray) -> Vec<Intersection> {
-        let mut v: Vec<Intersection> = Vec::with_capacity(2 * self.objects.len());
-        for obj in self.objects.iter() {
-            append_intersects(r, obj, &mut v);
-        }
+    /// Prepares the world for tracing rays, by compiling its objects
+    /// into a structure that speeds up finding intersections.  This
+    /// happens anyway when the first ray is traced; calling it
+    /// beforehand keeps the cost out of the rendering.  The objects of
+    /// the world must not be changed afterwards.
+    pub fn prepare(&self) {
+        self.accel();
+    }
+
+    fn accel(&self) -> &Accel {
+        self.accel.get_or_init(|| Accel::build(&self.objects))
+    }
+
+    pub fn intersect(self: &Self, r: &Ray) -> Vec<Intersection<'_>> {
+        let mut v: Vec<Intersection> = Vec::with_capacity(8);
+        self.accel().intersect(&self.objects, r, &mut v);
 
         v.sort_by(|i1, i2| {
             let t1 = i1.t_value;
@@ -513,12 +705,12 @@ impl World {
         if rlimit == 0 {
             return RGB::black();
         }
-        if comps.object.material().reflective() == 0.0 {
+        if comps.material.reflective() == 0.0 {
             RGB::black()
         } else {
             let reflected_ray = ray(comps.over_point, comps.reflectv);
             let c = self.colour_at_intersect(&reflected_ray, rlimit - 1);
-            c * comps.object.material().reflective()
+            c * comps.material.reflective()
         }
     }
 
@@ -526,7 +718,7 @@ impl World {
         if rlimit == 0 {
             return RGB::black();
         }
-        if comps.object.material().transparency() == 0.0 {
+        if comps.material.transparency() == 0.0 {
             RGB::black()
         } else {
             let ratio = comps.n1 / comps.n2;
@@ -551,42 +743,70 @@ impl World {
                     comps.normalv.scale((ratio * cos_i) - cos_t) - (comps.eyev.scale(ratio));
                 let refract_ray = ray(comps.under_point, direction);
                 let c = self.colour_at_intersect(&refract_ray, rlimit - 1);
-                c * comps.object.material().transparency()
+                c * comps.material.transparency()
             }
         }
     }
 
-    pub fn light_factor(&self, point: Tuple4, light: &RadialLightSource) -> f64 {
-        let point_to_light = light.position() - point;
-        let mag = point_to_light.magnitude();
-        let r = ray(point, point_to_light.normalize());
+    /// The fraction of the light's intensity that reaches the point.
+    ///
+    /// 1.0 means the point is fully lit, 0.0 means it is in full shadow.
+    /// Transparent objects between the point and the light let some
+    /// of the light through.  For area lights, the result is the average
+    /// over each of the light's sample points, so points in a penumbra
+    /// receive a value between 0.0 and 1.0.
+    pub fn light_factor(&self, point: Tuple4, light: &LightSource) -> f64 {
+        let samples = light.sample_points(point);
+        let total: f64 = samples.iter()
+            .map(|s| {
+                let (lightv, distance) = light.towards(point, *s);
+                self.transmittance(point, lightv, distance)
+            })
+            .sum();
+        total / samples.len() as f64
+    }
+
+    fn transmittance(&self, point: Tuple4, lightv: Tuple4, mag: f64) -> f64 {
+        let r = ray(point, lightv);
 
         let accumulatd: f64 = self
             .intersect(&r)
             .iter()
             .filter(|i| i.t_value >= 0.0 && i.t_value < mag)
-            .map(|h| h.intersected.material().transparency())
+            .map(|h| h.material().transparency())
             .fold(1.0, |x, y| x * y);
         accumulatd
     }
 }
 
 #[derive(Debug)]
-struct HitCalculations {
+struct HitCalculations<'a> {
     t_value: f64,
-    object: Object,
+    object: &'a Object,
+    /// The material of the surface hit, which may not be the object's
+    /// own; see `Intersection::material`.
+    material: &'a Material,
     point: Tuple4,
     eyev: Tuple4,
     normalv: Tuple4,
     inside: bool,
     over_point: Tuple4,
     under_point: Tuple4,
+    /// The over point in the space the object's transform is relative
+    /// to, where an instance placed the object, so that its patterns
+    /// move with the instance.  Otherwise it is the over point, as
+    /// patterns on grouped objects are found in world space.
+    parent_over_point: Tuple4,
     reflectv: Tuple4,
     n1: f64,
     n2: f64,
+    /// The texture co-ordinates of the point hit, if the object has any.
+    uv: Option<(f64, f64)>,
+    /// Where on the triangle the point hit is, if it is a triangle.
+    barycentric: Option<(f64, f64)>,
 }
 
-fn hit_data(r: &Ray, hit_index: usize, intersects: &[Intersection]) -> HitCalculations {
+fn hit_data<'a>(r: &Ray, hit_index: usize, intersects: &[Intersection<'a>]) -> HitCalculations<'a> {
     let hit: &Intersection = &intersects[hit_index];
     let pos = r.position(hit.t_value());
 
@@ -598,45 +818,57 @@ fn hit_data(r: &Ray, hit_index: usize, intersects: &[Intersection]) -> HitCalcul
     let norm = if is_inside { -n } else { n };
     let r = reflect(r.direction, norm);
     let (n1, n2) = refractive_indices(hit_index, intersects);
+    let over_point = pos + (norm.scale(1e-5));
 
     HitCalculations {
         t_value: hit.t_value(),
-        object: hit.intersected(),
+        object: hit.object(),
+        material: hit.material_ref(),
         point: pos,
         eyev: e,
         normalv: norm,
         inside: is_inside,
-        over_point: pos + (norm.scale(1e-5)),
+        over_point,
         under_point: pos - (norm.scale(1e-5)),
+        parent_over_point: if hit.instanced { hit.to_group_space().mult(over_point) } else { over_point },
         reflectv: r,
         n1,
         n2,
+        uv: hit.object().texture_coords_at(hit),
+        barycentric: hit.u().zip(hit.v()).filter(|_| is_triangular(hit.object())),
     }
 }
 
+/// True if the u and v of a hit on the object are barycentric
+/// co-ordinates, rather than texture co-ordinates as on a torus or disc.
+fn is_triangular(obj: &Object) -> bool {
+    matches!(obj.shape(), Shape::Triangle { .. } | Shape::SmoothTri { .. } | Shape::Mesh(_))
+}
+
 fn refractive_indices(hit_index: usize, intersects: &[Intersection]) -> (f64, f64) {
-    let mut containers: Vec<&Object> = Vec::with_capacity(intersects.len());
+    // the objects the ray is inside, each with the refractive index of
+    // the surface it entered through
+    let mut containers: Vec<((&Object, Option<&Object>), f64)> = Vec::with_capacity(intersects.len());
     let mut n1 = 1.0;
     let mut n2 = 1.0;
     for (i, current) in intersects.iter().enumerate() {
         if i == hit_index {
-            if !containers.is_empty() {
-                n1 = containers.last().unwrap().material().refractive_index();
+            if let Some((_, n)) = containers.last() {
+                n1 = *n;
             }
         }
-        let object: &Object = &current.intersected;
+        let container = current.container();
 
-        match find(&containers, object) {
-            Some(obj_index) => containers.remove(obj_index),
-            None => {
-                containers.push(object);
-                object
-            }
+        match find(&containers, container) {
+            Some(obj_index) => {
+                containers.remove(obj_index);
+            },
+            None => containers.push((container, current.medium().refractive_index())),
         };
 
         if i == hit_index {
-            if !containers.is_empty() {
-                n2 = containers.last().unwrap().material().refractive_index();
+            if let Some((_, n)) = containers.last() {
+                n2 = *n;
             }
             break;
         }
@@ -645,9 +877,17 @@ fn refractive_indices(hit_index: usize, intersects: &[Intersection]) -> (f64, f6
     (n1, n2)
 }
 
-fn find(objects: &[&Object], obj: &Object) -> Option<usize> {
-    for (i, item) in objects.iter().enumerate() {
-        if *item == obj {
+/// Finds the position of the object, placed by the instance, in the
+/// list.  Objects are compared by identity; two distinct objects that
+/// happen to be equal are not the same container.
+fn find(containers: &[((&Object, Option<&Object>), f64)], (obj, placement): (&Object, Option<&Object>)) -> Option<usize> {
+    let same = |a: Option<&Object>, b: Option<&Object>| match (a, b) {
+        (Some(a), Some(b)) => std::ptr::eq(a, b),
+        (None, None) => true,
+        _ => false,
+    };
+    for (i, ((item, item_placement), _)) in containers.iter().enumerate() {
+        if std::ptr::eq(*item, obj) && same(*item_placement, placement) {
             return Some(i);
         }
     }
@@ -655,20 +895,23 @@ fn find(objects: &[&Object], obj: &Object) -> Option<usize> {
 }
 
 fn shade_hit(world: &World, comps: &HitCalculations, rlimit: u32) -> RGB {
+    let matrl_colr = comps.object.colour_of_material_at(
+        comps.material, comps.parent_over_point, comps.uv, comps.barycentric);
     world.lights.iter().fold(RGB::black(), |prev_colour, light| {
-        let surface = lighting(
+        let surface = lighting_with_colour(
             light,
             comps.over_point,
             comps.normalv,
-            &comps.object,
+            comps.material,
+            matrl_colr,
             comps.eyev,
             world.light_factor(comps.over_point, light),
         );
         let reflected = world.reflected_colour(&comps, rlimit);
         let refracted = world.refracted_colour(&comps, rlimit);
 
-        let c = if comps.object.material().reflective() > 0.0
-            && comps.object.material().transparency() > 0.0
+        let c = if comps.material.reflective() > 0.0
+            && comps.material.transparency() > 0.0
         {
             let reflectance = schlick(comps);
             surface + (reflected * reflectance) + (refracted * (1.0 - reflectance))
@@ -722,10 +965,90 @@ pub struct Camera {
     half_height: f64,
     pixel_size: f64,
     inverse_view_t: Matrix,
+    sampling: Sampling,
+    seed: u64,
 }
 
 const RECURSION_LIMIT: u32 = 5;
 
+/// Dictates where, and how many, rays are fired through each pixel.
+///
+/// Firing several rays through different parts of a pixel and
+/// averaging the colours they return smooths out jagged edges
+/// (anti-aliasing).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Sampling {
+    /// One ray through the centre of the pixel.
+    Centre,
+    /// n x n rays arranged in a regular grid over the pixel.
+    Grid(u32),
+    /// n x n rays, one placed randomly within each cell of a regular grid.
+    Jittered(u32),
+    /// n rays placed randomly anywhere in the pixel.
+    Random(u32),
+}
+
+impl Sampling {
+    /// Calculates the positions within a pixel to fire rays through.
+    /// Each offset is in the range [0, 1) in both directions, where
+    /// (0, 0) is the top left corner of the pixel.
+    pub fn offsets(&self, rng: &mut Rng) -> Vec<(f64, f64)> {
+        match *self {
+            Sampling::Centre => vec![(0.5, 0.5)],
+            Sampling::Grid(n) => grid_offsets(n, |_| 0.5),
+            Sampling::Jittered(n) => grid_offsets(n, |_| rng.next_f64()),
+            Sampling::Random(n) => (0..n.max(1)).map(|_| (rng.next_f64(), rng.next_f64())).collect(),
+        }
+    }
+}
+
+fn grid_offsets<F>(n: u32, mut within_cell: F) -> Vec<(f64, f64)>
+    where F: FnMut(u32) -> f64
+{
+    let n = n.max(1);
+    let cell = 1.0 / f64::from(n);
+    let mut v = Vec::with_capacity((n * n) as usize);
+    for j in 0..n {
+        for i in 0..n {
+            let dx = (f64::from(i) + within_cell(i)) * cell;
+            let dy = (f64::from(j) + within_cell(j)) * cell;
+            v.push((dx, dy));
+        }
+    }
+    v
+}
+
+#[derive(Debug)]
+pub struct BadSampling(String);
+
+impl fmt::Display for BadSampling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bad sampling '{}': expected centre, grid:N, jitter:N or random:N", self.0)
+    }
+}
+
+impl FromStr for Sampling {
+    type Err = BadSampling;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || BadSampling(s.to_string());
+        if s == "centre" || s == "1" {
+            return Ok(Sampling::Centre);
+        }
+        let mut parts = s.splitn(2, ':');
+        let kind = parts.next().ok_or_else(bad)?;
+        let n = parts.next().ok_or_else(bad)?.parse::<u32>().map_err(|_| bad())?;
+        if n == 0 {
+            return Err(bad());
+        }
+        match kind {
+            "grid" => Ok(Sampling::Grid(n)),
+            "jitter" => Ok(Sampling::Jittered(n)),
+            "random" => Ok(Sampling::Random(n)),
+            _ => Err(bad()),
+        }
+    }
+}
+
 impl Camera {
     pub fn new(hsize: u32, vsize: u32, fov: f64) -> Camera {
         let half_view = (fov / 2.0).tan();
@@ -751,6 +1074,8 @@ impl Camera {
             half_height,
             pixel_size,
             inverse_view_t: identity(),
+            sampling: Sampling::Centre,
+            seed: 0,
         }
     }
     pub fn hsize(self: &Self) -> u32 {
@@ -779,12 +1104,33 @@ impl Camera {
         self.pixel_size
     }
 
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+    pub fn set_sampling(&mut self, s: Sampling) -> &mut Self {
+        self.sampling = s;
+        self
+    }
+    /// The seed for the random numbers used by jittered and random sampling.
+    /// Rendering twice with the same seed produces the same image.
+    pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = seed;
+        self
+    }
+
     pub fn ray_for_pixel(self: &Self, px: u32, py: u32) -> Ray {
+        self.ray_through_pixel(px, py, 0.5, 0.5)
+    }
+
+    /// Creates a ray passing through a point within a pixel.  The offsets
+    /// dx and dy are in the range [0, 1), measured from the top left
+    /// corner of the pixel.
+    pub fn ray_through_pixel(&self, px: u32, py: u32, dx: f64, dy: f64) -> Ray {
         assert!(px < self.hsize);
         assert!(py < self.vsize);
 
-        let xoffset = (f64::from(px) + 0.5) * self.pixel_size;
-        let yoffset = (f64::from(py) + 0.5) * self.pixel_size;
+        let xoffset = (f64::from(px) + dx) * self.pixel_size;
+        let yoffset = (f64::from(py) + dy) * self.pixel_size;
         let worldx = self.half_width - xoffset;
         let worldy = self.half_height - yoffset;
         let inv_t = self.inverse_view_t();
@@ -800,15 +1146,80 @@ impl Camera {
     {
         let mut canv = canvas(self.hsize as usize, self.vsize as usize);
         for y in 0..self.vsize {
-            for x in 0..self.hsize {
-                let r = self.ray_for_pixel(x, y);
-                let c = w.colour_at_intersect(&r, RECURSION_LIMIT);
-                canv.set_colour_at(x as usize, y as usize, c);
+            for (x, c) in self.render_row(w, y).into_iter().enumerate() {
+                canv.set_colour_at(x, y as usize, c);
             }
             progress(y + 1, self.vsize);
         }
         canv
     }
+
+    /// Renders the world using a number of worker threads.
+    ///
+    /// Rows of the image are handed out to the workers one at a
+    /// time, so a thread that finishes a cheap row moves straight on
+    /// to the next one.  The resulting canvas is identical to the one
+    /// produced by `render`.  The progress callback is invoked on the
+    /// calling thread with the number of rows completed so far.
+    pub fn render_parallel<F>(&self, w: &World, threads: usize, mut progress: F) -> Canvas
+        where F: FnMut(u32, u32)
+    {
+        // build this once, up front, rather than have the first thread
+        // to trace a ray build it while the others wait
+        w.prepare();
+        let mut canv = canvas(self.hsize as usize, self.vsize as usize);
+        let next_row = AtomicU32::new(0);
+        let (sender, receiver) = mpsc::channel::<(u32, Vec<RGB>)>();
+
+        thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                let sender = sender.clone();
+                let next_row = &next_row;
+                scope.spawn(move || loop {
+                    let y = next_row.fetch_add(1, AtomicOrdering::Relaxed);
+                    if y >= self.vsize {
+                        break;
+                    }
+                    if sender.send((y, self.render_row(w, y))).is_err() {
+                        break;
+                    }
+                });
+            }
+            // Only the workers hold senders now, so the receiving loop
+            // ends once every worker has run out of rows.
+            drop(sender);
+
+            let mut completed = 0;
+            for (y, row) in receiver {
+                for (x, c) in row.into_iter().enumerate() {
+                    canv.set_colour_at(x, y as usize, c);
+                }
+                completed += 1;
+                progress(completed, self.vsize);
+            }
+        });
+        canv
+    }
+
+    fn render_row(&self, w: &World, y: u32) -> Vec<RGB> {
+        // Seed each row separately, so the image doesn't depend on
+        // the order in which rows are rendered.
+        let mut rng = Rng::seeded(self.seed ^ u64::from(y).wrapping_mul(0x2545_f491_4f6c_dd1d));
+        (0..self.hsize)
+            .map(|x| self.colour_of_pixel(w, x, y, &mut rng))
+            .collect()
+    }
+
+    /// Determines the colour of a pixel by averaging the colours seen
+    /// by each of the rays the sampling strategy fires through it.
+    pub fn colour_of_pixel(&self, w: &World, px: u32, py: u32, rng: &mut Rng) -> RGB {
+        let offsets = self.sampling.offsets(rng);
+        let total = offsets.iter().fold(RGB::black(), |acc, (dx, dy)| {
+            let r = self.ray_through_pixel(px, py, *dx, *dy);
+            acc + w.colour_at_intersect(&r, RECURSION_LIMIT)
+        });
+        total * (1.0 / offsets.len() as f64)
+    }
 }
 
 /// Describes how to colour the surface of an object.
@@ -820,6 +1231,12 @@ pub enum Pattern {
     Ring { a: RGB, b: RGB },
     Checkers { a: RGB, b: RGB },
     Test,
+    /// Colours the surface from an image, mapped onto the surface by `mapping`.
+    #[serde(skip)]
+    Image { texture: Texture, mapping: UvMapping },
+    /// Blends a colour for each corner of a triangle, weighted by how
+    /// near the point is to each corner.
+    VertexColours([RGB; 3]),
 }
 
 impl Pattern {
@@ -841,6 +1258,12 @@ impl Pattern {
     pub fn tester() -> Pattern {
         Pattern::Test
     }
+    pub fn image(texture: Texture, mapping: UvMapping) -> Pattern {
+        Pattern::Image { texture, mapping }
+    }
+    pub fn vertex_colours(c1: RGB, c2: RGB, c3: RGB) -> Pattern {
+        Pattern::VertexColours([c1, c2, c3])
+    }
 
     pub fn colour_at(self: &Self, pattern_space_pos: Tuple4) -> RGB {
         match *self {
@@ -850,10 +1273,42 @@ impl Pattern {
             Pattern::Ring { a, b } => ring_colour(a, b, pattern_space_pos),
             Pattern::Checkers { a, b } => checkers_colour(a, b, pattern_space_pos),
             Pattern::Test => no_op_colour(pattern_space_pos),
+            Pattern::Image { texture, mapping } => {
+                let (u, v) = mapping.map(pattern_space_pos);
+                texture.sample(u, v)
+            },
+            // without knowing where on the triangle the point is, the
+            // best we can do is the colour at its centre
+            Pattern::VertexColours(cs) => blend_corners(cs, 1.0 / 3.0, 1.0 / 3.0),
+        }
+    }
+
+    /// Like `colour_at`, but an image using `UvMapping::Vertices` is
+    /// sampled at the given texture co-ordinates, when there are some.
+    pub fn colour_at_uv(&self, pattern_space_pos: Tuple4, uv: Option<(f64, f64)>) -> RGB {
+        self.colour_at_surface(pattern_space_pos, uv, None)
+    }
+
+    /// Like `colour_at_uv`, but vertex colours are also blended by the
+    /// barycentric co-ordinates of the point on the triangle hit, when
+    /// there are some.
+    pub fn colour_at_surface(&self, pattern_space_pos: Tuple4, uv: Option<(f64, f64)>, barycentric: Option<(f64, f64)>) -> RGB {
+        match (*self, uv, barycentric) {
+            (Pattern::Image { texture, mapping: UvMapping::Vertices }, Some((u, v)), _) => {
+                texture.sample(u, v)
+            },
+            (Pattern::VertexColours(cs), _, Some((u, v))) => blend_corners(cs, u, v),
+            _ => self.colour_at(pattern_space_pos),
         }
     }
 }
 
+/// The colours of the corners weighted as `intersect_triangle` weights
+/// the second and third corners by `u` and `v`.
+fn blend_corners(cs: [RGB; 3], u: f64, v: f64) -> RGB {
+    RGB::from(cs[0].inner.scale(1.0 - u - v) + cs[1].inner.scale(u) + cs[2].inner.scale(v))
+}
+
 fn no_op_colour(pattern_space_pos: Tuple4) -> RGB {
     RGB::from(pattern_space_pos)
 }
@@ -902,7 +1357,7 @@ fn checkers_colour(a: RGB, b: RGB, p: Tuple4) -> RGB {
 
 // A helper for the following test modules
 #[cfg(test)]
-fn singleton_hit_data(r: &Ray, hit: &Intersection) -> HitCalculations {
+fn singleton_hit_data<'a>(r: &Ray, hit: &Intersection<'a>) -> HitCalculations<'a> {
     hit_data(r, 0, &[hit.clone()])
 }
 
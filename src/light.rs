@@ -0,0 +1,231 @@
+use crate::*;
+use crate::rng::Rng;
+
+/// Dictates the shape of a light source, and how light leaves it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LightKind {
+    /// A point that emits light in all directions.
+    Point { position: Tuple4 },
+    /// A rectangle, made of a grid of usteps x vsteps cells, emitting light
+    /// in all directions.  A surface point partially hidden from the
+    /// rectangle lies in a penumbra, giving soft edged shadows.
+    Area { corner: Tuple4, uvec: Tuple4, usteps: u32, vvec: Tuple4, vsteps: u32 },
+    /// A light so distant that its rays are parallel, travelling in
+    /// `direction`, such as the sun.
+    Directional { direction: Tuple4 },
+    /// A point that emits a cone of light along `direction`.  Points
+    /// within `inner` radians of the cone's axis are fully lit, points
+    /// beyond `outer` radians receive none of the light, and the light
+    /// fades smoothly in between.
+    Spot { position: Tuple4, direction: Tuple4, inner: f64, outer: f64 },
+}
+
+/// How a light's intensity falls away with distance.  At a distance `d`
+/// from the light the intensity is divided by
+/// `constant + linear * d + quadratic * d * d`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Attenuation {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+impl Attenuation {
+    pub fn new(constant: f64, linear: f64, quadratic: f64) -> Attenuation {
+        Attenuation { constant, linear, quadratic }
+    }
+
+    /// Light that doesn't diminish with distance.
+    pub fn none() -> Attenuation {
+        Attenuation::new(1.0, 0.0, 0.0)
+    }
+
+    /// The physically based falloff, in proportion to the inverse
+    /// square of the distance.
+    pub fn inverse_square() -> Attenuation {
+        Attenuation::new(0.0, 0.0, 1.0)
+    }
+
+    /// The fraction of the intensity that remains at the given distance.
+    pub fn factor(&self, distance: f64) -> f64 {
+        if distance.is_infinite() {
+            // only directional lights are infinitely far away, and
+            // they don't diminish
+            return 1.0;
+        }
+        let denom = self.constant + distance * (self.linear + distance * self.quadratic);
+        if denom <= 0.0 { 1.0 } else { 1.0 / denom }
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation::none()
+    }
+}
+
+/// A source of light in the scene.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LightSource {
+    kind: LightKind,
+    intensity: RGB,
+    attenuation: Attenuation,
+}
+
+pub fn point_light(p: Tuple4, i: RGB) -> LightSource {
+    LightSource {
+        kind: LightKind::Point { position: p },
+        intensity: i,
+        attenuation: Attenuation::default(),
+    }
+}
+
+/// Creates a rectangular light with one corner at `corner`, and edges
+/// given by the vectors `full_uvec` and `full_vvec`.  The rectangle is
+/// divided into `usteps` x `vsteps` cells, and one sample is taken from
+/// a random position within each cell when determining how much of the
+/// light reaches a point.
+pub fn area_light(corner: Tuple4, full_uvec: Tuple4, usteps: u32, full_vvec: Tuple4, vsteps: u32, i: RGB) -> LightSource {
+    let usteps = usteps.max(1);
+    let vsteps = vsteps.max(1);
+    LightSource {
+        kind: LightKind::Area {
+            corner,
+            uvec: full_uvec.scale(1.0 / f64::from(usteps)),
+            usteps,
+            vvec: full_vvec.scale(1.0 / f64::from(vsteps)),
+            vsteps,
+        },
+        intensity: i,
+        attenuation: Attenuation::default(),
+    }
+}
+
+/// Creates a light whose rays all travel in the given direction.
+pub fn directional_light(direction: Tuple4, i: RGB) -> LightSource {
+    LightSource {
+        kind: LightKind::Directional { direction: direction.normalize() },
+        intensity: i,
+        attenuation: Attenuation::default(),
+    }
+}
+
+/// Creates a light at `p` shining along `direction`.  The angles are
+/// measured from the axis of the cone, in radians.
+pub fn spot_light(p: Tuple4, direction: Tuple4, inner: f64, outer: f64, i: RGB) -> LightSource {
+    LightSource {
+        kind: LightKind::Spot {
+            position: p,
+            direction: direction.normalize(),
+            inner: inner.min(outer),
+            outer,
+        },
+        intensity: i,
+        attenuation: Attenuation::default(),
+    }
+}
+
+impl LightSource {
+    pub fn kind(&self) -> LightKind {
+        self.kind
+    }
+
+    /// The position of the light.  For area lights, this is the centre
+    /// of the rectangle.
+    ///
+    /// # Panics
+    ///
+    /// Directional lights are infinitely far away, so have no position;
+    /// use `towards` to find the way to them.
+    pub fn position(self: &Self) -> Tuple4 {
+        match self.kind {
+            LightKind::Point { position } => position,
+            LightKind::Spot { position, .. } => position,
+            LightKind::Directional { .. } => panic!("a directional light has no position"),
+            LightKind::Area { corner, uvec, usteps, vvec, vsteps } => {
+                corner
+                    + uvec.scale(f64::from(usteps) / 2.0)
+                    + vvec.scale(f64::from(vsteps) / 2.0)
+            },
+        }
+    }
+
+    pub fn intensity(self: &Self) -> RGB {
+        self.intensity
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn set_attenuation(&mut self, a: Attenuation) -> &mut Self {
+        self.attenuation = a;
+        self
+    }
+
+    /// The points on the light from which shadow and shading calculations
+    /// are made, when lighting the point `at`.
+    ///
+    /// For area lights there is one point per cell, placed randomly within
+    /// the cell.  The random positions are derived from `at`, so the same
+    /// surface point always sees the same samples; this keeps the shadow
+    /// and shading calculations for a point consistent with each other,
+    /// and keeps rendering deterministic.
+    ///
+    /// A directional light has a single sample, a point one unit back
+    /// towards the light from `at`, though `towards` doesn't need it.
+    pub fn sample_points(&self, at: Tuple4) -> Vec<Tuple4> {
+        match self.kind {
+            LightKind::Point { .. } | LightKind::Spot { .. } => vec![self.position()],
+            LightKind::Directional { direction } => vec![at - direction],
+            LightKind::Area { corner, uvec, usteps, vvec, vsteps } => {
+                let mut rng = Rng::seeded(
+                    at.x().to_bits() ^ at.y().to_bits().rotate_left(21) ^ at.z().to_bits().rotate_left(42)
+                );
+                let mut v = Vec::with_capacity((usteps * vsteps) as usize);
+                for j in 0..vsteps {
+                    for i in 0..usteps {
+                        let u = f64::from(i) + rng.next_f64();
+                        let w = f64::from(j) + rng.next_f64();
+                        v.push(corner + uvec.scale(u) + vvec.scale(w));
+                    }
+                }
+                v
+            },
+        }
+    }
+
+    /// The unit vector from `at` towards a sample point on the light, and
+    /// the distance to the sample.  The distance to a directional light is
+    /// infinite.
+    pub fn towards(&self, at: Tuple4, sample: Tuple4) -> (Tuple4, f64) {
+        match self.kind {
+            LightKind::Directional { direction } => (-direction, f64::INFINITY),
+            _ => {
+                let v = sample - at;
+                (v.normalize(), v.magnitude())
+            },
+        }
+    }
+
+    /// How much of the light travels along `lightv`, a unit vector from
+    /// a surface towards the light.  Only spot lights return anything
+    /// other than 1.0.
+    pub fn cone_factor(&self, lightv: Tuple4) -> f64 {
+        match self.kind {
+            LightKind::Spot { direction, inner, outer, .. } => {
+                let cos_angle = (-lightv).dot(direction);
+                let (cos_inner, cos_outer) = (inner.cos(), outer.cos());
+                if cos_angle >= cos_inner {
+                    1.0
+                } else if cos_angle <= cos_outer {
+                    0.0
+                } else {
+                    let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+                    t * t * (3.0 - 2.0 * t)
+                }
+            },
+            _ => 1.0,
+        }
+    }
+}
@@ -609,6 +609,115 @@ pub fn view_transform(from: Tuple4, to: Tuple4, up: Tuple4) -> Matrix {
     m * translation(-from.x(), -from.y(), -from.z())
 }
 
+/// The real roots of a x² + b x + c = 0, in ascending order.  A
+/// repeated root appears twice.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { vec![] } else { vec![-c / b] };
+    }
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return vec![];
+    }
+    // adding numbers of the same sign avoids the cancellation of the
+    // textbook formula when b² is much larger than 4ac
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    if q == 0.0 {
+        return vec![0.0, 0.0];
+    }
+    let (r0, r1) = (q / a, c / q);
+    vec![r0.min(r1), r0.max(r1)]
+}
+
+/// The real roots of a x³ + b x² + c x + d = 0, in ascending order.
+/// Where there is one real root and a repeated one, only one of them
+/// may be found.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return solve_quadratic(b, c, d);
+    }
+    let (b, c, d) = (b / a, c / a, d / a);
+    let q = (b * b - 3.0 * c) / 9.0;
+    let r = (2.0 * b * b * b - 9.0 * b * c + 27.0 * d) / 54.0;
+    let shift = b / 3.0;
+
+    let mut roots = if r * r < q * q * q {
+        let theta = (r / (q * q * q).sqrt()).clamp(-1.0, 1.0).acos();
+        let m = -2.0 * q.sqrt();
+        vec![
+            m * (theta / 3.0).cos() - shift,
+            m * ((theta + 2.0 * std::f64::consts::PI) / 3.0).cos() - shift,
+            m * ((theta - 2.0 * std::f64::consts::PI) / 3.0).cos() - shift,
+        ]
+    } else {
+        let e = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+        let f = if e == 0.0 { 0.0 } else { q / e };
+        vec![e + f - shift]
+    };
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    roots
+}
+
+/// The real roots of a x⁴ + b x³ + c x² + d x + e = 0, in ascending
+/// order.  Ray tracing tori needs these, where grazing rays make the
+/// closed form solution lose precision, so each root is refined by a
+/// few steps of Newton's method on the original polynomial.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return solve_cubic(b, c, d, e);
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // substituting x = y - b/4 leaves y⁴ + p y² + q y + r = 0
+    let p = c - 3.0 * b * b / 8.0;
+    let q = d - b * c / 2.0 + b * b * b / 8.0;
+    let r = e - b * d / 4.0 + b * b * c / 16.0 - 3.0 * b * b * b * b / 256.0;
+
+    let mut ys = vec![];
+    // the resolvent cubic has a positive root m unless q is 0
+    let m = solve_cubic(8.0, 8.0 * p, 2.0 * p * p - 8.0 * r, -q * q)
+        .last()
+        .copied()
+        .unwrap_or(0.0);
+    if q.abs() < 1e-12 || m <= 0.0 {
+        // biquadratic: solve for y²
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= 0.0 {
+                ys.push(-z.sqrt());
+                ys.push(z.sqrt());
+            }
+        }
+    } else {
+        // the quartic is the difference of two squares, so factors
+        // into two quadratics
+        let s = (2.0 * m).sqrt();
+        ys.extend(solve_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s)));
+        ys.extend(solve_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s)));
+    }
+
+    let poly = |x: f64| (((x + b) * x + c) * x + d) * x + e;
+    let slope = |x: f64| ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+    let mut roots: Vec<f64> = ys.into_iter()
+        .map(|y| {
+            let mut x = y - b / 4.0;
+            for _ in 0..8 {
+                let dx = poly(x) / slope(x);
+                if !dx.is_finite() {
+                    break;
+                }
+                x -= dx;
+                if dx.abs() <= 1e-14 * (1.0 + x.abs()) {
+                    break;
+                }
+            }
+            x
+        })
+        .filter(|x| x.is_finite())
+        .collect();
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    roots
+}
+
 fn sin(r: f64) -> f64 {
     r.sin()
 }
@@ -0,0 +1,67 @@
+use crate::*;
+use crate::{ply, stl, wavefront};
+
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// The formats a model can be read from.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ModelFormat {
+    Obj,
+    Stl,
+    Ply,
+}
+
+impl ModelFormat {
+    /// The format of a file, going by its extension.  Files with the
+    /// extension .stl or .ply are in those formats; anything else is
+    /// taken to be an obj file.
+    pub fn of(path: &Path) -> ModelFormat {
+        let has_extension = |ext: &str| matches!(path.extension(), Some(e) if e.eq_ignore_ascii_case(ext));
+        if has_extension("stl") {
+            ModelFormat::Stl
+        } else if has_extension("ply") {
+            ModelFormat::Ply
+        } else {
+            ModelFormat::Obj
+        }
+    }
+}
+
+/// Reads the objects in a model file, in the format given by its
+/// extension; see `ModelFormat::of`.  The material libraries of an obj
+/// file are found in the same directory as the file.
+///
+/// Errors in the contents of the file are reported as `InvalidData`,
+/// naming the file.
+pub fn read_model(path: &Path) -> io::Result<Vec<Object>> {
+    let mut f = File::open(path)?;
+    match ModelFormat::of(path) {
+        ModelFormat::Stl => stl::read_object_vec(&mut f).map_err(|e| model_error(path, e)),
+        ModelFormat::Ply => ply::read_object_vec(&mut f).map_err(|e| model_error(path, e)),
+        ModelFormat::Obj => {
+            let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+            wavefront::read_object_vec_from(&mut f, base_dir).map_err(|e| model_error(path, e))
+        },
+    }
+}
+
+/// Like `read_model`, but an obj or stl file is read into a single
+/// mesh, which takes far less memory for a large model.  Groups and
+/// materials in an obj file are ignored.  A ply file is read as by
+/// `read_model`.
+pub fn read_model_mesh(path: &Path) -> io::Result<Vec<Object>> {
+    let mut f = File::open(path)?;
+    match ModelFormat::of(path) {
+        ModelFormat::Stl => Ok(vec![stl::read_mesh(&mut f).map_err(|e| model_error(path, e))?]),
+        ModelFormat::Ply => ply::read_object_vec(&mut f).map_err(|e| model_error(path, e)),
+        ModelFormat::Obj => Ok(vec![wavefront::read_mesh(&mut f).map_err(|e| model_error(path, e))?]),
+    }
+}
+
+/// Names the file in an error from reading it.
+pub fn model_error(path: &Path, e: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+}
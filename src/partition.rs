@@ -34,6 +34,195 @@ pub fn bbox_map(depth: usize, scene: Vec<Object>) -> BoundingBoxMap {
     box_map
 }
 
+/// Arrange objects from a scene into a bounding volume hierarchy,
+/// to speed up finding intersections.
+///
+/// Unlike `binary_partition`, no depth needs to be chosen.  The
+/// objects are recursively split into two groups, choosing at each
+/// step the split that the surface area heuristic estimates will
+/// be cheapest to trace rays through.  The splitting stops once
+/// keeping the objects together is estimated to be cheaper than
+/// splitting them further.
+///
+/// Objects with infinite bounds, such as planes, would be hit by
+/// practically any ray, so they are placed directly in the root group.
+pub fn bvh_partition(scene: Vec<Object>) -> Object {
+    let flattened = flatten(&scene);
+    let (bounded, mut unbounded): (Vec<Object>, Vec<Object>) =
+        flattened.into_iter().partition(|o| o.bounds().is_finite());
+
+    let items: Vec<BvhItem> = bounded.into_iter().map(BvhItem::from).collect();
+    if items.is_empty() {
+        group(unbounded)
+    } else if unbounded.is_empty() {
+        build_bvh(items)
+    } else {
+        unbounded.push(build_bvh(items));
+        group(unbounded)
+    }
+}
+
+/// The estimated cost of stepping into a group, relative to the cost
+/// of intersecting a ray with one of its members.
+const TRAVERSAL_COST: f64 = 0.125;
+const INTERSECTION_COST: f64 = 1.0;
+/// The number of candidate split positions tried along an axis.
+const SAH_BUCKETS: usize = 16;
+/// Larger groups of objects are split, even where the heuristic
+/// estimates no gain, to keep the cost of a miss bounded.
+const MAX_LEAF_SIZE: usize = 8;
+
+struct BvhItem {
+    bounds: Bounds,
+    object: Object,
+}
+
+impl From<Object> for BvhItem {
+    fn from(object: Object) -> Self {
+        let bounds = object.bounds();
+        BvhItem { bounds, object }
+    }
+}
+
+fn build_bvh(items: Vec<BvhItem>) -> Object {
+    match sah_split(items, |i| i.bounds) {
+        SahSplit::Leaf(items) => bvh_leaf(items),
+        SahSplit::Split(left, right) => group(vec![build_bvh(left), build_bvh(right)]),
+    }
+}
+
+/// The outcome of weighing up whether to split a set of items.
+pub(crate) enum SahSplit<T> {
+    /// Keep the items together.
+    Leaf(Vec<T>),
+    /// Split the items into two sets.
+    Split(Vec<T>, Vec<T>),
+}
+
+/// Decides, using the surface area heuristic, whether the items are
+/// best kept together or split in two, and if so, how.
+///
+/// Candidate splits are made by sorting the items into buckets along
+/// the axis where their centres are most spread out.
+pub(crate) fn sah_split<T, F>(items: Vec<T>, bounds_of: F) -> SahSplit<T>
+    where F: Fn(&T) -> Bounds
+{
+    if items.len() <= 1 {
+        return SahSplit::Leaf(items);
+    }
+
+    let node_bounds = items.iter().fold(Bounds::empty(), |b, i| b.union(&bounds_of(i)));
+    let centres = items.iter().fold(Bounds::empty(), |b, i| {
+        let c = bounds_of(i).centre();
+        b.union(&Bounds::new(c, c))
+    });
+    let extent = centres.max() - centres.min();
+    let axis = if extent.x() >= extent.y() && extent.x() >= extent.z() {
+        0
+    } else if extent.y() >= extent.z() {
+        1
+    } else {
+        2
+    };
+    let axis_min = component(centres.min(), axis);
+    let axis_len = component(extent, axis);
+    if axis_len <= 0.0 {
+        // every item is centred at the same point; there's no way to split them
+        return SahSplit::Leaf(items);
+    }
+
+    let bucket_of = |item: &T| {
+        let c = component(bounds_of(item).centre(), axis);
+        let b = ((c - axis_min) / axis_len * SAH_BUCKETS as f64) as usize;
+        b.min(SAH_BUCKETS - 1)
+    };
+    let mut counts = [0usize; SAH_BUCKETS];
+    let mut bucket_bounds = [Bounds::empty(); SAH_BUCKETS];
+    for item in items.iter() {
+        let b = bucket_of(item);
+        counts[b] += 1;
+        bucket_bounds[b] = bucket_bounds[b].union(&bounds_of(item));
+    }
+
+    // the cost of splitting after each bucket
+    let node_area = node_bounds.surface_area();
+    let mut best: Option<(usize, f64)> = None;
+    for split in 0..SAH_BUCKETS - 1 {
+        let (l_count, l_bounds) = (0..=split)
+            .fold((0, Bounds::empty()), |(n, b), i| (n + counts[i], b.union(&bucket_bounds[i])));
+        let (r_count, r_bounds) = (split + 1..SAH_BUCKETS)
+            .fold((0, Bounds::empty()), |(n, b), i| (n + counts[i], b.union(&bucket_bounds[i])));
+        if l_count == 0 || r_count == 0 {
+            continue;
+        }
+        let cost = TRAVERSAL_COST + INTERSECTION_COST
+            * (l_bounds.surface_area() * l_count as f64 + r_bounds.surface_area() * r_count as f64)
+            / node_area;
+        if best.map(|(_, c)| cost < c).unwrap_or(true) {
+            best = Some((split, cost));
+        }
+    }
+
+    let leaf_cost = INTERSECTION_COST * items.len() as f64;
+    match best {
+        Some((split, cost)) if cost < leaf_cost || items.len() > MAX_LEAF_SIZE => {
+            let (left, right): (Vec<T>, Vec<T>) =
+                items.into_iter().partition(|i| bucket_of(i) <= split);
+            SahSplit::Split(left, right)
+        },
+        _ => SahSplit::Leaf(items),
+    }
+}
+
+fn bvh_leaf(items: Vec<BvhItem>) -> Object {
+    group(items.into_iter().map(|i| i.object).collect())
+}
+
+fn component(t: Tuple4, axis: usize) -> f64 {
+    match axis {
+        0 => t.x(),
+        1 => t.y(),
+        _ => t.z(),
+    }
+}
+
+/// Estimates the cost of finding the intersections of a ray with the
+/// given object tree, using the surface area heuristic.
+///
+/// A ray entering a group is tested against each of the group's
+/// members, but for a member that is itself a group, only the
+/// member's bounding box is tested.  The chance of a ray that passes
+/// through the root's bounding box also passing through a group's box
+/// is taken to be the ratio of the boxes' surface areas.  The result
+/// is the expected number of objects a ray is tested against, plus a
+/// smaller cost for each bounding box test.  Lower is better.
+pub fn traversal_cost(root: &Object) -> f64 {
+    if root.is_group() {
+        TRAVERSAL_COST + group_cost(root, root.bounds().surface_area())
+    } else {
+        INTERSECTION_COST
+    }
+}
+
+/// The expected cost of the tests made once a ray has entered the group.
+fn group_cost(grp: &Object, root_area: f64) -> f64 {
+    let area = grp.bounds().surface_area();
+    // where the boxes are infinite, the chance can't be weighed, so
+    // presume every ray enters
+    let chance = if area.is_finite() && root_area.is_finite() && root_area > 0.0 {
+        area / root_area
+    } else {
+        1.0
+    };
+    grp.children().iter()
+        .map(|c| if c.is_group() {
+            chance * TRAVERSAL_COST + group_cost(c, root_area)
+        } else {
+            chance * INTERSECTION_COST
+        })
+        .sum()
+}
+
 #[derive(Debug)]
 struct TreeNode {
     bbox: Bounds,
@@ -200,7 +389,8 @@ fn create_node(node: &TreeNode) -> Option<Object> {
 
 /// Takes a list of objects which may contain nested groups.
 /// The result is a linear list of objects which have no groups.
-/// In other words, the leaf objects from the original list.
+/// In other words, the leaf objects from the original list.  CSG
+/// objects are leaves, so their operands stay together.
 pub fn flatten(input: &[Object]) -> Vec<Object> {
     let mut v: Vec<Object> = vec![];
     for o in input {
@@ -0,0 +1,87 @@
+//! Portable float map images, which keep every channel as a 32 bit
+//! float.
+use std::convert::TryInto;
+use std::io;
+use crate::{canvas, colour, Canvas};
+
+/// Encode the canvas pixel information to the writer, according to the
+/// pfm format, in little endian byte order.  The values are only
+/// rounded to 32 bit floats, not clamped.
+pub fn encode(c: &Canvas, w: &mut dyn io::Write) -> io::Result<()> {
+    // a negative scale marks the data as little endian
+    write!(w, "PF\n{} {}\n-1.0\n", c.width(), c.height())?;
+    let mut row = Vec::with_capacity(c.width() * 12);
+    // the rows are stored from the bottom of the image up
+    for y in (0..c.height()).rev() {
+        row.clear();
+        for x in 0..c.width() {
+            let p = c.colour_at(x, y);
+            for v in [p.red(), p.green(), p.blue()].iter() {
+                row.extend_from_slice(&(*v as f32).to_le_bytes());
+            }
+        }
+        w.write_all(&row)?;
+    }
+    Ok(())
+}
+
+/// Decode a pfm image from the reader into a canvas, the reverse of
+/// `encode`.  Both colour ("PF") and greyscale ("Pf") images in either
+/// byte order can be read.  The scale is only used for its sign.
+pub fn decode(r: &mut dyn io::Read) -> io::Result<Canvas> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut bytes = vec![];
+    r.read_to_end(&mut bytes)?;
+
+    // the header is four whitespace separated words, then a single
+    // whitespace character before the data
+    let mut words = vec![];
+    let mut pos = 0;
+    while words.len() < 4 {
+        while matches!(bytes.get(pos), Some(b) if b.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        let start = pos;
+        while matches!(bytes.get(pos), Some(b) if !b.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid("pfm header is incomplete"));
+        }
+        words.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+    }
+    pos += 1;
+
+    let channels = match words[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(invalid("not a pfm image")),
+    };
+    let width: usize = words[1].parse().map_err(|_| invalid("bad width in pfm image"))?;
+    let height: usize = words[2].parse().map_err(|_| invalid("bad height in pfm image"))?;
+    let scale: f64 = words[3].parse().map_err(|_| invalid("bad scale in pfm image"))?;
+    let little_endian = scale < 0.0;
+
+    let size = width.checked_mul(height).and_then(|n| n.checked_mul(channels * 4))
+        .ok_or_else(|| invalid("pfm image is too large"))?;
+    let data = bytes.get(pos..).and_then(|rest| rest.get(..size))
+        .ok_or_else(|| invalid("pfm image is truncated"))?;
+    let value = |i: usize| {
+        let b: [u8; 4] = data[i * 4..i * 4 + 4].try_into().unwrap();
+        (if little_endian { f32::from_le_bytes(b) } else { f32::from_be_bytes(b) }) as f64
+    };
+
+    let mut canv = canvas(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let i = ((height - 1 - y) * width + x) * channels;
+            let c = if channels == 1 {
+                colour(value(i), value(i), value(i))
+            } else {
+                colour(value(i), value(i + 1), value(i + 2))
+            };
+            canv.set_colour_at(x, y, c);
+        }
+    }
+    Ok(canv)
+}
@@ -0,0 +1,376 @@
+//! Reading of polygon file format (ply) files, as written by many
+//! scanners, in ASCII or in binary of either byte order.
+use crate::*;
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The input could not be read.
+    Io,
+    /// A line of the header could not be understood.  Lines are
+    /// numbered from 1.
+    BadHeader { line: usize, text: String, reason: Reason },
+    /// The input ended before all the elements the header declares.
+    UnexpectedEnd,
+    /// A value in an ASCII file should have been a number.
+    BadNumber(String),
+    /// A face refers to a vertex that isn't in the file.  Vertices
+    /// are numbered from 0.
+    IndexOutOfRange(i64),
+    /// A face has fewer than three vertices.
+    TooFewVertices,
+}
+
+/// Why a line of the header could not be understood.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    /// The file doesn't begin with "ply".
+    NotPly,
+    /// The body is stored in a format other than ascii,
+    /// binary_little_endian or binary_big_endian.
+    UnknownFormat(String),
+    /// The given text should have been a type such as "float" or "uchar".
+    UnknownType(String),
+    /// The given text should have been a count of elements.
+    BadCount(String),
+    /// The instruction has too few values.
+    MissingValues,
+    /// A property was declared before any element.
+    PropertyOutsideElement,
+    /// An element lacks a property it needs, such as a vertex without
+    /// an x co-ordinate.
+    MissingProperty(String),
+    /// The header ended without saying what format the body is in.
+    MissingFormat,
+}
+
+impl Error for ParseError {
+
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io => write!(f, "the input could not be read"),
+            ParseError::BadHeader { line, text, reason } =>
+                write!(f, "line {}: {}: {}", line, reason, text),
+            ParseError::UnexpectedEnd => write!(f, "the input ended before all the elements the header declares"),
+            ParseError::BadNumber(x) => write!(f, "'{}' is not a number", x),
+            ParseError::IndexOutOfRange(i) => write!(f, "vertex index {} is out of range", i),
+            ParseError::TooFewVertices => write!(f, "a face needs at least three vertices"),
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::NotPly => write!(f, "not a ply file"),
+            Reason::UnknownFormat(x) => write!(f, "'{}' is not a known format", x),
+            Reason::UnknownType(x) => write!(f, "'{}' is not a known type", x),
+            Reason::BadCount(x) => write!(f, "'{}' is not a count", x),
+            Reason::MissingValues => write!(f, "too few values"),
+            Reason::PropertyOutsideElement => write!(f, "a property must follow an element"),
+            Reason::MissingProperty(x) => write!(f, "missing property '{}'", x),
+            Reason::MissingFormat => write!(f, "the header has no format"),
+        }
+    }
+}
+
+/// Reads the triangles described by a ply file, splitting larger
+/// faces into fans of triangles.  Where the vertices have normals, the
+/// triangles are smooth; where they have colours, each triangle's
+/// pattern blends the colours of its corners.
+pub fn read_object_vec(input: &mut dyn io::Read) -> Result<Vec<Object>, ParseError> {
+    let mut bytes = vec![];
+    input.read_to_end(&mut bytes).map_err(|_| ParseError::Io)?;
+
+    let (header, body_start) = read_header(&bytes)?;
+    let body = &bytes[body_start..];
+    let (vertices, faces) = match header.format {
+        Format::Ascii => {
+            let text = std::str::from_utf8(body).map_err(|_| ParseError::Io)?;
+            read_body(&header, &mut AsciiValues { words: text.split_ascii_whitespace() })?
+        },
+        Format::BinaryLittleEndian => read_body(&header, &mut BinaryValues { bytes: body, big_endian: false })?,
+        Format::BinaryBigEndian => read_body(&header, &mut BinaryValues { bytes: body, big_endian: true })?,
+    };
+
+    let mut objects = vec![];
+    for face in faces.iter() {
+        if face.len() < 3 {
+            return Err(ParseError::TooFewVertices);
+        }
+        let corner = |i: i64| {
+            usize::try_from(i).ok().and_then(|i| vertices.get(i)).ok_or(ParseError::IndexOutOfRange(i))
+        };
+        let first = corner(face[0])?;
+        for pair in face[1..].windows(2) {
+            let (a, b, c) = (first, corner(pair[0])?, corner(pair[1])?);
+            let mut tri = match (a.normal, b.normal, c.normal) {
+                (Some(n1), Some(n2), Some(n3)) => smooth_triangle(a.position, b.position, c.position, n1, n2, n3),
+                _ => triangle(a.position, b.position, c.position),
+            };
+            if let (Some(c1), Some(c2), Some(c3)) = (a.colour, b.colour, c.colour) {
+                tri.mut_material().set_pattern(Pattern::vertex_colours(c1, c2, c3));
+            }
+            objects.push(tri);
+        }
+    }
+    Ok(objects)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// The types a property may be stored as.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Scalar {
+    I8, U8, I16, U16, I32, U32, F32, F64,
+}
+
+impl Scalar {
+    fn named(name: &str) -> Result<Scalar, Reason> {
+        match name {
+            "char" | "int8" => Ok(Scalar::I8),
+            "uchar" | "uint8" => Ok(Scalar::U8),
+            "short" | "int16" => Ok(Scalar::I16),
+            "ushort" | "uint16" => Ok(Scalar::U16),
+            "int" | "int32" => Ok(Scalar::I32),
+            "uint" | "uint32" => Ok(Scalar::U32),
+            "float" | "float32" => Ok(Scalar::F32),
+            "double" | "float64" => Ok(Scalar::F64),
+            _ => Err(Reason::UnknownType(name.to_string())),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Property {
+    Scalar { name: String, ty: Scalar },
+    List { name: String, count: Scalar, item: Scalar },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } | Property::List { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn position(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|p| p.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+/// Reads the header, returning it along with where the body starts.
+fn read_header(bytes: &[u8]) -> Result<(Header, usize), ParseError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    let mut start = 0;
+    let mut line = 0;
+
+    loop {
+        line += 1;
+        let end = match bytes[start..].iter().position(|b| *b == b'\n') {
+            Some(i) => start + i,
+            None => return Err(ParseError::UnexpectedEnd),
+        };
+        let text = String::from_utf8_lossy(&bytes[start..end]);
+        let text = text.trim_end_matches('\r');
+        start = end + 1;
+        let bad = |reason| ParseError::BadHeader { line, text: text.to_string(), reason };
+
+        let mut words = text.split_whitespace();
+        match words.next() {
+            Some("ply") if line == 1 => continue,
+            _ if line == 1 => return Err(bad(Reason::NotPly)),
+            Some("format") => {
+                format = Some(match words.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    Some(x) => return Err(bad(Reason::UnknownFormat(x.to_string()))),
+                    None => return Err(bad(Reason::MissingValues)),
+                });
+            },
+            Some("element") => {
+                let name = words.next().ok_or_else(|| bad(Reason::MissingValues))?;
+                let count = words.next().ok_or_else(|| bad(Reason::MissingValues))?;
+                let count = count.parse().map_err(|_| bad(Reason::BadCount(count.to_string())))?;
+                elements.push(Element { name: name.to_string(), count, properties: vec![] });
+            },
+            Some("property") => {
+                let words: Vec<&str> = words.collect();
+                let property = match words[..] {
+                    ["list", count, item, name] => Property::List {
+                        name: name.to_string(),
+                        count: Scalar::named(count).map_err(bad)?,
+                        item: Scalar::named(item).map_err(bad)?,
+                    },
+                    [ty, name] => Property::Scalar { name: name.to_string(), ty: Scalar::named(ty).map_err(bad)? },
+                    _ => return Err(bad(Reason::MissingValues)),
+                };
+                let element = elements.last_mut().ok_or_else(|| bad(Reason::PropertyOutsideElement))?;
+                element.properties.push(property);
+            },
+            Some("end_header") => {
+                let format = format.ok_or_else(|| bad(Reason::MissingFormat))?;
+                check_properties(&elements).map_err(bad)?;
+                return Ok((Header { format, elements }, start));
+            },
+            // comments, obj_info and anything else we don't understand
+            _ => (),
+        }
+    }
+}
+
+fn check_properties(elements: &[Element]) -> Result<(), Reason> {
+    for e in elements {
+        let required: &[&str] = match e.name.as_str() {
+            "vertex" => &["x", "y", "z"],
+            "face" if e.position("vertex_index").is_none() => &["vertex_indices"],
+            _ => &[],
+        };
+        if let Some(missing) = required.iter().find(|name| e.position(name).is_none()) {
+            return Err(Reason::MissingProperty(missing.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// A source of the values in the body of the file.
+trait Values {
+    fn next(&mut self, ty: Scalar) -> Result<f64, ParseError>;
+}
+
+struct AsciiValues<'a> {
+    words: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl Values for AsciiValues<'_> {
+    fn next(&mut self, _ty: Scalar) -> Result<f64, ParseError> {
+        let w = self.words.next().ok_or(ParseError::UnexpectedEnd)?;
+        w.parse().map_err(|_| ParseError::BadNumber(w.to_string()))
+    }
+}
+
+struct BinaryValues<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl Values for BinaryValues<'_> {
+    fn next(&mut self, ty: Scalar) -> Result<f64, ParseError> {
+        let n = ty.size();
+        if self.bytes.len() < n {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        let mut le = [0u8; 8];
+        le[..n].copy_from_slice(&self.bytes[..n]);
+        if self.big_endian {
+            le[..n].reverse();
+        }
+        self.bytes = &self.bytes[n..];
+
+        Ok(match ty {
+            Scalar::I8 => le[0] as i8 as f64,
+            Scalar::U8 => le[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([le[0], le[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([le[0], le[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes(le[..4].try_into().unwrap()) as f64,
+            Scalar::U32 => u32::from_le_bytes(le[..4].try_into().unwrap()) as f64,
+            Scalar::F32 => f32::from_le_bytes(le[..4].try_into().unwrap()) as f64,
+            Scalar::F64 => f64::from_le_bytes(le),
+        })
+    }
+}
+
+struct Vertex {
+    position: Tuple4,
+    normal: Option<Tuple4>,
+    colour: Option<RGB>,
+}
+
+/// Reads the vertices and faces, skipping over any other elements.
+fn read_body(header: &Header, values: &mut dyn Values) -> Result<(Vec<Vertex>, Vec<Vec<i64>>), ParseError> {
+    let mut vertices = vec![];
+    let mut faces = vec![];
+    for e in header.elements.iter() {
+        let mut row = vec![0.0; e.properties.len()];
+        let mut list = vec![];
+        let list_at = e.position("vertex_indices").or_else(|| e.position("vertex_index"));
+
+        for _ in 0..e.count {
+            for (i, p) in e.properties.iter().enumerate() {
+                match p {
+                    Property::Scalar { ty, .. } => row[i] = values.next(*ty)?,
+                    Property::List { count, item, .. } => {
+                        let n = values.next(*count)? as usize;
+                        list.clear();
+                        for _ in 0..n {
+                            list.push(values.next(*item)? as i64);
+                        }
+                        if Some(i) == list_at && e.name == "face" {
+                            faces.push(list.clone());
+                        }
+                    },
+                }
+            }
+            if e.name == "vertex" {
+                vertices.push(vertex(e, &row));
+            }
+        }
+    }
+    Ok((vertices, faces))
+}
+
+fn vertex(e: &Element, row: &[f64]) -> Vertex {
+    let value = |name| e.position(name).map(|i| row[i]);
+    let triple = |a, b, c| Some((value(a)?, value(b)?, value(c)?));
+    // colours stored as integers use the whole range of the type
+    let colour_scale = match e.position("red").map(|i| &e.properties[i]) {
+        Some(Property::Scalar { ty: Scalar::U8, .. }) => 255.0,
+        Some(Property::Scalar { ty: Scalar::U16, .. }) => 65535.0,
+        _ => 1.0,
+    };
+
+    let (x, y, z) = triple("x", "y", "z").unwrap();
+    Vertex {
+        position: point(x, y, z),
+        normal: triple("nx", "ny", "nz").map(|(x, y, z)| vector(x, y, z)),
+        colour: triple("red", "green", "blue")
+            .map(|(r, g, b)| colour(r / colour_scale, g / colour_scale, b / colour_scale)),
+    }
+}
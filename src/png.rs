@@ -1,5 +1,5 @@
 use std::io;
-use crate::Canvas;
+use crate::{canvas, colour, Canvas};
 use png;
 
 /// Encode the canvas pixel information to the writer, according to the
@@ -15,6 +15,48 @@ pub fn encode(c: &Canvas, w: &mut dyn io::Write) -> io::Result<()> {
     Ok(())
 }
 
+/// Decode a png image from the reader into a canvas.  Each 8 bit
+/// channel value is scaled into the range [0.0, 1.0], the reverse of
+/// `encode`.  Any alpha channel is ignored.
+pub fn decode(r: &mut dyn io::Read) -> io::Result<Canvas> {
+    let mut decoder = png::Decoder::new(r);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder.read_info()?;
+    let mut data = vec![0; info.buffer_size()];
+    reader.next_frame(&mut data)?;
+
+    let (colour_type, depth) = reader.output_color_type();
+    let channels = match colour_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::RGB => 3,
+        png::ColorType::RGBA => 4,
+        png::ColorType::Indexed => {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpanded palette in png image"));
+        },
+    };
+    // for 16 bit images only the most significant byte of each channel is used
+    let bytes_per_channel = if depth == png::BitDepth::Sixteen { 2 } else { 1 };
+    let stride = channels * bytes_per_channel;
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut canv = canvas(width, height);
+    for y in 0..height {
+        let row = &data[y * info.line_size..];
+        for x in 0..width {
+            let px = &row[x * stride..];
+            let channel = |c: usize| f64::from(px[c * bytes_per_channel]) / 255.0;
+            let c = if channels < 3 {
+                colour(channel(0), channel(0), channel(0))
+            } else {
+                colour(channel(0), channel(1), channel(2))
+            };
+            canv.set_colour_at(x, y, c);
+        }
+    }
+    Ok(canv)
+}
+
 fn to_rgb_array(canv: &Canvas) -> Vec<u8> {
     let mut vec: Vec<u8> = Vec::with_capacity(canv.pixels.len() * 3);
     for rgb in canv.pixels.iter() {
@@ -90,6 +132,21 @@ mod pngtest {
             128, 0, 0,
         ])
     }
+
+    #[allow(non_snake_case)]
+    #[test]
+    fn decode___reads_back_encoded_canvas() {
+        let mut canvs = canvas(2, 3);
+        canvs.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+        canvs.set_colour_at(1, 0, colour(0.0, 1.0, 0.0));
+        canvs.set_colour_at(0, 1, colour(0.0, 0.0, 1.0));
+        canvs.set_colour_at(1, 2, colour(1.0, 1.0, 1.0));
+        let mut bytes = vec![];
+        encode(&canvs, &mut bytes).unwrap();
+
+        let decoded = decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded, canvs);
+    }
 }
 
 #[allow(non_snake_case)]
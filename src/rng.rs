@@ -0,0 +1,30 @@
+/// A small, seedable pseudo-random number generator.
+///
+/// The renderer only needs reproducible noise for things like
+/// jittering sample positions, so a SplitMix64 generator is more
+/// than adequate.  The same seed always produces the same sequence,
+/// which keeps renders (and tests) deterministic.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in the range [0.0, 1.0)
+    pub fn next_f64(&mut self) -> f64 {
+        // use the top 53 bits, the precision of an f64 mantissa
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
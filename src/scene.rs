@@ -0,0 +1,558 @@
+//! Reads a description of a scene, written in TOML, and builds the
+//! `World` and `Camera` needed to render it.
+//!
+//! A scene file has four sections.  Only `camera` is mandatory.
+//!
+//! ```toml
+//! [camera]
+//! width = 800                  # pixels
+//! height = 600                 # pixels
+//! field_of_view = 60.0         # degrees
+//! from = [0.0, 1.5, -5.0]      # where the camera sits
+//! to = [0.0, 1.0, 0.0]         # the point the camera looks at
+//! up = [0.0, 1.0, 0.0]         # optional, defaults to the y axis
+//! samples = "jitter:2"         # optional, see `Sampling`; defaults to "centre"
+//!
+//! [[lights]]
+//! position = [-10.0, 10.0, -10.0]
+//! intensity = [1.0, 1.0, 1.0]  # optional, defaults to white
+//! attenuation = [1.0, 0.0, 0.0] # optional, constant, linear and quadratic
+//!                              # falloff with distance; defaults to none
+//!
+//! [[lights]]
+//! kind = "area"                # optional, "point" by default
+//! corner = [-1.0, 5.0, -1.0]   # a rectangle with one corner here...
+//! uvec = [2.0, 0.0, 0.0]       # ...and these two edges
+//! vvec = [0.0, 0.0, 2.0]
+//! usteps = 4                   # sampled in a 4 x 4 grid of cells,
+//! vsteps = 4                   # each defaults to 1
+//!
+//! [[lights]]
+//! kind = "spot"                # a cone of light
+//! position = [0.0, 5.0, 0.0]
+//! direction = [0.0, -1.0, 0.0] # along the axis of the cone
+//! inner_angle = 20.0           # degrees from the axis; fully lit within this.
+//!                              # optional, defaults to outer_angle
+//! outer_angle = 30.0           # degrees from the axis; unlit beyond this
+//!
+//! [[lights]]
+//! kind = "directional"         # parallel rays, like the sun
+//! direction = [1.0, -1.0, 1.0] # the direction the light travels
+//!
+//! [materials.red]              # a material named "red"
+//! pattern = { Solid = [1.0, 0.0, 0.0] }
+//! pattern_transform = [{ scale = [0.5, 0.5, 0.5] }]
+//! ambient = 0.1                # each of these is optional and
+//! diffuse = 0.9                # defaults to the value used by
+//! specular = 0.9               # Material::default()
+//! shininess = 200.0
+//! reflective = 0.0
+//! transparency = 0.0
+//! refractive_index = 1.0
+//!
+//! [[objects]]
+//! shape = "sphere"
+//! material = "red"             # optional, the name of a material
+//! transform = [{ scale = [2.0, 2.0, 2.0] }, { translate = [0.0, 1.0, 0.0] }]
+//! ```
+//!
+//! Patterns are written as a table with a single key naming the kind of
+//! pattern: `{ Solid = [r, g, b] }`, `{ Stripes = { a = [..], b = [..] } }`,
+//! `{ Gradient = { from = [..], to = [..] } }`, `{ Ring = { a = [..], b = [..] } }`,
+//! `{ Checkers = { a = [..], b = [..] } }` or, for triangles,
+//! `{ VertexColours = [[..], [..], [..]] }`, blending a colour for each corner.
+//!
+//! Instead of a pattern, a material may take its colour from a png or ppm image:
+//! `texture = { file = "earth.png", mapping = "spherical" }`.  The file is
+//! found relative to the scene file, and the mapping, which decides how the
+//! image is wrapped around the object, is one of `"spherical"`, `"planar"`,
+//! `"cylindrical"`, `"cubic"` or `"vertices"`.  See `UvMapping`.
+//!
+//! A transform is a list of operations, applied in the order they are
+//! written: `{ translate = [x, y, z] }`, `{ scale = [x, y, z] }`,
+//! `{ rotate_x = degrees }`, `{ rotate_y = degrees }` or `{ rotate_z = degrees }`.
+//!
+//! The `shape` of an object is one of the following.  Some shapes take
+//! extra keys.
+//!
+//! | shape               | extra keys                                            |
+//! |---------------------|-------------------------------------------------------|
+//! | `"sphere"`          |                                                       |
+//! | `"plane"`           |                                                       |
+//! | `"cube"`            |                                                       |
+//! | `"cylinder"`        | `min`, `max` (default unbounded), `closed` (false)    |
+//! | `"cone"`            | `min`, `max` and `closed`, as for cylinders           |
+//! | `"torus"`           | `major` (1.0) and `minor` (0.25) radii                |
+//! | `"disc"`            | `radius` (1.0)                                        |
+//! | `"rect"`            | `width` and `depth` (1.0), along x and z              |
+//! | `"quad"`            | `points`, three corners, the first between the others |
+//! | `"triangle"`        | `points = [[x, y, z], [x, y, z], [x, y, z]]`          |
+//! | `"smooth_triangle"` | `points` as above, `normals` likewise                 |
+//! | `"group"`           | `children`, a list of objects, e.g. `[[objects.children]]` |
+//! | `"obj"`             | `file`, a wavefront obj file relative to the scene file |
+//!
+//! A material given to a group or an obj include is applied to every
+//! object within it.
+//!
+//! Mistakes in a scene file, such as a misspelt key or a reference to a
+//! material that doesn't exist, are reported with the line and column
+//! at which they occur.
+
+use crate::*;
+use crate::wavefront;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::Deserialize;
+use toml::Spanned;
+
+/// Reads the scene file at the given path.  Any files the scene refers
+/// to are found relative to the directory containing the scene file.
+pub fn load_scene(path: &Path) -> Result<(World, Camera), SceneError> {
+    let text = fs::read_to_string(path).map_err(|e| SceneError::Io(path.to_path_buf(), e))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    read_scene(&text, base)
+}
+
+/// Builds a world and camera from the text of a scene file.  Any files
+/// the scene refers to are found relative to `base_dir`.
+pub fn read_scene(text: &str, base_dir: &Path) -> Result<(World, Camera), SceneError> {
+    let desc: SceneDesc = toml::from_str(text).map_err(|e| syntax_error(text, &e))?;
+    let mut materials = BTreeMap::new();
+    for (name, m) in desc.materials.iter() {
+        materials.insert(name.clone(), material_from(text, base_dir, m)?);
+    }
+    let builder = Builder { text, base_dir, materials: &materials };
+
+    let camera = builder.camera(&desc.camera)?;
+    let mut lights = Vec::with_capacity(desc.lights.len());
+    for (i, l) in desc.lights.iter().enumerate() {
+        lights.push(builder.light(i, l)?);
+    }
+    let mut objects = Vec::with_capacity(desc.objects.len());
+    for o in desc.objects.iter() {
+        objects.push(builder.object(o)?);
+    }
+    Ok((World::with(lights, objects), camera))
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, io::Error),
+    Invalid { line: usize, column: usize, message: String },
+}
+
+impl std::error::Error for SceneError {}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            SceneError::Invalid { line, column, message } => {
+                write!(f, "line {}, column {}: {}", line, column, message)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    camera: CameraDesc,
+    #[serde(default)]
+    lights: Vec<LightDesc>,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialDesc>,
+    #[serde(default)]
+    objects: Vec<ObjectDesc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    width: u32,
+    height: u32,
+    field_of_view: f64,
+    from: [f64; 3],
+    to: [f64; 3],
+    #[serde(default = "default_up")]
+    up: [f64; 3],
+    samples: Option<Spanned<String>>,
+}
+
+fn default_up() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightDesc {
+    kind: Option<Spanned<LightType>>,
+    #[serde(default = "RGB::white")]
+    intensity: RGB,
+    attenuation: Option<[f64; 3]>,
+
+    // point and spot lights
+    position: Option<[f64; 3]>,
+
+    // spot and directional lights
+    direction: Option<[f64; 3]>,
+
+    // spot lights
+    inner_angle: Option<f64>,
+    outer_angle: Option<f64>,
+
+    // area lights
+    corner: Option<[f64; 3]>,
+    uvec: Option<[f64; 3]>,
+    usteps: Option<u32>,
+    vvec: Option<[f64; 3]>,
+    vsteps: Option<u32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LightType {
+    Point,
+    Area,
+    Spot,
+    Directional,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDesc {
+    pattern: Option<Pattern>,
+    texture: Option<TextureDesc>,
+    #[serde(default)]
+    pattern_transform: Vec<TransformDesc>,
+    ambient: Option<f64>,
+    diffuse: Option<f64>,
+    specular: Option<f64>,
+    shininess: Option<f64>,
+    reflective: Option<f64>,
+    transparency: Option<f64>,
+    refractive_index: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextureDesc {
+    file: Spanned<String>,
+    mapping: UvMapping,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TransformDesc {
+    Translate([f64; 3]),
+    Scale([f64; 3]),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ShapeKind {
+    Sphere,
+    Plane,
+    Cube,
+    Cylinder,
+    Cone,
+    Torus,
+    Disc,
+    Rect,
+    Quad,
+    Triangle,
+    SmoothTriangle,
+    Group,
+    Obj,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectDesc {
+    shape: Spanned<ShapeKind>,
+    material: Option<Spanned<String>>,
+    #[serde(default)]
+    transform: Vec<TransformDesc>,
+
+    // cylinders and cones
+    min: Option<f64>,
+    max: Option<f64>,
+    closed: Option<bool>,
+
+    // tori
+    major: Option<f64>,
+    minor: Option<f64>,
+
+    // discs and rects
+    radius: Option<f64>,
+    width: Option<f64>,
+    depth: Option<f64>,
+
+    // triangles and quads
+    points: Option<[[f64; 3]; 3]>,
+    normals: Option<[[f64; 3]; 3]>,
+
+    // groups
+    children: Option<Vec<ObjectDesc>>,
+
+    // obj includes
+    file: Option<Spanned<String>>,
+}
+
+struct Builder<'a> {
+    text: &'a str,
+    base_dir: &'a Path,
+    materials: &'a BTreeMap<String, Material>,
+}
+
+impl<'a> Builder<'a> {
+    fn camera(&self, desc: &CameraDesc) -> Result<Camera, SceneError> {
+        let mut cam = Camera::new(desc.width, desc.height, desc.field_of_view.to_radians());
+        cam.orient(point_from(desc.from), point_from(desc.to), vector_from(desc.up));
+        if let Some(samples) = &desc.samples {
+            let s = samples.get_ref().parse::<Sampling>()
+                .map_err(|e| self.invalid(samples.start(), e.to_string()))?;
+            cam.set_sampling(s);
+        }
+        Ok(cam)
+    }
+
+    fn light(&self, index: usize, desc: &LightDesc) -> Result<LightSource, SceneError> {
+        let mut light = self.light_kind(index, desc)?;
+        if let Some([c, l, q]) = desc.attenuation {
+            light.set_attenuation(Attenuation::new(c, l, q));
+        }
+        Ok(light)
+    }
+
+    fn light_kind(&self, index: usize, desc: &LightDesc) -> Result<LightSource, SceneError> {
+        // a light without a kind is reported at its table header, if it has one
+        let at = match &desc.kind {
+            Some(k) => k.start(),
+            None => nth_light_header(self.text, index).unwrap_or(0),
+        };
+        let missing = |key: &str| self.invalid(at, format!("this light requires the key '{}'", key));
+        match desc.kind.as_ref().map(|k| *k.get_ref()).unwrap_or(LightType::Point) {
+            LightType::Point => {
+                let position = desc.position.ok_or_else(|| missing("position"))?;
+                Ok(point_light(point_from(position), desc.intensity))
+            },
+            LightType::Area => {
+                let corner = desc.corner.ok_or_else(|| missing("corner"))?;
+                let uvec = desc.uvec.ok_or_else(|| missing("uvec"))?;
+                let vvec = desc.vvec.ok_or_else(|| missing("vvec"))?;
+                Ok(area_light(
+                    point_from(corner),
+                    vector_from(uvec), desc.usteps.unwrap_or(1),
+                    vector_from(vvec), desc.vsteps.unwrap_or(1),
+                    desc.intensity,
+                ))
+            },
+            LightType::Spot => {
+                let position = desc.position.ok_or_else(|| missing("position"))?;
+                let direction = desc.direction.ok_or_else(|| missing("direction"))?;
+                let outer = desc.outer_angle.ok_or_else(|| missing("outer_angle"))?;
+                let inner = desc.inner_angle.unwrap_or(outer);
+                Ok(spot_light(
+                    point_from(position),
+                    vector_from(direction),
+                    inner.to_radians(),
+                    outer.to_radians(),
+                    desc.intensity,
+                ))
+            },
+            LightType::Directional => {
+                let direction = desc.direction.ok_or_else(|| missing("direction"))?;
+                Ok(directional_light(vector_from(direction), desc.intensity))
+            },
+        }
+    }
+
+    fn object(&self, desc: &ObjectDesc) -> Result<Object, SceneError> {
+        let at = desc.shape.start();
+        let mut obj = match desc.shape.get_ref() {
+            ShapeKind::Sphere => unit_sphere(),
+            ShapeKind::Plane => plane(),
+            ShapeKind::Cube => cube(),
+            ShapeKind::Cylinder | ShapeKind::Cone => {
+                let kind = if desc.closed.unwrap_or(false) { CylKind::Closed } else { CylKind::Open };
+                let make = if *desc.shape.get_ref() == ShapeKind::Cone { cone } else { cylinder };
+                make(
+                    kind,
+                    desc.min.unwrap_or(f64::NEG_INFINITY),
+                    desc.max.unwrap_or(f64::INFINITY),
+                )
+            },
+            ShapeKind::Torus => torus(desc.major.unwrap_or(1.0), desc.minor.unwrap_or(0.25)),
+            ShapeKind::Disc => disc(desc.radius.unwrap_or(1.0)),
+            ShapeKind::Rect => rect(desc.width.unwrap_or(1.0), desc.depth.unwrap_or(1.0)),
+            ShapeKind::Quad => {
+                let [p1, p2, p3] = desc.points.ok_or_else(|| self.missing(at, "points"))?;
+                let corner = point_from(p1);
+                quad(corner, point_from(p2) - corner, point_from(p3) - corner)
+            },
+            ShapeKind::Triangle => {
+                let [p1, p2, p3] = desc.points.ok_or_else(|| self.missing(at, "points"))?;
+                triangle(point_from(p1), point_from(p2), point_from(p3))
+            },
+            ShapeKind::SmoothTriangle => {
+                let [p1, p2, p3] = desc.points.ok_or_else(|| self.missing(at, "points"))?;
+                let [n1, n2, n3] = desc.normals.ok_or_else(|| self.missing(at, "normals"))?;
+                smooth_triangle(
+                    point_from(p1), point_from(p2), point_from(p3),
+                    vector_from(n1), vector_from(n2), vector_from(n3),
+                )
+            },
+            ShapeKind::Group => {
+                let descs = desc.children.as_ref().ok_or_else(|| self.missing(at, "children"))?;
+                let mut children = Vec::with_capacity(descs.len());
+                for c in descs.iter() {
+                    children.push(self.object(c)?);
+                }
+                group(children)
+            },
+            ShapeKind::Obj => {
+                let file = desc.file.as_ref().ok_or_else(|| self.missing(at, "file"))?;
+                let path = self.base_dir.join(file.get_ref());
+                let mut f = fs::File::open(&path).map_err(|e| SceneError::Io(path.clone(), e))?;
+                let dir = path.parent().unwrap_or(self.base_dir);
+                let objects = wavefront::read_object_vec_from(&mut f, dir)
+                    .map_err(|e| self.invalid(file.start(), format!("{}: {}", path.display(), e)))?;
+                group(objects)
+            },
+        };
+
+        if let Some(name) = &desc.material {
+            let m = self.materials.get(name.get_ref())
+                .ok_or_else(|| self.invalid(name.start(), format!("no material named '{}'", name.get_ref())))?;
+            apply_material(&mut obj, m);
+        }
+        obj.set_object_to_world_spc(transform_from(&desc.transform));
+        Ok(obj)
+    }
+
+    fn missing(&self, at: usize, key: &str) -> SceneError {
+        self.invalid(at, format!("this shape requires the key '{}'", key))
+    }
+
+    fn invalid(&self, offset: usize, message: String) -> SceneError {
+        invalid(self.text, offset, message)
+    }
+}
+
+fn invalid(text: &str, offset: usize, message: String) -> SceneError {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    SceneError::Invalid { line, column, message }
+}
+
+/// Converts an error from the toml parser into a SceneError.
+///
+/// The toml parser reports problems found while interpreting a table
+/// at the start of the table, rather than at the key responsible.  Where
+/// the error message names the key, this searches forward from the
+/// start of the table to find where the key is written.
+fn syntax_error(text: &str, e: &toml::de::Error) -> SceneError {
+    // only used when reporting an error, so there's no need to cache these
+    let suffix_re = Regex::new(r" at line \d+ column \d+$").unwrap();
+    let named_re = Regex::new(r"unknown (field|variant) `([^`]+)`").unwrap();
+    let for_key_re = Regex::new(r"for key `(?:[^`]*\.)?([^`.]+)`").unwrap();
+    let full = e.to_string();
+    let message = suffix_re.replace(&full, "").to_string();
+    let (line, column) = e.line_col().unwrap_or((0, 0));
+
+    let candidates = [named_re.captures(&message), for_key_re.captures(&message)];
+    let found = candidates.iter()
+        .flat_map(|c| c.as_ref().and_then(|c| c.get(c.len() - 1)))
+        .filter_map(|key| find_key(text, line, key.as_str()))
+        .next();
+
+    let (line, column) = found.unwrap_or((line, column));
+    SceneError::Invalid { line: line + 1, column: column + 1, message }
+}
+
+/// Finds the first place, at or after the given line, where the key is
+/// assigned a value.  Lines and columns are counted from 0.
+fn find_key(text: &str, from_line: usize, key: &str) -> Option<(usize, usize)> {
+    let key_re = Regex::new(&format!(r"(?:^|[\s{{,])({})\s*=", regex::escape(key))).ok()?;
+    text.lines()
+        .enumerate()
+        .skip(from_line)
+        .filter_map(|(i, l)| key_re.captures(l).and_then(|c| c.get(1)).map(|m| (i, m.start())))
+        .next()
+}
+
+/// The offset of the header of the nth `[[lights]]` table.
+fn nth_light_header(text: &str, n: usize) -> Option<usize> {
+    lazy_static! {
+        static ref HEADER_RE: Regex = Regex::new(r"(?m)^[ \t]*\[\[\s*lights\s*\]\]").unwrap();
+    }
+    HEADER_RE.find_iter(text).nth(n).map(|m| m.start() + m.as_str().find('[').unwrap_or(0))
+}
+
+/// Sets the material of an object.  For groups, the material of
+/// every object within the group is set instead.
+fn apply_material(obj: &mut Object, m: &Material) {
+    if obj.is_group() {
+        let children = obj.children().iter().map(|c| {
+            let mut child = c.clone();
+            apply_material(&mut child, m);
+            child
+        }).collect();
+        let transform = obj.object_to_world_spc();
+        *obj = group(children);
+        obj.set_object_to_world_spc(transform);
+    } else {
+        obj.set_material(*m);
+    }
+}
+
+fn material_from(text: &str, base_dir: &Path, desc: &MaterialDesc) -> Result<Material, SceneError> {
+    let mut m = Material::default();
+    if let Some(p) = desc.pattern {
+        m.set_pattern(p);
+    }
+    if let Some(t) = &desc.texture {
+        let path = base_dir.join(t.file.get_ref());
+        let image = load_texture(&path)
+            .map_err(|e| invalid(text, t.file.start(), format!("{}: {}", path.display(), e)))?;
+        m.set_pattern(Pattern::image(image, t.mapping));
+    }
+    m.set_pattern_to_object_spc(transform_from(&desc.pattern_transform));
+    if let Some(x) = desc.ambient { m.set_ambient(x); }
+    if let Some(x) = desc.diffuse { m.set_diffuse(x); }
+    if let Some(x) = desc.specular { m.set_specular(x); }
+    if let Some(x) = desc.shininess { m.set_shininess(x); }
+    if let Some(x) = desc.reflective { m.set_reflective(x); }
+    if let Some(x) = desc.transparency { m.set_transparency(x); }
+    if let Some(x) = desc.refractive_index { m.set_refractive_index(x); }
+    Ok(m)
+}
+
+fn transform_from(steps: &[TransformDesc]) -> Matrix {
+    steps.iter().fold(identity(), |m, step| match *step {
+        TransformDesc::Translate([x, y, z]) => m.translate(x, y, z),
+        TransformDesc::Scale([x, y, z]) => m.scale(x, y, z),
+        TransformDesc::RotateX(deg) => m.rotate_x(deg.to_radians()),
+        TransformDesc::RotateY(deg) => m.rotate_y(deg.to_radians()),
+        TransformDesc::RotateZ(deg) => m.rotate_z(deg.to_radians()),
+    })
+}
+
+fn point_from([x, y, z]: [f64; 3]) -> Tuple4 {
+    point(x, y, z)
+}
+
+fn vector_from([x, y, z]: [f64; 3]) -> Tuple4 {
+    vector(x, y, z)
+}
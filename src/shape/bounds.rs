@@ -55,6 +55,45 @@ impl Bounds {
         Bounds::new(min, max)
     }
 
+    /// A box containing nothing.  Its min is greater than its max, so
+    /// the union of it with any other box is the other box.
+    pub fn empty() -> Bounds {
+        Bounds {
+            min: point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    /// The smallest box containing both this box and the other.
+    pub fn union(&self, b: &Bounds) -> Bounds {
+        Bounds::new(Tuple4::min(self.min, b.min), Tuple4::max(self.max, b.max))
+    }
+
+    pub fn centre(&self) -> Tuple4 {
+        point(
+            (self.min.x() + self.max.x()) / 2.0,
+            (self.min.y() + self.max.y()) / 2.0,
+            (self.min.z() + self.max.z()) / 2.0,
+        )
+    }
+
+    /// True if no side of the box is infinitely far away.
+    pub fn is_finite(&self) -> bool {
+        [self.min.x(), self.min.y(), self.min.z(), self.max.x(), self.max.y(), self.max.z()]
+            .iter()
+            .all(|v| v.is_finite())
+    }
+
+    /// The total area of the six faces of the box.  An empty box has
+    /// no area.
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        if d.x() < 0.0 || d.y() < 0.0 || d.z() < 0.0 {
+            return 0.0;
+        }
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
     pub fn contains(&self, b: &Bounds) -> bool {
         (self.min.x() <= b.min.x()) && (b.min.x() <= b.max.x()) && (b.max.x() <= self.max.x())
             && (self.min.y() <= b.min.y()) && (b.min.y() <= b.max.y()) && (b.max.y() <= self.max.y())
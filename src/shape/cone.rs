@@ -0,0 +1,105 @@
+use crate::*;
+
+
+/// Creates an infinite double-napped open cone along the y-axis, with
+/// its apex at the origin.
+///
+/// Imagine the lines x = y and x = -y in the x-y plane, spun around
+/// the y-axis.  The radius at any height is the distance from the apex.
+pub fn inf_cone() -> Object {
+    cone(CylKind::Open, f64::NEG_INFINITY, f64::INFINITY)
+}
+
+/// Creates a cone along the y-axis, with its apex at the origin, cut
+/// off at `lbound` and `ubound`.  If the bounds are either side of the
+/// origin, the cone is double-napped, with two halves meeting at the
+/// apex.  The caps of a closed cone have a radius equal to the
+/// distance of their bound from the apex.
+pub fn cone(kind: CylKind, lbound: f64, ubound: f64) -> Object {
+    Object {
+        world_to_object_spc: identity(),
+        material: Material::default(),
+        shape: Shape::Cone { kind, lbound, ubound },
+    }
+}
+
+
+pub fn bounds_of_cone(lbound: f64, ubound: f64) -> Bounds {
+    let r = lbound.abs().max(ubound.abs());
+    Bounds::new(point(-r, lbound, -r), point(r, ubound, r))
+}
+
+
+pub fn normal_of_cone(lbound: f64, ubound: f64, pos: Tuple4) -> Tuple4 {
+    let mag = pos.x().powi(2) + pos.z().powi(2);
+    if mag < pos.y().powi(2) {
+        if pos.y() >= (ubound - crate::EPSILON) {
+            return vector(0.0, 1.0, 0.0);
+        } else if pos.y() <= (lbound + crate::EPSILON) {
+            return vector(0.0, -1.0, 0.0);
+        }
+    }
+
+    // the normal slopes away from the apex, downwards above it and
+    // upwards below it
+    let y = if pos.y() > 0.0 { -mag.sqrt() } else { mag.sqrt() };
+    vector(pos.x(), y, pos.z())
+}
+
+
+pub fn append_cone_intersects<'a>(
+    r: &Ray,
+    cone: &'a Object,
+    vec: &mut Vec<Intersection<'a>>,
+    kind: CylKind,
+    lower: f64,
+    upper: f64)
+{
+    for t in intersect_cone(r) {
+        let y = (r.origin + (r.direction.scale(t))).y();
+        if lower < y && y < upper {
+            vec.push(intersection(t, cone));
+        }
+    }
+
+    if kind == CylKind::Open || almost_eq(r.direction.y().abs(), 0.0) {
+        return;
+    }
+
+    for bound in [lower, upper].iter() {
+        let t = (bound - r.origin.y()) / r.direction.y();
+        if check_cap(r, t, *bound) {
+            vec.push(intersection(t, cone))
+        }
+    }
+}
+
+fn check_cap(ray: &Ray, t: f64, radius: f64) -> bool {
+    let x = ray.origin.x() + (t * ray.direction.x());
+    let z = ray.origin.z() + (t * ray.direction.z());
+    x.powi(2) + z.powi(2) <= radius.powi(2)
+}
+
+/// The distances along the ray to the sides of the infinite cone.
+fn intersect_cone(ray: &Ray) -> Vec<f64> {
+    let (o, d) = (ray.origin, ray.direction);
+    let a = d.x().powi(2) - d.y().powi(2) + d.z().powi(2);
+    let b = (2.0 * o.x() * d.x()) - (2.0 * o.y() * d.y()) + (2.0 * o.z() * d.z());
+    let c = o.x().powi(2) - o.y().powi(2) + o.z().powi(2);
+
+    if almost_eq(a, 0.0) {
+        // the ray is parallel to one of the halves, so crosses at most
+        // the other one
+        if almost_eq(b, 0.0) {
+            return vec![];
+        }
+        return vec![-c / (2.0 * b)];
+    }
+
+    let disc = b.powi(2) - (4.0 * a * c);
+    if disc < 0.0 { return vec![]; }
+
+    let t0 = ( -b - disc.sqrt()) / (2.0*a);
+    let t1 = ( -b + disc.sqrt()) / (2.0*a);
+    vec![t0.min(t1), t0.max(t1)]
+}
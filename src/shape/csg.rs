@@ -0,0 +1,97 @@
+use crate::*;
+use crate::shape::bounds;
+use std::cmp::Ordering;
+
+/// Which parts of its two operands a CSG object keeps.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOp {
+    /// Everything inside either operand.
+    Union,
+    /// Only what is inside both operands.
+    Intersection,
+    /// What is inside the left operand but not the right.
+    Difference,
+}
+
+/// Combines two objects by constructive solid geometry, such as a lens
+/// made from the intersection of two spheres.
+///
+/// The operands keep their own transforms and materials; the surface of
+/// the result is made of the parts of their surfaces that `op` keeps.
+/// The operands should be closed solids, as a ray is taken to be inside
+/// an operand between entering and leaving it.
+pub fn csg(op: CsgOp, left: Object, right: Object) -> Object {
+    let bounds = bounds_of(op, &left, &right);
+    Object {
+        world_to_object_spc: identity(),
+        material: Material::default(),
+        shape: Shape::Csg { op, left: Box::new(left), right: Box::new(right), bounds },
+    }
+}
+
+fn bounds_of(op: CsgOp, left: &Object, right: &Object) -> Bounds {
+    let (l, r) = (left.bounds(), right.bounds());
+    match op {
+        CsgOp::Union => l.union(&r),
+        CsgOp::Intersection => {
+            let overlap = Bounds::new(Tuple4::max(l.min(), r.min()), Tuple4::min(l.max(), r.max()));
+            if overlap.surface_area() > 0.0 { overlap } else { Bounds::empty() }
+        },
+        // removing from the left operand can only make it smaller
+        CsgOp::Difference => l,
+    }
+}
+
+/// True if a hit on one of the operands is on the surface of the
+/// combined object, given which operand was hit and which operands the
+/// ray is inside at that point.
+pub(crate) fn intersection_allowed(op: CsgOp, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+    match op {
+        CsgOp::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+        CsgOp::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+        CsgOp::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+    }
+}
+
+pub(crate) fn append_csg_intersects<'a>(r: &Ray, obj: &'a Object, vec: &mut Vec<Intersection<'a>>,
+                                        op: CsgOp, left: &'a Object, right: &'a Object) {
+    if bounds::intersect_bounding_box(r, obj.shape.bounds()).is_none() {
+        return;
+    }
+
+    let mut hits = vec![];
+    append_intersects(r, left, &mut hits);
+    let from_left = hits.len();
+    append_intersects(r, right, &mut hits);
+
+    let mut tagged: Vec<(bool, Intersection<'a>)> = hits.into_iter()
+        .enumerate()
+        .map(|(n, i)| (n < from_left, i))
+        .collect();
+    tagged.sort_by(|(_, a), (_, b)| a.t_value().partial_cmp(&b.t_value()).unwrap_or(Ordering::Equal));
+
+    let (mut in_left, mut in_right) = (false, false);
+    // the material of the left operand where the ray last entered it
+    let mut left_medium = None;
+    for (left_hit, mut i) in tagged {
+        if left_hit && !in_left {
+            left_medium = Some(i.medium());
+        }
+        if intersection_allowed(op, left_hit, in_left, in_right) {
+            let m = i.to_group_space() * obj.world_to_object_spc();
+            i.set_to_group_space(m);
+            // an enclosing CSG object replaces this one as the solid
+            i.set_solid(obj);
+            // the right operand only bounds what's left of the left one
+            if let (CsgOp::Difference, false, Some(medium)) = (op, left_hit, left_medium) {
+                i.set_medium(medium);
+            }
+            vec.push(i);
+        }
+        if left_hit {
+            in_left = !in_left;
+        } else {
+            in_right = !in_right;
+        }
+    }
+}
@@ -35,7 +35,7 @@ pub fn normal_of_cube(pos: Tuple4) -> Tuple4 {
 }
 
 
-pub fn intersect_cube(r: &Ray, obj: &Object) -> Option<(Intersection, Intersection)> {
+pub fn intersect_cube<'a>(r: &Ray, obj: &'a Object) -> Option<(Intersection<'a>, Intersection<'a>)> {
     match intersect_bounding_box(r, Bounds::unit()) {
         Some((tmin, tmax)) => {
             Some((intersection(tmin, obj), intersection(tmax, obj)))
@@ -46,10 +46,10 @@ pub fn normal_of_cylinder(lbound: f64, ubound: f64, pos: Tuple4) -> Tuple4 {
 }
 
 
-pub fn append_cyl_intersects(
+pub fn append_cyl_intersects<'a>(
     r: &Ray,
-    cyl: &Object,
-    vec: &mut Vec<Intersection>,
+    cyl: &'a Object,
+    vec: &mut Vec<Intersection<'a>>,
     lower: f64,
     upper: f64)
 {
@@ -69,7 +69,7 @@ pub fn append_cyl_intersects(
     intersect_caps(cyl, r, vec);
 }
 
-fn intersect_caps(cyl: &Object, r: &Ray, vec: &mut Vec<Intersection>) {
+fn intersect_caps<'a>(cyl: &'a Object, r: &Ray, vec: &mut Vec<Intersection<'a>>) {
 
     if let Shape::Cylinder { kind, lbound, ubound } = cyl.shape {
         if kind == CylKind::Open || almost_eq(r.direction.y().abs(), 0.0) {
@@ -95,7 +95,7 @@ fn check_cap(ray: &Ray, t: f64) -> bool {
     x.powi(2) + z.powi(2) <= 1.0
 }
 
-fn intersect_cylinder(ray: &Ray, obj: &Object) -> Option<(Intersection, Intersection)> {
+fn intersect_cylinder<'a>(ray: &Ray, obj: &'a Object) -> Option<(Intersection<'a>, Intersection<'a>)> {
     let a = ray.direction.x().powi(2) + ray.direction.z().powi(2);
 
     if almost_eq(a, 0.0) { return None; }
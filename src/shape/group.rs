@@ -35,7 +35,7 @@ fn max_point(arr: &[Object]) -> Tuple4 {
     p
 }
 
-pub fn append_grp_intersects(r: &Ray, grp: &Object, vec: &mut Vec<Intersection>, children: &[Object]) {
+pub fn append_grp_intersects<'a>(r: &Ray, grp: &Object, vec: &mut Vec<Intersection<'a>>, children: &'a [Object]) {
     if bounds::intersect_bounding_box(r, grp.shape.bounds()).is_none() {
         return;
     }
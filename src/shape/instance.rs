@@ -0,0 +1,38 @@
+use crate::*;
+use std::sync::Arc;
+
+
+/// Places a copy of a shared object, such as a group loaded from a
+/// model file.  Many instances may share one prototype, each with its
+/// own transform, while the prototype is only stored once.
+///
+/// With a material, every surface of the copy has that material
+/// instead of its own, and `set_material` changes it.  Without one,
+/// the copy keeps the prototype's materials.
+///
+/// The world treats an instance as a single object rather than taking
+/// it apart as it does a group, so a large prototype should already be
+/// partitioned, e.g. by `bvh_partition`.
+pub fn instance(prototype: &Arc<Object>, material: Option<Material>) -> Object {
+    Object {
+        world_to_object_spc: identity(),
+        material: material.unwrap_or_else(Material::default),
+        shape: Shape::Instance { prototype: Arc::clone(prototype), overrides: material.is_some() },
+    }
+}
+
+
+pub fn append_instance_intersects<'a>(r: &Ray, inst: &'a Object, vec: &mut Vec<Intersection<'a>>, prototype: &'a Object, overrides: bool) {
+    let first = vec.len();
+    append_intersects(r, prototype, vec);
+    for i in vec[first..].iter_mut() {
+        let m = i.to_group_space() * inst.world_to_object_spc();
+        i.set_to_group_space(m);
+        // an enclosing instance replaces this one as the placement
+        i.set_placement(inst);
+        // an enclosing instance's material replaces this one's
+        if overrides {
+            i.set_material_override(&inst.material);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::*;
+use crate::accel::Bvh;
+use std::fmt;
+use std::sync::Arc;
+
+/// One triangle of a mesh, given as indices into the mesh's buffers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MeshFace {
+    pub vertices: [u32; 3],
+    /// If given, the face is smooth shaded by interpolating the
+    /// normals at its corners.  Otherwise it is flat.
+    pub normals: Option<[u32; 3]>,
+    pub texture_coords: Option<[u32; 3]>,
+}
+
+/// Many triangles sharing buffers of vertices, normals and texture
+/// co-ordinates.
+///
+/// A mesh is far more compact than an `Object` per triangle, each of
+/// which carries its own transform and material.  The faces are kept
+/// in a bounding volume hierarchy of their own, so a mesh with
+/// millions of faces is still quick to intersect.
+#[derive(PartialEq)]
+pub struct Mesh {
+    vertices: Vec<Tuple4>,
+    normals: Vec<Tuple4>,
+    texture_coords: Vec<(f64, f64)>,
+    faces: Vec<MeshFace>,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    /// # Panics
+    ///
+    /// If a face refers to a vertex, normal or texture co-ordinate
+    /// that isn't in the buffers.
+    pub fn new(vertices: Vec<Tuple4>, normals: Vec<Tuple4>, texture_coords: Vec<(f64, f64)>, faces: Vec<MeshFace>) -> Mesh {
+        let in_range = |indices: Option<[u32; 3]>, len: usize| {
+            indices.map(|is| is.iter().all(|&i| (i as usize) < len)).unwrap_or(true)
+        };
+        for f in faces.iter() {
+            assert!(in_range(Some(f.vertices), vertices.len()), "face refers to a missing vertex");
+            assert!(in_range(f.normals, normals.len()), "face refers to a missing normal");
+            assert!(in_range(f.texture_coords, texture_coords.len()), "face refers to a missing texture co-ordinate");
+        }
+
+        let bounds_of = |f: &MeshFace| {
+            let [a, b, c] = f.vertices;
+            let ps = [vertices[a as usize], vertices[b as usize], vertices[c as usize]];
+            Bounds::new(Tuple4::min_all(&ps), Tuple4::max_all(&ps))
+        };
+        let (bvh, faces) = Bvh::build(faces, bounds_of);
+        Mesh { vertices, normals, texture_coords, faces, bvh }
+    }
+
+    /// The number of faces.
+    pub fn len(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    /// The faces, in the order the mesh keeps them, which may differ
+    /// from the order given to `new`.
+    pub fn faces(&self) -> &[MeshFace] {
+        &self.faces
+    }
+
+    pub fn vertices(&self) -> &[Tuple4] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Tuple4] {
+        &self.normals
+    }
+
+    pub fn texture_coords(&self) -> &[(f64, f64)] {
+        &self.texture_coords
+    }
+
+    pub(crate) fn bounds(&self) -> Bounds {
+        self.bvh.bounds()
+    }
+
+    fn corners(&self, face: &MeshFace) -> (Tuple4, Tuple4, Tuple4) {
+        let [a, b, c] = face.vertices;
+        (self.vertices[a as usize], self.vertices[b as usize], self.vertices[c as usize])
+    }
+
+    pub(crate) fn normal_at(&self, hit: &Intersection) -> Tuple4 {
+        let face = &self.faces[hit.face().expect("mesh intersection without a face")];
+        match face.normals {
+            Some([a, b, c]) => {
+                let (u, v) = (hit.u().unwrap(), hit.v().unwrap());
+                let ns = &self.normals;
+                ns[b as usize].scale(u) + ns[c as usize].scale(v) + ns[a as usize].scale(1.0 - u - v)
+            },
+            None => {
+                let (p1, p2, p3) = self.corners(face);
+                (p3 - p1).cross(p2 - p1).normalize()
+            },
+        }
+    }
+
+    pub(crate) fn texture_coords_at(&self, hit: &Intersection) -> Option<(f64, f64)> {
+        let face = &self.faces[hit.face()?];
+        let [a, b, c] = face.texture_coords?;
+        let t = &self.texture_coords;
+        let (u, v) = (hit.u()?, hit.v()?);
+        let w = 1.0 - u - v;
+        let (t1, t2, t3) = (t[a as usize], t[b as usize], t[c as usize]);
+        Some((t1.0 * w + t2.0 * u + t3.0 * v, t1.1 * w + t2.1 * u + t3.1 * v))
+    }
+
+    pub(crate) fn append_intersects<'a>(&self, r: &Ray, obj: &'a Object, vec: &mut Vec<Intersection<'a>>) {
+        self.bvh.traverse(r, |range| {
+            for i in range {
+                let (p1, p2, p3) = self.corners(&self.faces[i]);
+                if let Some((t, u, v)) = super::triangle::intersect_triangle(r, p1, p2 - p1, p3 - p1) {
+                    vec.push(intersection_with_face(t, obj, i, u, v));
+                }
+            }
+        });
+    }
+}
+
+impl fmt::Debug for Mesh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the buffers are far too big to be useful in debug output
+        write!(f, "Mesh {{ faces: {}, vertices: {}, normals: {}, texture_coords: {} }}",
+               self.faces.len(), self.vertices.len(), self.normals.len(), self.texture_coords.len())
+    }
+}
+
+pub fn mesh(m: Mesh) -> Object {
+    Object {
+        world_to_object_spc: identity(),
+        material: Material::default(),
+        shape: Shape::Mesh(Arc::new(m)),
+    }
+}
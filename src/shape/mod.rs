@@ -1,12 +1,19 @@
 use crate::*;
+use std::sync::Arc;
 
 mod sphere;
 mod plane;
 mod cube;
 mod cylinder;
+mod cone;
+mod torus;
+mod planar;
+mod instance;
 mod bounds;
 mod group;
 mod triangle;
+mod mesh;
+mod csg;
 
 pub use sphere::unit_sphere;
 pub use sphere::glass_sphere;
@@ -15,12 +22,22 @@ pub use cube::cube;
 pub use cylinder::CylKind;
 pub use cylinder::cylinder;
 pub use cylinder::inf_cylinder;
+pub use cone::{cone, inf_cone};
+pub use torus::torus;
+pub use planar::{disc, quad, rect};
+pub use instance::instance;
 pub use group::group;
 pub use triangle::triangle;
 pub use triangle::smooth_triangle;
+pub use mesh::{mesh, Mesh, MeshFace};
+pub use csg::{csg, CsgOp};
 pub use bounds::Bounds;
+pub(crate) use bounds::intersect_bounding_box;
 
 
+/// The texture co-ordinates (u, v) at each vertex of a triangle.
+pub type TexCoords = [(f64, f64); 3];
+
 /// Determines what shape an object has.
 ///
 /// Influences the calculation of surface normals and intersections.
@@ -30,9 +47,25 @@ pub enum Shape {
     Plane,
     Cube,
     Cylinder { kind: CylKind, lbound: f64, ubound: f64 },
-    Triangle { p1: Tuple4, p2: Tuple4, p3: Tuple4, e1: Tuple4, e2: Tuple4, normal: Tuple4},
-    SmoothTri { p1: Tuple4, p2: Tuple4, p3: Tuple4, n1: Tuple4, n2: Tuple4, n3: Tuple4 },
+    Cone { kind: CylKind, lbound: f64, ubound: f64 },
+    /// A ring around the y-axis; `major` is the radius of the ring and
+    /// `minor` the radius of its tube.
+    Torus { major: f64, minor: f64 },
+    /// A flat, round disc in the x-z plane.
+    Disc { radius: f64 },
+    /// A flat parallelogram with sides along `u` and `v`.
+    Quad { corner: Tuple4, u: Tuple4, v: Tuple4, normal: Tuple4 },
+    Triangle { p1: Tuple4, p2: Tuple4, p3: Tuple4, e1: Tuple4, e2: Tuple4, normal: Tuple4, uvs: Option<TexCoords> },
+    SmoothTri { p1: Tuple4, p2: Tuple4, p3: Tuple4, n1: Tuple4, n2: Tuple4, n3: Tuple4, uvs: Option<TexCoords> },
     Group { children: Vec<Object>, bounds: Bounds },
+    /// Many triangles sharing their vertices.  Objects may share a
+    /// mesh, placing copies of it in different positions.
+    Mesh(Arc<Mesh>),
+    /// Two objects combined by constructive solid geometry.
+    Csg { op: CsgOp, left: Box<Object>, right: Box<Object>, bounds: Bounds },
+    /// A placement of an object shared with other instances.  If
+    /// `overrides`, the instance's material replaces the prototype's.
+    Instance { prototype: Arc<Object>, overrides: bool },
 }
 
 impl Shape {
@@ -46,16 +79,21 @@ impl Shape {
                 // presume the sphere is centred at (0, 0, 0)
                 position - point(0.0, 0.0, 0.0)
             }
-            Shape::Plane => vector(0.0, 1.0, 0.0),
+            Shape::Plane | Shape::Disc { .. } => vector(0.0, 1.0, 0.0),
             Shape::Cube => cube::normal_of_cube(position),
             Shape::Cylinder { lbound, ubound, ..} => {
                 cylinder::normal_of_cylinder(*lbound, *ubound, position)
             },
-            Shape::Triangle { normal, .. } => *normal,
-            Shape::SmoothTri { p1, p2, p3, n1, n2, n3 } => {
+            Shape::Cone { lbound, ubound, ..} => {
+                cone::normal_of_cone(*lbound, *ubound, position)
+            },
+            Shape::Torus { major, .. } => torus::normal_of_torus(*major, position),
+            Shape::Triangle { normal, .. } | Shape::Quad { normal, .. } => *normal,
+            Shape::SmoothTri { p1, p2, p3, n1, n2, n3, .. } => {
                 triangle::normal_of_smooth_triangle(*p1, *p2, *p3, *n1, *n2, *n3, position, hit)
             },
-            Shape::Group { .. } => {
+            Shape::Mesh(m) => m.normal_at(hit),
+            Shape::Group { .. } | Shape::Csg { .. } | Shape::Instance { .. } => {
                 unimplemented!()
             },
         }
@@ -67,6 +105,10 @@ impl Shape {
             Shape::Cube => Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0)),
             Shape::Cylinder {lbound, ubound, ..} =>
                 Bounds::new(point(-1.0, *lbound, -1.0), point(1.0, *ubound, 1.0)),
+            Shape::Cone {lbound, ubound, ..} => cone::bounds_of_cone(*lbound, *ubound),
+            Shape::Torus {major, minor} => torus::bounds_of_torus(*major, *minor),
+            Shape::Disc {radius} => planar::bounds_of_disc(*radius),
+            Shape::Quad {corner, u, v, ..} => planar::bounds_of_quad(*corner, *u, *v),
             Shape::Plane => {
                 Bounds::new(
                     point(std::f64::NEG_INFINITY, std::f64::NEG_INFINITY, std::f64::NEG_INFINITY),
@@ -80,6 +122,9 @@ impl Shape {
                 Bounds::new(Tuple4::min_all(&[*p1, *p2, *p3]), Tuple4::max_all(&[*p1, *p2, *p3]))
             },
             Shape::Group {children: _, bounds} => *bounds,
+            Shape::Mesh(m) => m.bounds(),
+            Shape::Csg { bounds, .. } => *bounds,
+            Shape::Instance { prototype, .. } => prototype.bounds(),
         }
     }
 }
@@ -93,7 +138,14 @@ impl std::fmt::Display for Shape {
             Shape::Triangle {..} => write!(f, "Triangle"),
             Shape::SmoothTri {..} => write!(f, "SmoothTri"),
             Shape::Cylinder {..} => write!(f, "Cylinder"),
+            Shape::Cone {..} => write!(f, "Cone"),
+            Shape::Torus {..} => write!(f, "Torus"),
+            Shape::Disc {..} => write!(f, "Disc"),
+            Shape::Quad {..} => write!(f, "Quad"),
             Shape::Group {children, ..} => write!(f, "Group ({})", children.len()),
+            Shape::Mesh(m) => write!(f, "Mesh ({})", m.len()),
+            Shape::Csg { op, .. } => write!(f, "Csg ({:?})", op),
+            Shape::Instance { prototype, .. } => write!(f, "Instance ({})", prototype.shape),
         }
     }
 }
@@ -112,6 +164,10 @@ pub struct Object {
 }
 
 impl Object {
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
     /// The transformation matrix to convert co-ordinates from
     /// object space to world space.
     pub fn object_to_world_spc(&self) -> Matrix {
@@ -137,6 +193,11 @@ impl Object {
         self.material
     }
 
+    /// The material, without copying it.
+    pub(crate) fn material_ref(&self) -> &Material {
+        &self.material
+    }
+
     pub fn set_material(self: &mut Self, m: Material) -> &mut Self {
         self.material = m;
         self
@@ -156,9 +217,59 @@ impl Object {
     }
 
     pub fn material_colour_at(self: &Self, world_point: Tuple4) -> RGB {
-        let to_pattern_space = self.material().object_to_pattern_spc() * self.world_to_object_spc();
+        let to_pattern_space = self.material.object_to_pattern_spc() * self.world_to_object_spc();
         let p = to_pattern_space.mult(world_point);
-        self.material().pattern().colour_at(p)
+        self.material.pattern.colour_at(p)
+    }
+
+    /// Sets the texture co-ordinates of the vertices of a triangle, so
+    /// an image texture using `UvMapping::Vertices` can be stretched
+    /// across it.  Has no effect on other shapes.
+    pub fn set_texture_coords(&mut self, coords: TexCoords) -> &mut Self {
+        match &mut self.shape {
+            Shape::Triangle { uvs, .. } | Shape::SmoothTri { uvs, .. } => *uvs = Some(coords),
+            _ => (),
+        }
+        self
+    }
+
+    /// The texture co-ordinates at the point hit, interpolated from
+    /// those at the vertices, or None if the object has none.
+    pub fn texture_coords_at(&self, hit: &Intersection) -> Option<(f64, f64)> {
+        match &self.shape {
+            Shape::Triangle { uvs: Some(t), .. } | Shape::SmoothTri { uvs: Some(t), .. } => {
+                let (u, v) = (hit.u()?, hit.v()?);
+                let w = 1.0 - u - v;
+                Some((
+                    t[0].0 * w + t[1].0 * u + t[2].0 * v,
+                    t[0].1 * w + t[1].1 * u + t[2].1 * v,
+                ))
+            },
+            Shape::Mesh(m) => m.texture_coords_at(hit),
+            Shape::Torus { .. } | Shape::Disc { .. } | Shape::Quad { .. } => hit.u().zip(hit.v()),
+            _ => None,
+        }
+    }
+
+    /// The colour of the material at the point, taking the texture
+    /// co-ordinates into account when the pattern makes use of them.
+    pub fn material_colour_at_uv(&self, world_point: Tuple4, uv: Option<(f64, f64)>) -> RGB {
+        self.material_colour_at_surface(world_point, uv, None)
+    }
+
+    /// Like `material_colour_at_uv`, also taking into account where on
+    /// a triangle the point is, for patterns that blend vertex colours.
+    pub fn material_colour_at_surface(&self, world_point: Tuple4, uv: Option<(f64, f64)>, barycentric: Option<(f64, f64)>) -> RGB {
+        self.colour_of_material_at(&self.material, world_point, uv, barycentric)
+    }
+
+    /// Like `material_colour_at_surface`, for a material other than
+    /// the object's own, placed on the object as its own would be.  The
+    /// point is in the space the object's transform is relative to.
+    pub(crate) fn colour_of_material_at(&self, material: &Material, point: Tuple4, uv: Option<(f64, f64)>, barycentric: Option<(f64, f64)>) -> RGB {
+        let to_pattern_space = material.object_to_pattern_spc() * self.world_to_object_spc();
+        let p = to_pattern_space.mult(point);
+        material.pattern.colour_at_surface(p, uv, barycentric)
     }
 
     pub fn children(&self) -> &[Object] {
@@ -200,6 +311,14 @@ impl Object {
         Bounds::new(minp, maxp)
     }
 
+    /// The object this is a copy of, if it is an instance.
+    pub fn prototype(&self) -> Option<&Arc<Object>> {
+        match &self.shape {
+            Shape::Instance { prototype, .. } => Some(prototype),
+            _ => None,
+        }
+    }
+
     pub fn is_group(&self) -> bool {
         match self.shape {
             Shape::Group {..} => true,
@@ -215,39 +334,59 @@ impl std::fmt::Display for Object {
 }
 
 // TODO this should be an internal function, not public.
-pub fn append_intersects(orig: &Ray, s: &Object, vec: &mut Vec<Intersection>) {
+pub fn append_intersects<'a>(orig: &Ray, s: &'a Object, vec: &mut Vec<Intersection<'a>>) {
     let to_object_space = s.world_to_object_spc();
-    let r = orig.transform(&to_object_space);
+    append_local_intersects(&orig.transform(&to_object_space), s, vec)
+}
+
+/// Like `append_intersects`, for a ray already in the object's own
+/// space.
+pub(crate) fn append_local_intersects<'a>(r: &Ray, s: &'a Object, vec: &mut Vec<Intersection<'a>>) {
     let shape = &s.shape;
     match shape {
         Shape::Sphere => {
-            if let Some((a, b)) = sphere::intersect_sphere(&r, s) {
+            if let Some((a, b)) = sphere::intersect_sphere(r, s) {
                 vec.push(a);
                 vec.push(b);
             }
         },
         Shape::Plane => {
-            if let Some(a) = plane::intersect_plane(&r, s) {
+            if let Some(a) = plane::intersect_plane(r, s) {
                 vec.push(a);
             }
         },
         Shape::Cube => {
-            if let Some((a, b)) = cube::intersect_cube(&r, s) {
+            if let Some((a, b)) = cube::intersect_cube(r, s) {
                 vec.push(a);
                 vec.push(b);
             }
         },
         Shape::Cylinder { lbound, ubound, .. } => {
-            cylinder::append_cyl_intersects(&r, s, vec, *lbound, *ubound)
+            cylinder::append_cyl_intersects(r, s, vec, *lbound, *ubound)
+        },
+        Shape::Cone { kind, lbound, ubound } => {
+            cone::append_cone_intersects(r, s, vec, *kind, *lbound, *ubound)
+        },
+        Shape::Torus { major, minor } => {
+            torus::append_torus_intersects(r, s, vec, *major, *minor)
+        },
+        Shape::Disc { radius } => planar::append_disc_intersects(r, s, vec, *radius),
+        Shape::Quad { corner, u, v, .. } => {
+            planar::append_quad_intersects(r, s, vec, *corner, *u, *v)
         },
         Shape::Group {children, ..} => {
-            group::append_grp_intersects(&r, s, vec, &children)
+            group::append_grp_intersects(r, s, vec, &children)
         },
         Shape::Triangle {p1, p2, p3, e1, e2, ..} => {
-            triangle::append_tri_intersects(&r, s, vec, *p1, *p2, *p3, *e1, *e2)
+            triangle::append_tri_intersects(r, s, vec, *p1, *p2, *p3, *e1, *e2)
         },
         Shape::SmoothTri {p1, p2, p3, ..} => {
-            triangle::append_tri_intersects(&r, s, vec, *p1, *p2, *p3, *p2 - *p1, *p3 - *p1)
+            triangle::append_tri_intersects(r, s, vec, *p1, *p2, *p3, *p2 - *p1, *p3 - *p1)
+        },
+        Shape::Mesh(m) => m.append_intersects(r, s, vec),
+        Shape::Csg { op, left, right, .. } => csg::append_csg_intersects(r, s, vec, *op, left, right),
+        Shape::Instance { prototype, overrides } => {
+            instance::append_instance_intersects(r, s, vec, prototype, *overrides)
         },
     }
 }
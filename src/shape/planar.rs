@@ -0,0 +1,85 @@
+use crate::*;
+
+/// Creates a disc of the given radius in the x-z plane, centred at the
+/// origin and facing up the y-axis.
+///
+/// Unlike a plane, it is finite, so it can be partitioned.
+pub fn disc(radius: f64) -> Object {
+    Object {
+        world_to_object_spc: identity(),
+        material: Material::default(),
+        shape: Shape::Disc { radius },
+    }
+}
+
+/// Creates a rectangle of the given width along the x-axis and depth
+/// along the z-axis, in the x-z plane, centred at the origin and facing
+/// up the y-axis.
+///
+/// Its texture co-ordinates run from (0, 0) at its corner nearest
+/// (-x, +z) to (1, 1) at its corner nearest (+x, -z), as for a disc.
+pub fn rect(width: f64, depth: f64) -> Object {
+    quad(point(-width / 2.0, 0.0, depth / 2.0), vector(width, 0.0, 0.0), vector(0.0, 0.0, -depth))
+}
+
+/// Creates a parallelogram with one corner at `corner` and sides along
+/// the edges `u` and `v`.  It faces along u × v.
+///
+/// Its texture co-ordinates are how far along each edge the point is,
+/// so (0, 0) at `corner` and (1, 1) at the opposite one.
+pub fn quad(corner: Tuple4, u: Tuple4, v: Tuple4) -> Object {
+    Object {
+        world_to_object_spc: identity(),
+        material: Material::default(),
+        shape: Shape::Quad { corner, u, v, normal: u.cross(v).normalize() },
+    }
+}
+
+
+pub fn bounds_of_disc(radius: f64) -> Bounds {
+    Bounds::new(point(-radius, 0.0, -radius), point(radius, 0.0, radius))
+}
+
+pub fn bounds_of_quad(corner: Tuple4, u: Tuple4, v: Tuple4) -> Bounds {
+    let corners = [corner, corner + u, corner + v, corner + u + v];
+    Bounds::new(Tuple4::min_all(&corners), Tuple4::max_all(&corners))
+}
+
+
+pub fn append_disc_intersects<'a>(r: &Ray, disc: &'a Object, vec: &mut Vec<Intersection<'a>>, radius: f64) {
+    if r.direction.y().abs() < EPSILON {
+        return;
+    }
+    let t = -r.origin.y() / r.direction.y();
+    let p = r.position(t);
+    if p.x().powi(2) + p.z().powi(2) <= radius.powi(2) {
+        let size = 2.0 * radius;
+        vec.push(intersection_with_uv(t, disc, p.x() / size + 0.5, 0.5 - p.z() / size));
+    }
+}
+
+pub fn append_quad_intersects<'a>(
+    r: &Ray,
+    quad: &'a Object,
+    vec: &mut Vec<Intersection<'a>>,
+    corner: Tuple4,
+    u: Tuple4,
+    v: Tuple4)
+{
+    let n = u.cross(v);
+    let denom = n.dot(r.direction);
+    if denom.abs() < EPSILON * n.magnitude() * r.direction.magnitude() {
+        return;
+    }
+    let t = n.dot(corner - r.origin) / denom;
+
+    // how far along each edge the point is, found by projecting onto
+    // the plane of the quad
+    let w = r.position(t) - corner;
+    let nn = n.dot(n);
+    let a = n.dot(w.cross(v)) / nn;
+    let b = n.dot(u.cross(w)) / nn;
+    if (0.0..=1.0).contains(&a) && (0.0..=1.0).contains(&b) {
+        vec.push(intersection_with_uv(t, quad, a, b));
+    }
+}
@@ -9,7 +9,7 @@ pub fn plane() -> Object {
     }
 }
 
-pub fn intersect_plane(r: &Ray, s: &Object) -> Option<Intersection> {
+pub fn intersect_plane<'a>(r: &Ray, s: &'a Object) -> Option<Intersection<'a>> {
     if r.direction.y().abs() < EPSILON {
         None
     } else {
@@ -22,7 +22,7 @@ pub fn glass_sphere() -> Object {
 }
 
 
-pub fn intersect_sphere(r: &Ray, sphere: &Object) -> Option<(Intersection, Intersection)> {
+pub fn intersect_sphere<'a>(r: &Ray, sphere: &'a Object) -> Option<(Intersection<'a>, Intersection<'a>)> {
     // presume the sphere is centred at (0,0,0)
     let s_to_ray = r.origin - point(0.0, 0.0, 0.0);
     let a = r.direction.dot(r.direction);
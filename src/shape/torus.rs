@@ -0,0 +1,88 @@
+use crate::*;
+use crate::math::solve_quartic;
+use std::f64::consts::PI;
+
+
+/// Creates a torus around the y-axis, centred at the origin.
+///
+/// Imagine a circle of radius `minor` in the x-y plane, centred at
+/// (`major`, 0, 0), spun around the y-axis.
+pub fn torus(major: f64, minor: f64) -> Object {
+    Object {
+        world_to_object_spc: identity(),
+        material: Material::default(),
+        shape: Shape::Torus { major, minor },
+    }
+}
+
+
+pub fn bounds_of_torus(major: f64, minor: f64) -> Bounds {
+    let r = major + minor;
+    Bounds::new(point(-r, -minor, -r), point(r, minor, r))
+}
+
+
+pub fn normal_of_torus(major: f64, pos: Tuple4) -> Tuple4 {
+    let rho = (pos.x().powi(2) + pos.z().powi(2)).sqrt();
+    if rho == 0.0 {
+        return vector(0.0, pos.y().signum(), 0.0);
+    }
+    // away from the nearest point on the circle through the middle of
+    // the tube
+    let centre = point(pos.x() * major / rho, 0.0, pos.z() * major / rho);
+    pos - centre
+}
+
+
+/// The texture co-ordinates of a point on the torus.  u goes around
+/// the y-axis like a cylindrical map, and v goes around the tube,
+/// starting from its outer edge and passing over the top.
+pub fn torus_map(major: f64, pos: Tuple4) -> (f64, f64) {
+    let theta = pos.x().atan2(pos.z());
+    let u = 1.0 - (theta / (2.0 * PI) + 0.5);
+    let rho = (pos.x().powi(2) + pos.z().powi(2)).sqrt();
+    let phi = pos.y().atan2(rho - major);
+    (u.rem_euclid(1.0), (phi / (2.0 * PI)).rem_euclid(1.0))
+}
+
+
+pub fn append_torus_intersects<'a>(
+    r: &Ray,
+    torus: &'a Object,
+    vec: &mut Vec<Intersection<'a>>,
+    major: f64,
+    minor: f64)
+{
+    // The coefficients of the quartic grow with the distance to the
+    // torus, losing the precision that grazing rays need.  So solve
+    // along a unit direction, starting from where the ray meets a
+    // sphere around the torus, and convert back afterwards.
+    let len = r.direction.magnitude();
+    let d = r.direction.scale(1.0 / len);
+    let o = r.origin - point(0.0, 0.0, 0.0);
+    let outer = major + minor;
+    let b = o.dot(d);
+    let disc = b * b - (o.dot(o) - outer * outer);
+    if disc < 0.0 {
+        return;
+    }
+    let start = -b - disc.sqrt();
+    let o = o + d.scale(start);
+
+    let e = o.dot(o) + major * major - minor * minor;
+    let f = o.dot(d);
+    let four_r2 = 4.0 * major * major;
+    let roots = solve_quartic(
+        1.0,
+        4.0 * f,
+        4.0 * f * f + 2.0 * e - four_r2 * (d.x().powi(2) + d.z().powi(2)),
+        4.0 * f * e - 2.0 * four_r2 * (o.x() * d.x() + o.z() * d.z()),
+        e * e - four_r2 * (o.x().powi(2) + o.z().powi(2)),
+    );
+
+    for s in roots {
+        let p = point(0.0, 0.0, 0.0) + o + d.scale(s);
+        let (u, v) = torus_map(major, p);
+        vec.push(intersection_with_uv((start + s) / len, torus, u, v));
+    }
+}
@@ -7,36 +7,45 @@ pub fn triangle(p1: Tuple4, p2: Tuple4, p3: Tuple4) -> Object {
     Object {
         world_to_object_spc: identity(),
         material: Material::default(),
-        shape: Shape::Triangle {p1, p2, p3, e1, e2, normal},
+        shape: Shape::Triangle {p1, p2, p3, e1, e2, normal, uvs: None},
     }
 }
 
-pub fn append_tri_intersects(
-    r: &Ray, obj: &Object, vec: &mut Vec<Intersection>,
+pub fn append_tri_intersects<'a>(
+    r: &Ray, obj: &'a Object, vec: &mut Vec<Intersection<'a>>,
     p1: Tuple4, _p2: Tuple4, _p3: Tuple4,
     e1: Tuple4, e2: Tuple4)
 {
+    if let Some((t, u, v)) = intersect_triangle(r, p1, e1, e2) {
+        vec.push(intersection_with_uv(t, obj, u, v));
+    }
+}
+
+/// Finds where the ray hits the triangle with corner p1 and edges e1
+/// and e2 leading from it.  Returns the distance along the ray, and
+/// the barycentric co-ordinates (u, v) of the hit.
+pub(crate) fn intersect_triangle(r: &Ray, p1: Tuple4, e1: Tuple4, e2: Tuple4) -> Option<(f64, f64, f64)> {
     let c = r.direction.cross(e2);
     let det = e1.dot(c);
-    if det.abs() >= EPSILON {
-        let f = 1.0 / det;
-        let p1_to_origin = r.origin - p1;
-        let u = f * p1_to_origin.dot(c);
-
-        if u < 0.0 || u > 1.0 {
-            return;
-        }
-
-        let origin_cross_e1 = p1_to_origin.cross(e1);
-        let v = f * r.direction.dot(origin_cross_e1);
+    if det.abs() < EPSILON {
+        return None;
+    }
 
-        if v < 0.0 || (u + v) > 1.0 {
-            return;
-        }
+    let f = 1.0 / det;
+    let p1_to_origin = r.origin - p1;
+    let u = f * p1_to_origin.dot(c);
+    if u < 0.0 || u > 1.0 {
+        return None;
+    }
 
-        let t = f * e2.dot(origin_cross_e1);
-        vec.push(intersection_with_uv(t, obj, u, v));
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * r.direction.dot(origin_cross_e1);
+    if v < 0.0 || (u + v) > 1.0 {
+        return None;
     }
+
+    let t = f * e2.dot(origin_cross_e1);
+    Some((t, u, v))
 }
 
 pub fn normal_of_smooth_triangle(
@@ -55,7 +64,7 @@ pub fn smooth_triangle(p1: Tuple4, p2: Tuple4, p3: Tuple4, n1: Tuple4, n2: Tuple
         world_to_object_spc: identity(),
         material: Material::default(),
         shape: Shape::SmoothTri {
-            p1, p2, p3, n1, n2, n3,
+            p1, p2, p3, n1, n2, n3, uvs: None,
         }
     }
 }
@@ -0,0 +1,191 @@
+//! Reading of stereolithography (stl) files, in either their ASCII or
+//! binary form.
+use crate::*;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The input could not be read.
+    Io,
+    /// A binary file ended before all the triangles its header
+    /// declares.
+    Truncated { declared: u32, found: u32 },
+    /// An ASCII file ended part way through a facet.
+    UnexpectedEnd,
+    /// A line of an ASCII file could not be understood.  Lines are
+    /// numbered from 1.
+    BadInstruction { line: usize, text: String, reason: Reason },
+}
+
+/// Why a line of an ASCII file could not be understood.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    /// The given text should have been a number.
+    BadNumber(String),
+    /// The instruction has too few values.
+    MissingValues,
+    /// The instruction isn't one that belongs in an stl file.
+    UnknownInstruction(String),
+    /// The instruction is out of place, such as a vertex outside of a
+    /// facet.
+    OutOfPlace(String),
+    /// A facet has other than three vertices.
+    NotATriangle(usize),
+}
+
+impl Error for ParseError {
+
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io => write!(f, "the input could not be read"),
+            ParseError::Truncated { declared, found } =>
+                write!(f, "expected {} triangles but found only {}", declared, found),
+            ParseError::UnexpectedEnd => write!(f, "the input ended part way through a facet"),
+            ParseError::BadInstruction { line, text, reason } =>
+                write!(f, "line {}: {}: {}", line, reason, text),
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::BadNumber(x) => write!(f, "'{}' is not a number", x),
+            Reason::MissingValues => write!(f, "too few values"),
+            Reason::UnknownInstruction(x) => write!(f, "'{}' is not an stl instruction", x),
+            Reason::OutOfPlace(x) => write!(f, "'{}' is out of place", x),
+            Reason::NotATriangle(n) => write!(f, "a facet needs three vertices, not {}", n),
+        }
+    }
+}
+
+/// The corners of a facet.
+type Facet = [Tuple4; 3];
+
+/// Reads the triangles described by an stl file, whether ASCII or
+/// binary.  The facet normals in the file are ignored, as many
+/// programs leave them zeroed; the normals are found from the
+/// vertices instead.
+pub fn read_object_vec(input: &mut dyn io::Read) -> Result<Vec<Object>, ParseError> {
+    let facets = read_facets(input)?;
+    Ok(facets.iter().map(|[p1, p2, p3]| triangle(*p1, *p2, *p3)).collect())
+}
+
+/// Reads an stl file into a single mesh, which takes far less memory
+/// than an object per triangle.
+pub fn read_mesh(input: &mut dyn io::Read) -> Result<Object, ParseError> {
+    let facets = read_facets(input)?;
+    let vertices: Vec<Tuple4> = facets.iter().flat_map(|f| f.iter().copied()).collect();
+    let faces = (0..facets.len() as u32)
+        .map(|i| MeshFace { vertices: [3 * i, 3 * i + 1, 3 * i + 2], normals: None, texture_coords: None })
+        .collect();
+    Ok(mesh(Mesh::new(vertices, vec![], vec![], faces)))
+}
+
+fn read_facets(input: &mut dyn io::Read) -> Result<Vec<Facet>, ParseError> {
+    let mut bytes = vec![];
+    input.read_to_end(&mut bytes).map_err(|_| ParseError::Io)?;
+
+    if is_binary(&bytes) {
+        read_binary(&bytes)
+    } else {
+        let text = std::str::from_utf8(&bytes).map_err(|_| ParseError::Io)?;
+        read_ascii(text)
+    }
+}
+
+const HEADER_LEN: usize = 80;
+const FACET_LEN: usize = 50;
+
+/// Binary files may also begin with "solid", even though they
+/// shouldn't, so the size the header implies is the surer sign.
+/// Anything too short to have a header is taken to be text.
+fn is_binary(bytes: &[u8]) -> bool {
+    let count = match declared_count(bytes) {
+        Some(count) => count,
+        None => return false,
+    };
+    if bytes.len() == HEADER_LEN + 4 + FACET_LEN * count as usize {
+        return true;
+    }
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    !bytes[start..].starts_with(b"solid")
+}
+
+fn declared_count(bytes: &[u8]) -> Option<u32> {
+    let count = bytes.get(HEADER_LEN..HEADER_LEN + 4)?;
+    Some(u32::from_le_bytes(count.try_into().unwrap()))
+}
+
+fn read_binary(bytes: &[u8]) -> Result<Vec<Facet>, ParseError> {
+    let declared = declared_count(bytes).expect("binary stl without a header");
+    let body = &bytes[HEADER_LEN + 4..];
+    let found = (body.len() / FACET_LEN) as u32;
+    if found < declared {
+        return Err(ParseError::Truncated { declared, found });
+    }
+
+    let f32_at = |record: &[u8], i: usize| {
+        f32::from_le_bytes(record[4 * i..4 * i + 4].try_into().unwrap()) as f64
+    };
+    let facets = body.chunks_exact(FACET_LEN)
+        .take(declared as usize)
+        .map(|record| {
+            // the normal comes first, then the vertices, then two
+            // bytes of attributes that we've no use for
+            let corner = |n: usize| point(f32_at(record, 3 * n), f32_at(record, 3 * n + 1), f32_at(record, 3 * n + 2));
+            [corner(1), corner(2), corner(3)]
+        })
+        .collect();
+    Ok(facets)
+}
+
+const INSTRUCTIONS: [&str; 7] = ["solid", "endsolid", "facet", "outer", "endloop", "vertex", "endfacet"];
+
+fn read_ascii(text: &str) -> Result<Vec<Facet>, ParseError> {
+    let mut facets = vec![];
+    let mut corners: Option<Vec<Tuple4>> = None;
+
+    for (n, line) in text.lines().enumerate() {
+        let bad = |reason| ParseError::BadInstruction { line: n + 1, text: line.to_string(), reason };
+        let mut words = line.split_whitespace();
+        match (words.next(), corners.as_mut()) {
+            (None, _) => (),
+            (Some("solid"), None) | (Some("endsolid"), None) => (),
+            (Some("facet"), None) => corners = Some(Vec::with_capacity(3)),
+            (Some("outer"), Some(_)) | (Some("endloop"), Some(_)) => (),
+            (Some("vertex"), Some(cs)) => {
+                let p = read_point(words).map_err(bad)?;
+                cs.push(p);
+            },
+            (Some("endfacet"), Some(cs)) => {
+                if cs.len() != 3 {
+                    return Err(bad(Reason::NotATriangle(cs.len())));
+                }
+                facets.push([cs[0], cs[1], cs[2]]);
+                corners = None;
+            },
+            (Some(x), _) if INSTRUCTIONS.contains(&x) => return Err(bad(Reason::OutOfPlace(x.to_string()))),
+            (Some(x), _) => return Err(bad(Reason::UnknownInstruction(x.to_string()))),
+        }
+    }
+
+    if corners.is_some() {
+        return Err(ParseError::UnexpectedEnd);
+    }
+    Ok(facets)
+}
+
+fn read_point<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<Tuple4, Reason> {
+    let mut next = || {
+        let w = words.next().ok_or(Reason::MissingValues)?;
+        w.parse::<f64>().map_err(|_| Reason::BadNumber(w.to_string()))
+    };
+    Ok(point(next()?, next()?, next()?))
+}
@@ -63,8 +63,8 @@ fn refracted_colour_of_opaque_surface_is_black() {
 fn refracted_colour_at_max_recursive_depth_is_black() {
     let mut w = World::default();
     let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
-    let shape: &mut Object = &mut w.objects[0];
-    shape.mut_material().set_transparency(1.0).set_refractive_index(1.5);
+    w.objects[0].mut_material().set_transparency(1.0).set_refractive_index(1.5);
+    let shape = &w.objects[0];
 
     let xs = vec![intersection(4.0, &shape), intersection(6.0, &shape)];
     let comps = hit_data(&r, 0, &xs);
@@ -75,8 +75,8 @@ fn refracted_colour_at_max_recursive_depth_is_black() {
 fn refracted_colour_not_at_max_recursive_depth_is_not_black() {
     let mut w = World::default();
     let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
-    let shape: &mut Object = &mut w.objects[0];
-    shape.mut_material().set_transparency(1.0).set_refractive_index(1.5);
+    w.objects[0].mut_material().set_transparency(1.0).set_refractive_index(1.5);
+    let shape = &w.objects[0];
 
     let xs = vec![intersection(4.0, &shape), intersection(6.0, &shape)];
     let comps = hit_data(&r, 0, &xs);
@@ -88,8 +88,8 @@ const ROOT2_BY_2: f64 = SQRT_2 / 2.0;
 #[test]
 fn refracted_colour_under_total_internal_reflection_is_black() {
     let mut w = World::default();
-    let shape: &mut Object = &mut w.objects[0];
-    shape.mut_material().set_transparency(1.0).set_refractive_index(1.5);
+    w.objects[0].mut_material().set_transparency(1.0).set_refractive_index(1.5);
+    let shape = &w.objects[0];
     let r = ray(point(0.0, 0.0, ROOT2_BY_2), vector(0.0, 1.0, 0.0));
 
     let xs = vec![intersection(-ROOT2_BY_2, &shape), intersection(ROOT2_BY_2, &shape)];
@@ -195,3 +195,65 @@ fn shade_hit_with_reflective_transparent_material() {
     assert_eq!(shade_hit(&w, &comps, 5), colour(1.11500, 0.69643, 0.69243));
 
 }
+
+#[test]
+fn csg_lens_is_a_single_container() {
+    let mut a = glass_sphere();
+    a.set_object_to_world_spc(translation(0.0, 0.0, 0.5));
+    let mut b = glass_sphere();
+    b.set_object_to_world_spc(translation(0.0, 0.0, -0.5));
+    let w = World::with(vec![], vec![csg(CsgOp::Intersection, a, b)]);
+
+    let r = ray(point(0.0, 0.0, -4.0), vector(0.0, 0.0, 1.0));
+    let xs = w.intersect(&r);
+    assert_eq!(xs.len(), 2);
+
+    let entering = hit_data(&r, 0, &xs);
+    assert_eq!((entering.n1, entering.n2), (1.0, 1.5));
+    // leaving through the other sphere's surface is still leaving the lens
+    let leaving = hit_data(&r, 1, &xs);
+    assert_eq!((leaving.n1, leaving.n2), (1.5, 1.0));
+}
+
+#[test]
+fn hollow_glass_is_entered_through_its_inner_wall() {
+    let bowl = glass_sphere();
+    let mut hollow = unit_sphere();
+    hollow.set_object_to_world_spc(scaling(0.5, 0.5, 0.5));
+    let w = World::with(vec![], vec![csg(CsgOp::Difference, bowl, hollow)]);
+
+    let r = ray(point(0.0, 0.0, -4.0), vector(0.0, 0.0, 1.0));
+    let xs = w.intersect(&r);
+    assert_eq!(xs.len(), 4);
+
+    let indices: Vec<(f64, f64)> = (0..4).map(|i| {
+        let comps = hit_data(&r, i, &xs);
+        (comps.n1, comps.n2)
+    }).collect();
+    // the glass on the far side of the inner wall is the bowl's, not
+    // the hollow's
+    assert_eq!(indices, vec![(1.0, 1.5), (1.5, 1.0), (1.0, 1.5), (1.5, 1.0)]);
+}
+
+#[test]
+fn overlapping_instances_of_one_prototype_are_separate_containers() {
+    let prototype = std::sync::Arc::new(glass_sphere());
+    let mut denser = glass_sphere().material();
+    denser.set_refractive_index(2.0);
+    let mut near = instance(&prototype, None);
+    near.set_object_to_world_spc(translation(0.0, 0.0, -0.5));
+    let mut far = instance(&prototype, Some(denser));
+    far.set_object_to_world_spc(translation(0.0, 0.0, 0.5));
+    let w = World::with(vec![], vec![near, far]);
+
+    let r = ray(point(0.0, 0.0, -4.0), vector(0.0, 0.0, 1.0));
+    let xs = w.intersect(&r);
+    assert_eq!(xs.len(), 4);
+
+    let indices: Vec<(f64, f64)> = (0..4).map(|i| {
+        let comps = hit_data(&r, i, &xs);
+        (comps.n1, comps.n2)
+    }).collect();
+    // entering the far copy doesn't take the ray out of the near one
+    assert_eq!(indices, vec![(1.0, 1.5), (1.5, 2.0), (2.0, 2.0), (2.0, 1.0)]);
+}
@@ -20,7 +20,7 @@ fn precompute_state_of_intersection() {
     let i = intersection(4.0, &shape);
     let comps = singleton_hit_data(&r, &i);
     assert_eq!(comps.t_value, 4.0);
-    assert_eq!(comps.object, shape);
+    assert_eq!(comps.object, &shape);
     assert_eq!(comps.point, point(0.0, 0.0, -1.0));
     assert_eq!(comps.eyev, vector(0.0, 0.0, -1.0));
     assert_eq!(comps.normalv, vector(0.0, 0.0, -1.0));
@@ -42,7 +42,7 @@ fn the_hit_when_an_intersection_occurs_on_inside() {
     let i = intersection(1.0, &shape);
     let comps = singleton_hit_data(&r, &i);
     assert_eq!(comps.t_value, 1.0);
-    assert_eq!(comps.object, shape);
+    assert_eq!(comps.object, &shape);
     assert_eq!(comps.point, point(0.0, 0.0, 1.0));
     assert_eq!(comps.eyev, vector(0.0, 0.0, -1.0));
     assert_eq!(comps.normalv, vector(0.0, 0.0, -1.0));
@@ -73,3 +73,43 @@ fn shade_an_intersection_point_from_inside() {
     assert_eq!(c, colour(0.90498, 0.90498, 0.90498));
 }
 
+
+#[test]
+fn vertex_colours_are_only_blended_on_triangles() {
+    let (red, green, blue) = (colour(1.0, 0.0, 0.0), colour(0.0, 1.0, 0.0), colour(0.0, 0.0, 1.0));
+    let r = ray(point(0.25, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+
+    let mut tri = triangle(point(0.0, 0.0, 1.0), point(1.0, 0.0, 0.0), point(0.0, 0.0, -1.0));
+    tri.mut_material().set_pattern(Pattern::vertex_colours(red, green, blue));
+    let mut xs = vec![];
+    append_intersects(&r, &tri, &mut xs);
+    assert!(singleton_hit_data(&r, &xs[0]).barycentric.is_some());
+
+    // its texture co-ordinates aren't corner weights
+    let mut d = disc(1.0);
+    d.mut_material().set_pattern(Pattern::vertex_colours(red, green, blue));
+    let mut xs = vec![];
+    append_intersects(&r, &d, &mut xs);
+    assert_eq!(singleton_hit_data(&r, &xs[0]).barycentric, None);
+}
+
+#[test]
+fn patterns_are_found_in_group_space_only_for_instances() {
+    let mut s = unit_sphere();
+    s.mut_material().set_pattern(Pattern::stripes(RGB::white(), RGB::black()));
+    let mut g = group(vec![s.clone()]);
+    g.set_object_to_world_spc(translation(0.5, 0.0, 0.0));
+    let mut inst = instance(&std::sync::Arc::new(s), None);
+    inst.set_object_to_world_spc(translation(0.5, 0.0, 0.0));
+    let r = ray(point(0.5, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+    let grouped = World::with(vec![], vec![g]);
+    let xs = grouped.intersect(&r);
+    let comps = hit_data(&r, 0, &xs);
+    assert_eq!(comps.parent_over_point, comps.over_point);
+
+    let placed = World::with(vec![], vec![inst]);
+    let xs = placed.intersect(&r);
+    let comps = hit_data(&r, 0, &xs);
+    assert_eq!(comps.parent_over_point, comps.over_point - vector(0.5, 0.0, 0.0));
+}
@@ -0,0 +1,214 @@
+use crate::*;
+
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use serde::Deserialize;
+
+/// Dictates how a point on the surface of an object is mapped to
+/// a position (u, v) on a flat, two dimensional texture.  Both u and
+/// v are in the range [0.0, 1.0), with (0, 0) at the bottom left of
+/// the texture.
+///
+/// The point is taken in pattern space, so a pattern transform can
+/// be used to move, scale or rotate the texture across the surface.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UvMapping {
+    /// Wraps the texture around a unit sphere centred at the origin,
+    /// like the lines of latitude and longitude on a globe.
+    Spherical,
+    /// Tiles the texture across the xz plane, repeating every unit.
+    Planar,
+    /// Wraps the texture around a unit cylinder about the y axis,
+    /// repeating every unit along the axis.
+    Cylindrical,
+    /// Places a part of the texture on each face of a cube spanning
+    /// -1 to 1 along each axis.  The texture is laid out as an unfolded
+    /// cube: a grid 4 faces wide and 3 tall, with the left, front, right
+    /// and back faces along the middle row, and the up and down faces
+    /// above and below the front face.
+    Cubic,
+    /// Uses the texture co-ordinates given at each vertex of a
+    /// triangle, such as those read from an obj file.  Objects without
+    /// texture co-ordinates fall back to `Planar`.
+    Vertices,
+}
+
+impl UvMapping {
+    pub fn map(&self, p: Tuple4) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => spherical_map(p),
+            UvMapping::Planar => planar_map(p),
+            UvMapping::Cylindrical => cylindrical_map(p),
+            UvMapping::Cubic => cubic_map(p),
+            UvMapping::Vertices => planar_map(p),
+        }
+    }
+}
+
+pub fn spherical_map(p: Tuple4) -> (f64, f64) {
+    // the azimuthal angle, in the range (-pi, pi]
+    let theta = p.x().atan2(p.z());
+    let radius = vector(p.x(), p.y(), p.z()).magnitude();
+    // the polar angle, in the range [0, pi]
+    let phi = if radius == 0.0 { 0.0 } else { (p.y() / radius).clamp(-1.0, 1.0).acos() };
+
+    let raw_u = theta / (2.0 * PI);
+    // flip u, so it increases anticlockwise when viewed from above
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (wrap(u), v.min(1.0 - f64::EPSILON))
+}
+
+pub fn planar_map(p: Tuple4) -> (f64, f64) {
+    (wrap(p.x()), wrap(p.z()))
+}
+
+pub fn cylindrical_map(p: Tuple4) -> (f64, f64) {
+    let theta = p.x().atan2(p.z());
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    (wrap(u), wrap(p.y()))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+fn face_of(p: Tuple4) -> CubeFace {
+    let (ax, ay, az) = (p.x().abs(), p.y().abs(), p.z().abs());
+    let coord = ax.max(ay).max(az);
+    if coord == p.x() {
+        CubeFace::Right
+    } else if coord == -p.x() {
+        CubeFace::Left
+    } else if coord == p.y() {
+        CubeFace::Up
+    } else if coord == -p.y() {
+        CubeFace::Down
+    } else if coord == p.z() {
+        CubeFace::Front
+    } else {
+        CubeFace::Back
+    }
+}
+
+/// The position on one face of the cube, each of u and v in [0, 1].
+fn face_uv(face: CubeFace, p: Tuple4) -> (f64, f64) {
+    let half = |x: f64| ((x + 1.0) / 2.0).clamp(0.0, 1.0);
+    match face {
+        CubeFace::Front => (half(p.x()), half(p.y())),
+        CubeFace::Back => (half(-p.x()), half(p.y())),
+        CubeFace::Left => (half(p.z()), half(p.y())),
+        CubeFace::Right => (half(-p.z()), half(p.y())),
+        CubeFace::Up => (half(p.x()), half(-p.z())),
+        CubeFace::Down => (half(p.x()), half(p.z())),
+    }
+}
+
+pub fn cubic_map(p: Tuple4) -> (f64, f64) {
+    let face = face_of(p);
+    let (fu, fv) = face_uv(face, p);
+    // the column and row of the face in the unfolded cube, counting
+    // rows from the bottom
+    let (col, row) = match face {
+        CubeFace::Left => (0.0, 1.0),
+        CubeFace::Front => (1.0, 1.0),
+        CubeFace::Right => (2.0, 1.0),
+        CubeFace::Back => (3.0, 1.0),
+        CubeFace::Up => (1.0, 2.0),
+        CubeFace::Down => (1.0, 0.0),
+    };
+    // keep away from the very edges of the face, so filtering doesn't
+    // pick up colours from the neighbouring part of the texture
+    let inset = |x: f64| 0.001 + x * 0.998;
+    ((col + inset(fu)) / 4.0, (row + inset(fv)) / 3.0)
+}
+
+fn wrap(x: f64) -> f64 {
+    let w = x.rem_euclid(1.0);
+    // rem_euclid can round up to exactly 1.0 for tiny negative numbers
+    if w >= 1.0 { 0.0 } else { w }
+}
+
+/// Finds the colour of the image at (u, v), blending the four
+/// nearest pixels.  The image wraps around horizontally, and is
+/// clamped vertically.
+pub fn sample_bilinear(image: &Canvas, u: f64, v: f64) -> RGB {
+    let (w, h) = (image.width(), image.height());
+    if w == 0 || h == 0 {
+        return RGB::black();
+    }
+    // pixel centres lie at half pixel offsets; v runs from the bottom
+    let x = u * w as f64 - 0.5;
+    let y = (1.0 - v) * h as f64 - 0.5;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+
+    let col = |i: f64| (i as i64).rem_euclid(w as i64) as usize;
+    let row = |j: f64| (j.max(0.0) as usize).min(h - 1);
+    let (c0, c1, r0, r1) = (col(x0), col(x0 + 1.0), row(y0), row(y0 + 1.0));
+
+    let top = image.colour_at(c0, r0) * (1.0 - fx) + image.colour_at(c1, r0) * fx;
+    let bottom = image.colour_at(c0, r1) * (1.0 - fx) + image.colour_at(c1, r1) * fx;
+    top * (1.0 - fy) + bottom * fy
+}
+
+/// An image used as a texture.  It is only a handle, so it can be
+/// copied freely along with the patterns using it; the image itself is
+/// held once, in an `Arc` shared by every copy, and is kept for the
+/// rest of the program.
+#[derive(Debug, Copy, Clone)]
+pub struct Texture(usize);
+
+lazy_static! {
+    static ref TEXTURES: RwLock<Vec<Arc<Canvas>>> = RwLock::new(vec![]);
+}
+
+impl Texture {
+    pub fn new(image: Canvas) -> Texture {
+        let mut textures = TEXTURES.write().unwrap();
+        textures.push(Arc::new(image));
+        Texture(textures.len() - 1)
+    }
+
+    /// The image the handle refers to.
+    pub fn image(&self) -> Arc<Canvas> {
+        Arc::clone(&TEXTURES.read().unwrap()[self.0])
+    }
+
+    /// Like `sample_bilinear` on the image.
+    pub(crate) fn sample(&self, u: f64, v: f64) -> RGB {
+        sample_bilinear(&TEXTURES.read().unwrap()[self.0], u, v)
+    }
+}
+
+impl PartialEq for Texture {
+    /// Textures are equal if their images are, as when they were
+    /// loaded from the same file.
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 || self.image() == other.image()
+    }
+}
+
+/// Reads an image to use as a texture: a ppm image if the file has
+/// the extension .ppm, otherwise a png image.
+///
+/// Each call loads the image again, so load each image once, and share
+/// the texture between the patterns that need it.
+pub fn load_texture(path: &Path) -> io::Result<Texture> {
+    let mut f = File::open(path)?;
+    let is_ppm = path.extension().map(|e| e.eq_ignore_ascii_case("ppm")) == Some(true);
+    let image = if is_ppm { crate::ppm::decode(&mut f)? } else { crate::png::decode(&mut f)? };
+    Ok(Texture::new(image))
+}
@@ -0,0 +1,134 @@
+use crate::*;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// How colours beyond what a display can show are brought back into
+/// the range [0.0, 1.0].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ToneOperator {
+    /// Leaves the colours alone, for the encoder to clamp.
+    Clamp,
+    /// c / (1 + c), which compresses highlights smoothly but never
+    /// quite reaches white.
+    Reinhard,
+    /// A fit to the filmic curve of the Academy Color Encoding
+    /// System, with more contrast than Reinhard.
+    Aces,
+}
+
+#[derive(Debug)]
+pub struct BadToneOperator(String);
+
+impl fmt::Display for BadToneOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bad tone operator '{}': expected clamp, reinhard or aces", self.0)
+    }
+}
+
+impl FromStr for ToneOperator {
+    type Err = BadToneOperator;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" | "none" => Ok(ToneOperator::Clamp),
+            "reinhard" => Ok(ToneOperator::Reinhard),
+            "aces" => Ok(ToneOperator::Aces),
+            _ => Err(BadToneOperator(s.to_string())),
+        }
+    }
+}
+
+impl ToneOperator {
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            ToneOperator::Clamp => x,
+            ToneOperator::Reinhard => {
+                let x = x.max(0.0);
+                x / (1.0 + x)
+            },
+            ToneOperator::Aces => {
+                let x = x.max(0.0);
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                ((x * (a * x + b)) / (x * (c * x + d) + e)).min(1.0)
+            },
+        }
+    }
+}
+
+/// Adjusts the linear colours a camera renders for display, before
+/// they are encoded.  In order: the exposure scales the colours, the
+/// tone operator brings them into range, and the sRGB transfer curve
+/// encodes them as displays expect.
+///
+/// By default each step leaves the colours unchanged.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMapping {
+    exposure: f64,
+    operator: ToneOperator,
+    srgb: bool,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping { exposure: 0.0, operator: ToneOperator::Clamp, srgb: false }
+    }
+}
+
+impl ToneMapping {
+    /// Sets the exposure, in stops: each stop doubles the brightness.
+    pub fn set_exposure(&mut self, stops: f64) -> &mut Self {
+        self.exposure = stops;
+        self
+    }
+
+    pub fn set_operator(&mut self, op: ToneOperator) -> &mut Self {
+        self.operator = op;
+        self
+    }
+
+    /// Whether to encode the colours with the sRGB transfer curve.
+    /// PNG images are assumed to be sRGB, so without it renders look
+    /// too dark.
+    pub fn set_srgb(&mut self, srgb: bool) -> &mut Self {
+        self.srgb = srgb;
+        self
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == ToneMapping::default()
+    }
+
+    pub fn map_colour(&self, c: RGB) -> RGB {
+        let scale = 2f64.powf(self.exposure);
+        let channel = |x: f64| {
+            let x = self.operator.apply(x * scale);
+            if self.srgb { srgb_encode(x) } else { x }
+        };
+        colour(channel(c.red()), channel(c.green()), channel(c.blue()))
+    }
+
+    /// Maps every pixel of the canvas.
+    pub fn apply(&self, canv: &mut Canvas) {
+        if self.is_identity() {
+            return;
+        }
+        for y in 0..canv.height() {
+            for x in 0..canv.width() {
+                let c = self.map_colour(canv.colour_at(x, y));
+                canv.set_colour_at(x, y, c);
+            }
+        }
+    }
+}
+
+/// The sRGB transfer curve, from a linear value in [0.0, 1.0] to the
+/// value a display expects.  Values beyond 1.0 stay at 1.0.
+pub fn srgb_encode(x: f64) -> f64 {
+    if x <= 0.0 {
+        0.0
+    } else if x <= 0.003_130_8 {
+        12.92 * x
+    } else {
+        1.055 * x.min(1.0).powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -2,16 +2,49 @@ use crate::*;
 use std::f64;
 use std::io;
 use std::io::{BufRead, BufReader};
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::f64::consts::PI;
 use std::error::Error;
 use std::fmt;
+use std::convert::TryFrom;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
+    /// The input could not be read.
     Io,
-    BadInstruction,
+    /// A line of the input could not be understood.  Lines are
+    /// numbered from 1.
+    BadInstruction { line: usize, text: String, reason: Reason },
+}
+
+/// Why a line could not be understood.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    /// The given text should have been a number.
+    BadNumber(String),
+    /// The instruction has too few values.
+    MissingValues,
+    /// The given text should have been an index of a vertex, normal
+    /// or texture co-ordinate, counting from 1.
+    BadIndex(String),
+    /// A face refers to a vertex, normal or texture co-ordinate that
+    /// hasn't been declared.
+    IndexOutOfRange(isize),
+    /// A face has fewer than three vertices.
+    TooFewVertices,
+    /// Some, but not all, of the vertices of a face have normals.
+    MixedNormals,
+    /// The instruction describes something that can't be rendered,
+    /// such as a free-form curve or surface.
+    Unsupported(String),
+    /// The file the instruction refers to could not be read.
+    Unreadable(String),
+    /// The material library the instruction refers to has an error.
+    InLibrary(String, Box<ParseError>),
 }
 
 impl From<&ParseError> for ParseError {
@@ -27,8 +60,25 @@ impl Error for ParseError {
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ParseError::Io => write!(f, "Io"),
-            ParseError::BadInstruction => write!(f, "BadInstruction"),
+            ParseError::Io => write!(f, "the input could not be read"),
+            ParseError::BadInstruction { line, text, reason } =>
+                write!(f, "line {}: {}: {}", line, reason, text),
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::BadNumber(x) => write!(f, "'{}' is not a number", x),
+            Reason::MissingValues => write!(f, "too few values"),
+            Reason::BadIndex(x) => write!(f, "'{}' is not a valid index", x),
+            Reason::IndexOutOfRange(i) => write!(f, "index {} is out of range", i),
+            Reason::TooFewVertices => write!(f, "a face needs at least three vertices"),
+            Reason::MixedNormals => write!(f, "either all or none of the vertices of a face need normals"),
+            Reason::Unsupported(x) => write!(f, "'{}' is not supported", x),
+            Reason::Unreadable(x) => write!(f, "could not read '{}'", x),
+            Reason::InLibrary(x, e) => write!(f, "in '{}', {}", x, e),
         }
     }
 }
@@ -36,36 +86,60 @@ impl fmt::Display for ParseError {
 struct ParseState {
     pub vertices : Vec<Tuple4>,
     pub normals : Vec<Tuple4>,
+    pub texture_coords : Vec<(f64, f64)>,
     pub group_name: String,
     pub groups : BTreeMap<String, Vec<Object>>,
+    /// Where to look for material libraries, or None to ignore them.
+    pub base_dir: Option<PathBuf>,
+    pub materials: BTreeMap<String, Material>,
+    pub material: Material,
 }
 
 impl ParseState {
-    pub fn new() -> Self {
+    pub fn new(base_dir: Option<&Path>) -> Self {
         let mut groups : BTreeMap<String, Vec<Object>> = BTreeMap::new();
         groups.insert("".to_string(), vec![]);
-        ParseState { vertices: vec![], normals: vec![], group_name: "".to_string(), groups }
+        ParseState {
+            vertices: vec![],
+            normals: vec![],
+            texture_coords: vec![],
+            group_name: "".to_string(),
+            groups,
+            base_dir: base_dir.map(|d| d.to_path_buf()),
+            materials: BTreeMap::new(),
+            material: Material::default(),
+        }
     }
 
-    fn handle_triangle(&mut self, i1: &PolygonPoint, i2: &PolygonPoint, i3: &PolygonPoint) -> Result<(), ParseError> {
+    fn handle_triangle(&mut self, i1: &PolygonPoint, i2: &PolygonPoint, i3: &PolygonPoint) -> Result<(), Reason> {
         let points = &self.vertices;
-        let group = self.groups.get_mut(&self.group_name).unwrap();
-        let v1: Tuple4 = points[gt_zero(i1.vertex_index)?];
-        let v2: Tuple4 = points[gt_zero(i2.vertex_index)?];
-        let v3: Tuple4 = points[gt_zero(i3.vertex_index)?];
+        let v1: Tuple4 = lookup(points, i1.vertex_index)?;
+        let v2: Tuple4 = lookup(points, i2.vertex_index)?;
+        let v3: Tuple4 = lookup(points, i3.vertex_index)?;
 
-        if no_normals(i1, i2, i3) {
-            group.push(triangle(v1, v2, v3));
+        let mut tri = if no_normals(i1, i2, i3) {
+            triangle(v1, v2, v3)
         } else if all_have_normals (i1, i2, i3) {
             let normals = &self.normals;
 
-            let n1: Tuple4 = normals[gt_zero(i1.normal_index.unwrap())?];
-            let n2: Tuple4 = normals[gt_zero(i2.normal_index.unwrap())?];
-            let n3: Tuple4 = normals[gt_zero(i3.normal_index.unwrap())?];
-            group.push(smooth_triangle(v1, v2, v3, n1, n2, n3));
+            let n1: Tuple4 = lookup(normals, i1.normal_index.unwrap())?;
+            let n2: Tuple4 = lookup(normals, i2.normal_index.unwrap())?;
+            let n3: Tuple4 = lookup(normals, i3.normal_index.unwrap())?;
+            smooth_triangle(v1, v2, v3, n1, n2, n3)
         } else {
-            return Err(ParseError::BadInstruction);
+            return Err(Reason::MixedNormals);
+        };
+
+        // texture co-ordinates are only used if every vertex has them,
+        // and are ignored entirely by files that don't declare any
+        if let (Some(t1), Some(t2), Some(t3)) = (i1.texture_index, i2.texture_index, i3.texture_index) {
+            if !self.texture_coords.is_empty() {
+                let coords = &self.texture_coords;
+                tri.set_texture_coords([lookup(coords, t1)?, lookup(coords, t2)?, lookup(coords, t3)?]);
+            }
         }
+        tri.set_material(self.material);
+        self.groups.get_mut(&self.group_name).unwrap().push(tri);
 
         Ok(())
     }
@@ -83,32 +157,40 @@ impl ParseState {
     }
 }
 
+/// The indices given for one vertex of a face.  Positive indices
+/// count from 1 at the start of the file; negative ones count back
+/// from the most recently declared item, which is -1.
 #[derive(Debug, Clone)]
 pub struct PolygonPoint {
-    pub vertex_index: usize,
-    pub normal_index: Option<usize>
+    pub vertex_index: isize,
+    pub texture_index: Option<isize>,
+    pub normal_index: Option<isize>
 }
 
 impl FromStr for PolygonPoint {
-    type Err = ParseError;
+    type Err = Reason;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // one of v, v/vt, v//vn or v/vt/vn
         let splitted: Vec<&str> = s.split("/").collect();
-        if splitted.len() == 3 {
-            let vindex: usize = splitted[0].parse::<usize>().or_else(|_| Err(ParseError::BadInstruction))?;
-            let nindex: usize = splitted[2].parse::<usize>().or_else(|_| Err(ParseError::BadInstruction))?;
-            Ok(PolygonPoint {
-                vertex_index: vindex,
-                normal_index: Some(nindex)
-            })
-        } else if splitted.len() == 1 {
-            let vindex: usize = splitted[0].parse::<usize>().or_else(|_| Err(ParseError::BadInstruction))?;
-            Ok(PolygonPoint {
-                vertex_index: vindex,
-                normal_index: None
-            })
-        } else {
-            Err(ParseError::BadInstruction)
+        if splitted.len() > 3 {
+            return Err(Reason::BadIndex(s.to_string()));
         }
+        let index = |x: &str| x.parse::<isize>().map_err(|_| Reason::BadIndex(x.to_string()));
+        // only the texture index of v//vn may be left empty
+        let texture_index = match splitted.get(1) {
+            None => None,
+            Some(&"") if splitted.len() == 3 => None,
+            Some(x) => Some(index(x)?),
+        };
+        let normal_index = match splitted.get(2) {
+            None => None,
+            Some(x) => Some(index(x)?),
+        };
+        Ok(PolygonPoint {
+            vertex_index: index(splitted[0])?,
+            texture_index,
+            normal_index,
+        })
     }
 }
 
@@ -128,28 +210,46 @@ fn all_have_normals(p1: &PolygonPoint, p2: &PolygonPoint, p3: &PolygonPoint) ->
 }
 
 pub trait ParseHandler {
-    fn handle_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<(), ParseError>;
+    fn handle_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<(), Reason>;
+
+    fn handle_normal(&mut self, x: f64, y: f64, z: f64) -> Result<(), Reason>;
+
+    fn handle_polygon(&mut self, points: &[PolygonPoint]) -> Result<(), Reason>;
 
-    fn handle_normal(&mut self, x: f64, y: f64, z: f64) -> Result<(), ParseError>;
+    fn declare_group(&mut self, name: &str) -> Result<(), Reason> ;
+
+    /// A texture co-ordinate, from a `vt` instruction.  The optional
+    /// third component is dropped.
+    fn handle_texture_coord(&mut self, _u: f64, _v: f64) -> Result<(), Reason> {
+        Ok(())
+    }
 
-    fn handle_polygon(&mut self, points: &[PolygonPoint]) -> Result<(), ParseError>;
+    /// A material library named by an `mtllib` instruction, which is
+    /// called once for each library the instruction names.
+    fn use_material_library(&mut self, _name: &str) -> Result<(), Reason> {
+        Ok(())
+    }
 
-    fn declare_group(&mut self, name: &str) -> Result<(), ParseError> ;
+    /// The material for the polygons that follow, from a `usemtl`
+    /// instruction.
+    fn use_material(&mut self, _name: &str) -> Result<(), Reason> {
+        Ok(())
+    }
 }
 
 impl ParseHandler for ParseState {
-    fn handle_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<(), ParseError> {
+    fn handle_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<(), Reason> {
         self.vertices.push(point(x, y, z));
         Ok(())
     }
 
-    fn handle_normal(&mut self, x: f64, y: f64, z: f64) -> Result<(), ParseError> {
+    fn handle_normal(&mut self, x: f64, y: f64, z: f64) -> Result<(), Reason> {
         self.normals.push(vector(x, y, z));
         Ok(())
     }
 
-    fn handle_polygon(&mut self, points: &[PolygonPoint]) -> Result<(), ParseError>{
-        if points.len() < 3 { return Err(ParseError::BadInstruction); }
+    fn handle_polygon(&mut self, points: &[PolygonPoint]) -> Result<(), Reason>{
+        if points.len() < 3 { return Err(Reason::TooFewVertices); }
 
         let first = &points[0];
         for i in 1..=(points.len() - 2) {
@@ -161,45 +261,281 @@ impl ParseHandler for ParseState {
         Ok(())
     }
 
-    fn declare_group(&mut self, name: &str) -> Result<(), ParseError> {
+    fn declare_group(&mut self, name: &str) -> Result<(), Reason> {
         self.groups.insert(name.to_string(), vec![]);
         self.group_name = name.to_string();
         Ok(())
     }
+
+    fn handle_texture_coord(&mut self, u: f64, v: f64) -> Result<(), Reason> {
+        self.texture_coords.push((u, v));
+        Ok(())
+    }
+
+    fn use_material_library(&mut self, name: &str) -> Result<(), Reason> {
+        if let Some(dir) = &self.base_dir {
+            let path = dir.join(name);
+            // as with missing materials, the polygons are left with the
+            // default material
+            let mut f = match File::open(&path) {
+                Ok(f) => f,
+                Err(_) => return Ok(()),
+            };
+            let library = read_materials(&mut f, path.parent().unwrap_or(dir))
+                .map_err(|e| Reason::InLibrary(name.to_string(), Box::new(e)))?;
+            self.materials.extend(library);
+        }
+        Ok(())
+    }
+
+    fn use_material(&mut self, name: &str) -> Result<(), Reason> {
+        // materials missing from the libraries are left as the default
+        self.material = self.materials.get(name).copied().unwrap_or_else(Material::default);
+        Ok(())
+    }
 }
 
+/// Reads the objects described by an obj file.  Material libraries
+/// are ignored, so every object has the default material.
 pub fn read_object_vec(input: &mut dyn io::Read) -> Result<Vec<Object>, ParseError> {
-    let mut state = ParseState::new();
+    let mut state = ParseState::new(None);
     parse(&mut state, input)?;
     Ok(state.to_vec())
 }
 
+/// Reads the objects described by an obj file, giving them the
+/// materials found in its material libraries.  The libraries, and any
+/// textures they refer to, are found relative to `base_dir`; usually
+/// the directory holding the obj file.  Libraries that can't be opened
+/// are skipped, leaving the default material for the materials they
+/// would have held.
+pub fn read_object_vec_from(input: &mut dyn io::Read, base_dir: &Path) -> Result<Vec<Object>, ParseError> {
+    let mut state = ParseState::new(Some(base_dir));
+    parse(&mut state, input)?;
+    Ok(state.to_vec())
+}
+
+/// Reads an obj file into a single mesh, which takes far less memory
+/// than an object per triangle.  The file is read a line at a time,
+/// and its vertices and faces go straight into the mesh's buffers.
+/// Groups and materials are ignored: the mesh has the default
+/// material, which may be changed on the returned object.
+pub fn read_mesh(input: &mut dyn io::Read) -> Result<Object, ParseError> {
+    let mut builder = MeshBuilder::default();
+    parse(&mut builder, input)?;
+    Ok(mesh(Mesh::new(builder.vertices, builder.normals, builder.texture_coords, builder.faces)))
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    vertices: Vec<Tuple4>,
+    normals: Vec<Tuple4>,
+    texture_coords: Vec<(f64, f64)>,
+    faces: Vec<MeshFace>,
+}
+
+impl MeshBuilder {
+    fn indices(&self, points: [&PolygonPoint; 3]) -> Result<MeshFace, Reason> {
+        let resolve_all = |declared: usize, is: [isize; 3]| -> Result<[u32; 3], Reason> {
+            let mut out = [0; 3];
+            for (o, i) in out.iter_mut().zip(is.iter()) {
+                let position = resolve(declared, *i)?;
+                // beyond a u32, the buffers would be too large to hold anyway
+                *o = u32::try_from(position).map_err(|_| Reason::IndexOutOfRange(*i))?;
+            }
+            Ok(out)
+        };
+        let [p1, p2, p3] = points;
+
+        let vertices = resolve_all(self.vertices.len(), [p1.vertex_index, p2.vertex_index, p3.vertex_index])?;
+        let normals = match (p1.normal_index, p2.normal_index, p3.normal_index) {
+            (None, None, None) => None,
+            (Some(n1), Some(n2), Some(n3)) => Some(resolve_all(self.normals.len(), [n1, n2, n3])?),
+            _ => return Err(Reason::MixedNormals),
+        };
+        // as with read_object_vec, texture co-ordinates are only used if
+        // every vertex has them, and the file declares some
+        let texture_coords = match (p1.texture_index, p2.texture_index, p3.texture_index) {
+            (Some(t1), Some(t2), Some(t3)) if !self.texture_coords.is_empty() =>
+                Some(resolve_all(self.texture_coords.len(), [t1, t2, t3])?),
+            _ => None,
+        };
+        Ok(MeshFace { vertices, normals, texture_coords })
+    }
+}
+
+impl ParseHandler for MeshBuilder {
+    fn handle_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<(), Reason> {
+        self.vertices.push(point(x, y, z));
+        Ok(())
+    }
+
+    fn handle_normal(&mut self, x: f64, y: f64, z: f64) -> Result<(), Reason> {
+        self.normals.push(vector(x, y, z));
+        Ok(())
+    }
+
+    fn handle_polygon(&mut self, points: &[PolygonPoint]) -> Result<(), Reason> {
+        if points.len() < 3 { return Err(Reason::TooFewVertices); }
+
+        for i in 1..(points.len() - 1) {
+            let face = self.indices([&points[0], &points[i], &points[i + 1]])?;
+            self.faces.push(face);
+        }
+        Ok(())
+    }
+
+    fn declare_group(&mut self, _name: &str) -> Result<(), Reason> {
+        Ok(())
+    }
+
+    fn handle_texture_coord(&mut self, u: f64, v: f64) -> Result<(), Reason> {
+        self.texture_coords.push((u, v));
+        Ok(())
+    }
+}
+
+/// Reads the materials from an mtl file, keyed by name.
+///
+/// The diffuse colour (`Kd`) becomes a solid pattern, unless there is
+/// a diffuse texture (`map_Kd`), which is read relative to `base_dir`
+/// and mapped using the texture co-ordinates of the triangles.  The
+/// specular colour (`Ks`) is averaged into a single specular value.
+/// `Ns` is the shininess, `d` the opacity (or `Tr` the transparency)
+/// and `Ni` the refractive index.  Other instructions are ignored.
+pub fn read_materials(input: &mut dyn io::Read, base_dir: &Path) -> Result<BTreeMap<String, Material>, ParseError> {
+    let mut materials = BTreeMap::new();
+    let mut textures: BTreeMap<String, Texture> = BTreeMap::new();
+    let mut current: Option<(String, Material)> = None;
+
+    for (n, l) in BufReader::new(input).lines().enumerate() {
+        let line = l.map_err(|_| ParseError::Io)?;
+        handle_mtl_line(&line, base_dir, &mut materials, &mut textures, &mut current)
+            .map_err(|reason| ParseError::BadInstruction { line: n + 1, text: line.clone(), reason })?;
+    }
+    if let Some((name, m)) = current {
+        materials.insert(name, m);
+    }
+    Ok(materials)
+}
+
+fn handle_mtl_line(
+    line: &str,
+    base_dir: &Path,
+    materials: &mut BTreeMap<String, Material>,
+    textures: &mut BTreeMap<String, Texture>,
+    current: &mut Option<(String, Material)>,
+) -> Result<(), Reason> {
+    let mut words = line.split_whitespace();
+    let keyword = match words.next() {
+        Some(k) => k,
+        None => return Ok(()),
+    };
+    let args: Vec<&str> = words.collect();
+
+    if keyword == "newmtl" {
+        if let Some((name, m)) = current.take() {
+            materials.insert(name, m);
+        }
+        *current = Some((args.join(" "), Material::default()));
+        return Ok(());
+    }
+    let m = match current {
+        Some((_, m)) => m,
+        None => return Ok(()),
+    };
+    match keyword {
+        "Kd" => {
+            // a texture, wherever it appears, takes precedence
+            let (r, g, b) = mtl_triple(&args)?;
+            if !matches!(m.pattern(), Pattern::Image { .. }) {
+                m.set_pattern(Pattern::solid(colour(r, g, b)));
+            }
+        },
+        "Ks" => {
+            let (r, g, b) = mtl_triple(&args)?;
+            m.set_specular((r + g + b) / 3.0);
+        },
+        "Ns" => { m.set_shininess(mtl_number(&args)?); },
+        "d" => { m.set_transparency(1.0 - mtl_number(&args)?); },
+        "Tr" => { m.set_transparency(mtl_number(&args)?); },
+        "Ni" => { m.set_refractive_index(mtl_number(&args)?); },
+        "map_Kd" => {
+            // options such as -s come before the file name
+            let file = args.last().ok_or(Reason::MissingValues)?;
+            let texture = match textures.get(*file) {
+                Some(t) => *t,
+                None => {
+                    let t = load_texture(&base_dir.join(file))
+                        .map_err(|_| Reason::Unreadable(file.to_string()))?;
+                    textures.insert(file.to_string(), t);
+                    t
+                },
+            };
+            m.set_pattern(Pattern::image(texture, UvMapping::Vertices));
+        },
+        _ => (),
+    }
+    Ok(())
+}
+
+fn mtl_number(args: &[&str]) -> Result<f64, Reason> {
+    match args {
+        [x] => number(x),
+        _ => Err(Reason::MissingValues),
+    }
+}
+
+fn mtl_triple(args: &[&str]) -> Result<(f64, f64, f64), Reason> {
+    match args {
+        // a single value is used for all three components
+        [x] => { let n = number(x)?; Ok((n, n, n)) },
+        [r, g, b] => Ok((number(r)?, number(g)?, number(b)?)),
+        _ => Err(Reason::MissingValues),
+    }
+}
+
+/// Passes each instruction of an obj file to the handler.  Errors
+/// from the handler are reported against the line being handled.
 pub fn parse(handler: &mut dyn ParseHandler, input: &mut dyn io::Read) -> Result<(), ParseError> {
     let bufread = BufReader::new(input);
 
-    let mut lines = bufread.lines();
-    while let Some(l) = lines.next() {
-        let line = l.or_else(|_| Err(ParseError::Io))?;
-        handle_line(line, handler)?;
+    for (n, l) in bufread.lines().enumerate() {
+        let line = l.map_err(|_| ParseError::Io)?;
+        handle_line(&line, handler)
+            .map_err(|reason| ParseError::BadInstruction { line: n + 1, text: line.clone(), reason })?;
     }
     Ok(())
 }
 
-fn handle_line(line: String, handler: &mut dyn ParseHandler) -> Result<(), ParseError>  {
-    if line.starts_with("v ") {
-        read_point(&line[2..], handler)
-    } else if line.starts_with("vn ") {
-        read_normal(&line[3..], handler)
-    } else if line.starts_with("f ") {
-        read_facet(&line[2..], handler)
-    } else if line.starts_with("g ") {
-        handler.declare_group(&line[2..])
+/// Directives for free-form geometry, which would otherwise silently
+/// go missing from the model.
+const UNSUPPORTED: &[&str] = &["cstype", "curv", "curv2", "surf", "vp"];
+
+fn handle_line(line: &str, handler: &mut dyn ParseHandler) -> Result<(), Reason>  {
+    if let Some(args) = line.strip_prefix("v ") {
+        read_point(args, handler)
+    } else if let Some(args) = line.strip_prefix("vn ") {
+        read_normal(args, handler)
+    } else if let Some(args) = line.strip_prefix("vt ") {
+        read_texture_coord(args, handler)
+    } else if let Some(args) = line.strip_prefix("f ") {
+        read_facet(args, handler)
+    } else if let Some(name) = line.strip_prefix("g ") {
+        handler.declare_group(name)
+    } else if let Some(names) = line.strip_prefix("mtllib ") {
+        names.split_whitespace().try_for_each(|name| handler.use_material_library(name))
+    } else if let Some(name) = line.strip_prefix("usemtl ") {
+        handler.use_material(name.trim())
     } else {
-        Ok(())
+        match line.split_whitespace().next() {
+            Some(d) if UNSUPPORTED.contains(&d) => Err(Reason::Unsupported(d.to_string())),
+            _ => Ok(()),
+        }
     }
 }
 
-fn triple(s: &str) -> Result<(&str, &str, &str), ParseError> {
+fn triple(s: &str) -> Result<(&str, &str, &str), Reason> {
     let mut splitted = s.split_whitespace();
     if let Some(s1) = splitted.next() {
         if let Some(s2) = splitted.next() {
@@ -208,47 +544,403 @@ fn triple(s: &str) -> Result<(&str, &str, &str), ParseError> {
             }
         }
     }
-    Err(ParseError::BadInstruction)
+    Err(Reason::MissingValues)
+}
+
+fn number(s: &str) -> Result<f64, Reason> {
+    s.parse::<f64>().map_err(|_| Reason::BadNumber(s.to_string()))
 }
 
-fn read_point(triplet: &str, handler: &mut dyn ParseHandler) -> Result<(), ParseError> {
+fn read_point(triplet: &str, handler: &mut dyn ParseHandler) -> Result<(), Reason> {
     let (s1, s2, s3) = triple(triplet)?;
-    let x1 = s1.parse::<f64>().or_else(|_| Err(ParseError::BadInstruction))?;
-    let x2 = s2.parse::<f64>().or_else(|_| Err(ParseError::BadInstruction))?;
-    let x3 = s3.parse::<f64>().or_else(|_| Err(ParseError::BadInstruction))?;
+    let (x1, x2, x3) = (number(s1)?, number(s2)?, number(s3)?);
     handler.handle_vertex(x1, x2, x3)?;
     Ok(())
 }
 
-fn read_normal(triplet: &str, handler: &mut dyn ParseHandler) -> Result<(), ParseError> {
+fn read_normal(triplet: &str, handler: &mut dyn ParseHandler) -> Result<(), Reason> {
     let (s1, s2, s3) = triple(triplet)?;
-    let x1 = s1.parse::<f64>().or_else(|_| Err(ParseError::BadInstruction))?;
-    let x2 = s2.parse::<f64>().or_else(|_| Err(ParseError::BadInstruction))?;
-    let x3 = s3.parse::<f64>().or_else(|_| Err(ParseError::BadInstruction))?;
+    let (x1, x2, x3) = (number(s1)?, number(s2)?, number(s3)?);
     handler.handle_normal(x1, x2, x3)?;
     Ok(())
 }
 
-fn read_facet(args: &str, handler: &mut dyn ParseHandler) -> Result<(), ParseError> {
-    let (parsed, errs): (Vec<Result<PolygonPoint, ParseError>>, Vec<Result<PolygonPoint, ParseError>>) = args
+fn read_texture_coord(args: &str, handler: &mut dyn ParseHandler) -> Result<(), Reason> {
+    let mut splitted = args.split_whitespace();
+    let u = number(splitted.next().ok_or(Reason::MissingValues)?)?;
+    let v = match splitted.next() {
+        None => 0.0,
+        Some(v) => number(v)?,
+    };
+    handler.handle_texture_coord(u, v)
+}
+
+fn read_facet(args: &str, handler: &mut dyn ParseHandler) -> Result<(), Reason> {
+    let points = args
         .split_whitespace()
-        .map(|x| x.parse::<PolygonPoint>().or_else(|_| Err(ParseError::BadInstruction)))
-        .partition(|x| x.is_ok());
+        .map(|x| x.parse::<PolygonPoint>())
+        .collect::<Result<Vec<PolygonPoint>, Reason>>()?;
+    handler.handle_polygon(&points)
+}
 
-    if errs.len() > 0 {
-        return Err(ParseError::BadInstruction);
-    } else {
-        let oks: Vec<PolygonPoint> = parsed.iter().cloned().map(|x| x.unwrap()).collect();
-        handler.handle_polygon(&oks)
+/// Finds the item referred to by an index counting from 1, or back
+/// from the end of the items declared so far if negative.
+fn lookup<T: Copy>(items: &[T], index: isize) -> Result<T, Reason> {
+    resolve(items.len(), index).map(|i| items[i])
+}
+
+/// Finds the position, counting from 0, of the item referred to by an
+/// index, given how many items have been declared so far.
+fn resolve(declared: usize, index: isize) -> Result<usize, Reason> {
+    let position = match index {
+        0 => return Err(Reason::BadIndex(index.to_string())),
+        i if i > 0 => i - 1,
+        i => declared as isize + i,
+    };
+    if position < 0 || position as usize >= declared {
+        return Err(Reason::IndexOutOfRange(index));
     }
+    Ok(position as usize)
 }
 
-fn gt_zero(i: usize) -> Result<usize, ParseError> {
-    if i > 0 {
-        Ok(i - 1)
+/// How `write_object_vec` treats spheres, cubes, cylinders, cones, tori
+/// and discs, which aren't made of polygons.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Tessellation {
+    /// Leave them out.
+    Skip,
+    /// Approximate them with triangles.  Spheres, cylinders, cones, tori
+    /// and discs are divided into this many segments around their axis,
+    /// spheres into half as many bands from pole to pole, and tori into
+    /// half as many around the tube.
+    Segments(usize),
+}
+
+/// Writes the objects in obj format.
+///
+/// Transforms are baked into the co-ordinates, so everything is
+/// written in world space.  Each group becomes a `g` record, named
+/// `group1`, `group2` and so on in the order they're met.  Obj groups
+/// can't be nested, so a group within a group is written as a separate
+/// `g` record after its parent.  Planes, cylinders and cones of
+/// infinite length, and CSG objects, whose surfaces would have to be
+/// clipped against each other, can't be written and are always left
+/// out.
+pub fn write_object_vec(objects: &[Object], output: &mut dyn io::Write, tessellation: Tessellation) -> io::Result<()> {
+    let mut w = ObjWriter { output, tessellation, vertices: 0, normals: 0, texture_coords: 0 };
+    let mut groups: VecDeque<(&Object, Matrix)> = VecDeque::new();
+    w.write_members(objects, identity(), &mut groups)?;
+
+    let mut count = 0;
+    while let Some((g, to_world)) = groups.pop_front() {
+        count += 1;
+        writeln!(w.output, "g group{}", count)?;
+        w.write_members(g.children(), to_world * g.object_to_world_spc(), &mut groups)?;
+    }
+    Ok(())
+}
+
+struct ObjWriter<'a> {
+    output: &'a mut dyn io::Write,
+    tessellation: Tessellation,
+    /// How many of each have been written so far, for numbering the
+    /// next ones.
+    vertices: usize,
+    normals: usize,
+    texture_coords: usize,
+}
+
+/// A triangle in object space, with a normal at each vertex.
+type Facet = ([Tuple4; 3], [Tuple4; 3]);
+
+impl<'a> ObjWriter<'a> {
+    /// Writes the objects that aren't groups, and queues up those that
+    /// are, with the transform from their parent's space to world space.
+    fn write_members<'o>(&mut self, objects: &'o [Object], to_world: Matrix, groups: &mut VecDeque<(&'o Object, Matrix)>) -> io::Result<()> {
+        for o in objects {
+            if o.is_group() {
+                groups.push_back((o, to_world));
+            } else if let Some(p) = o.prototype() {
+                // each instance is written out in full
+                self.write_members(std::slice::from_ref(&**p), to_world * o.object_to_world_spc(), groups)?;
+            } else {
+                self.write_primitive(o, to_world * o.object_to_world_spc())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_primitive(&mut self, obj: &Object, to_world: Matrix) -> io::Result<()> {
+        let segments = match (obj.shape(), self.tessellation) {
+            (Shape::Triangle { p1, p2, p3, uvs, .. }, _) => {
+                return self.write_triangle(to_world, [*p1, *p2, *p3], None, *uvs);
+            },
+            (Shape::SmoothTri { p1, p2, p3, n1, n2, n3, uvs }, _) => {
+                return self.write_triangle(to_world, [*p1, *p2, *p3], Some([*n1, *n2, *n3]), *uvs);
+            },
+            (Shape::Mesh(m), _) => return self.write_mesh(to_world, m),
+            (Shape::Quad { corner, u, v, .. }, _) => {
+                let (a, b, c, d) = (*corner, *corner + *u, *corner + *u + *v, *corner + *v);
+                self.write_triangle(to_world, [a, b, c], None, Some([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]))?;
+                return self.write_triangle(to_world, [a, c, d], None, Some([(0.0, 0.0), (1.0, 1.0), (0.0, 1.0)]));
+            },
+            (_, Tessellation::Skip) => return Ok(()),
+            (_, Tessellation::Segments(n)) => n.max(3),
+        };
+        let facets = match obj.shape() {
+            Shape::Sphere => sphere_facets(segments),
+            Shape::Cube => cube_facets(),
+            Shape::Cylinder { kind, lbound, ubound } if lbound.is_finite() && ubound.is_finite() => {
+                cylinder_facets(segments, *kind, *lbound, *ubound)
+            },
+            Shape::Cone { kind, lbound, ubound } if lbound.is_finite() && ubound.is_finite() => {
+                cone_facets(segments, *kind, *lbound, *ubound)
+            },
+            Shape::Torus { major, minor } => torus_facets(segments, *major, *minor),
+            Shape::Disc { radius } => disc_facets(segments, *radius),
+            _ => vec![],
+        };
+        for (points, normals) in facets {
+            self.write_triangle(to_world, points, Some(normals), None)?;
+        }
+        Ok(())
+    }
+
+    fn write_triangle(&mut self, to_world: Matrix, points: [Tuple4; 3], normals: Option<[Tuple4; 3]>, uvs: Option<TexCoords>) -> io::Result<()> {
+        for p in points.iter() {
+            let q = to_world.mult(*p);
+            writeln!(self.output, "v {} {} {}", q.x(), q.y(), q.z())?;
+        }
+        if let Some(ns) = normals {
+            let normal_to_world = to_world.inverse().transpose();
+            for n in ns.iter() {
+                let m = normal_to_world.mult(*n);
+                let m = vector(m.x(), m.y(), m.z()).normalize();
+                writeln!(self.output, "vn {} {} {}", m.x(), m.y(), m.z())?;
+            }
+        }
+        if let Some(ts) = uvs {
+            for (u, v) in ts.iter() {
+                writeln!(self.output, "vt {} {}", u, v)?;
+            }
+        }
+
+        let latest = [0, 1, 2];
+        self.write_face(latest, uvs.map(|_| latest), normals.map(|_| latest))?;
+
+        self.vertices += 3;
+        if normals.is_some() { self.normals += 3; }
+        if uvs.is_some() { self.texture_coords += 3; }
+        Ok(())
+    }
+
+    /// Writes the mesh's buffers once, and its faces as indices into them.
+    fn write_mesh(&mut self, to_world: Matrix, m: &Mesh) -> io::Result<()> {
+        for p in m.vertices().iter() {
+            let q = to_world.mult(*p);
+            writeln!(self.output, "v {} {} {}", q.x(), q.y(), q.z())?;
+        }
+        let normal_to_world = to_world.inverse().transpose();
+        for n in m.normals().iter() {
+            let n = normal_to_world.mult(*n);
+            let n = vector(n.x(), n.y(), n.z()).normalize();
+            writeln!(self.output, "vn {} {} {}", n.x(), n.y(), n.z())?;
+        }
+        for (u, v) in m.texture_coords().iter() {
+            writeln!(self.output, "vt {} {}", u, v)?;
+        }
+
+        let widen = |is: [u32; 3]| [is[0] as usize, is[1] as usize, is[2] as usize];
+        for f in m.faces().iter() {
+            self.write_face(widen(f.vertices), f.texture_coords.map(widen), f.normals.map(widen))?;
+        }
+
+        self.vertices += m.vertices().len();
+        self.normals += m.normals().len();
+        self.texture_coords += m.texture_coords().len();
+        Ok(())
+    }
+
+    /// Writes an `f` record.  The indices count from 0, from the first
+    /// item written since the last vertex, normal or texture
+    /// co-ordinate already referred to.
+    fn write_face(&mut self, vs: [usize; 3], ts: Option<[usize; 3]>, ns: Option<[usize; 3]>) -> io::Result<()> {
+        write!(self.output, "f")?;
+        for i in 0..3 {
+            let v = self.vertices + vs[i] + 1;
+            match (ts, ns) {
+                (None, None) => write!(self.output, " {}", v)?,
+                (None, Some(n)) => write!(self.output, " {}//{}", v, self.normals + n[i] + 1)?,
+                (Some(t), None) => write!(self.output, " {}/{}", v, self.texture_coords + t[i] + 1)?,
+                (Some(t), Some(n)) =>
+                    write!(self.output, " {}/{}/{}", v, self.texture_coords + t[i] + 1, self.normals + n[i] + 1)?,
+            }
+        }
+        writeln!(self.output)
+    }
+}
+
+fn sphere_facets(segments: usize) -> Vec<Facet> {
+    let bands = (segments / 2).max(2);
+    // on a unit sphere, the normal at a point is the point itself
+    let at = |band: usize, seg: usize| {
+        let phi = PI * band as f64 / bands as f64;
+        let theta = 2.0 * PI * seg as f64 / segments as f64;
+        point(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin())
+    };
+    let facet = |a: Tuple4, b: Tuple4, c: Tuple4| ([a, b, c], [a - origin(), b - origin(), c - origin()]);
+
+    let mut facets = vec![];
+    for band in 0..bands {
+        for seg in 0..segments {
+            let (a, b) = (at(band, seg), at(band, seg + 1));
+            let (c, d) = (at(band + 1, seg + 1), at(band + 1, seg));
+            // the bands touching the poles are made of triangles
+            if band != 0 {
+                facets.push(facet(a, b, d));
+            }
+            if band != bands - 1 {
+                facets.push(facet(b, c, d));
+            }
+        }
+    }
+    facets
+}
+
+fn cube_facets() -> Vec<Facet> {
+    let mut facets = vec![];
+    for axis in 0..3 {
+        for &sign in [-1.0, 1.0].iter() {
+            // two unit vectors spanning the face, and its normal
+            let along = |i: usize, x: f64| {
+                let mut v = [0.0; 3];
+                v[(axis + i) % 3] = x;
+                vector(v[0], v[1], v[2])
+            };
+            let normal = along(0, sign);
+            let (s, t) = (along(1, 1.0), along(2, 1.0));
+            let centre = origin() + normal;
+            let corners = [centre - s - t, centre + s - t, centre + s + t, centre - s + t];
+            facets.push(([corners[0], corners[1], corners[2]], [normal; 3]));
+            facets.push(([corners[0], corners[2], corners[3]], [normal; 3]));
+        }
+    }
+    facets
+}
+
+fn cylinder_facets(segments: usize, kind: CylKind, lbound: f64, ubound: f64) -> Vec<Facet> {
+    let around = |seg: usize| {
+        let theta = 2.0 * PI * seg as f64 / segments as f64;
+        (theta.cos(), theta.sin())
+    };
+    let (up, down) = (vector(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+
+    let mut facets = vec![];
+    for seg in 0..segments {
+        let ((x1, z1), (x2, z2)) = (around(seg), around(seg + 1));
+        let (n1, n2) = (vector(x1, 0.0, z1), vector(x2, 0.0, z2));
+        let (a, b) = (point(x1, lbound, z1), point(x2, lbound, z2));
+        let (c, d) = (point(x2, ubound, z2), point(x1, ubound, z1));
+        facets.push(([a, b, c], [n1, n2, n2]));
+        facets.push(([a, c, d], [n1, n2, n1]));
+        if kind == CylKind::Closed {
+            facets.push(([point(0.0, lbound, 0.0), b, a], [down; 3]));
+            facets.push(([point(0.0, ubound, 0.0), d, c], [up; 3]));
+        }
+    }
+    facets
+}
+
+fn cone_facets(segments: usize, kind: CylKind, lbound: f64, ubound: f64) -> Vec<Facet> {
+    let around = |seg: usize| {
+        let theta = 2.0 * PI * seg as f64 / segments as f64;
+        (theta.cos(), theta.sin())
+    };
+    let ring = |(x, z): (f64, f64), y: f64| point(x * y.abs(), y, z * y.abs());
+    // a double-napped cone is made of two, meeting at the apex
+    let nappes = if lbound < 0.0 && ubound > 0.0 {
+        vec![(lbound, 0.0), (0.0, ubound)]
     } else {
-        Err(ParseError::BadInstruction)
+        vec![(lbound, ubound)]
+    };
+
+    let mut facets = vec![];
+    for (low, high) in nappes {
+        // the normal slopes away from the apex
+        let slope = if low + high > 0.0 { -1.0 } else { 1.0 };
+        for seg in 0..segments {
+            let (p, q) = (around(seg), around(seg + 1));
+            let (n1, n2) = (vector(p.0, slope, p.1), vector(q.0, slope, q.1));
+            let (a, b) = (ring(p, low), ring(q, low));
+            let (c, d) = (ring(q, high), ring(p, high));
+            // either triangle shrinks to a line at the apex
+            if low != 0.0 {
+                facets.push(([a, b, c], [n1, n2, n2]));
+            }
+            if high != 0.0 {
+                facets.push(([a, c, d], [n1, n2, n1]));
+            }
+        }
+    }
+    if kind == CylKind::Closed {
+        let (up, down) = (vector(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+        for seg in 0..segments {
+            let (p, q) = (around(seg), around(seg + 1));
+            if lbound != 0.0 {
+                facets.push(([point(0.0, lbound, 0.0), ring(q, lbound), ring(p, lbound)], [down; 3]));
+            }
+            if ubound != 0.0 {
+                facets.push(([point(0.0, ubound, 0.0), ring(p, ubound), ring(q, ubound)], [up; 3]));
+            }
+        }
+    }
+    facets
+}
+
+fn torus_facets(segments: usize, major: f64, minor: f64) -> Vec<Facet> {
+    let bands = (segments / 2).max(3);
+    let around = |seg: usize| {
+        let theta = 2.0 * PI * seg as f64 / segments as f64;
+        (theta.cos(), theta.sin())
+    };
+    // the normal at a point some way around the axis and the tube
+    let normal = |seg: usize, band: usize| {
+        let (x, z) = around(seg);
+        let phi = 2.0 * PI * band as f64 / bands as f64;
+        vector(phi.cos() * x, phi.sin(), phi.cos() * z)
+    };
+    let at = |seg: usize, band: usize| {
+        let (x, z) = around(seg);
+        point(major * x, 0.0, major * z) + normal(seg, band).scale(minor)
+    };
+
+    let mut facets = vec![];
+    for seg in 0..segments {
+        for band in 0..bands {
+            let (a, b) = (at(seg, band), at(seg + 1, band));
+            let (c, d) = (at(seg + 1, band + 1), at(seg, band + 1));
+            let (na, nb) = (normal(seg, band), normal(seg + 1, band));
+            let (nc, nd) = (normal(seg + 1, band + 1), normal(seg, band + 1));
+            facets.push(([a, b, c], [na, nb, nc]));
+            facets.push(([a, c, d], [na, nc, nd]));
+        }
     }
+    facets
+}
+
+fn disc_facets(segments: usize, radius: f64) -> Vec<Facet> {
+    let around = |seg: usize| {
+        let theta = 2.0 * PI * seg as f64 / segments as f64;
+        point(radius * theta.cos(), 0.0, radius * theta.sin())
+    };
+    let up = vector(0.0, 1.0, 0.0);
+    (0..segments)
+        .map(|seg| ([origin(), around(seg + 1), around(seg)], [up; 3]))
+        .collect()
+}
+
+fn origin() -> Tuple4 {
+    point(0.0, 0.0, 0.0)
 }
 
 #[cfg(test)]
@@ -268,6 +960,7 @@ mod test_string_to_polygon_point_conversion {
     fn with_slashes_produces_a_vertex_index_and_normal() {
         let p : PolygonPoint = "8//10".parse::<PolygonPoint>().unwrap();
         assert_eq!(p.vertex_index, 8);
+        assert_eq!(p.texture_index, None);
         assert_eq!(p.normal_index, Some(10));
     }
 
@@ -276,19 +969,32 @@ mod test_string_to_polygon_point_conversion {
     fn allow_a_texture_index() {
         let p : PolygonPoint = "8/7/10".parse::<PolygonPoint>().unwrap();
         assert_eq!(p.vertex_index, 8);
+        assert_eq!(p.texture_index, Some(7));
         assert_eq!(p.normal_index, Some(10));
     }
 
     #[allow(non_snake_case)]
     #[test]
-    fn dont_permit_negative_vertex_indices() {
-        assert_eq!("-8//10".parse::<PolygonPoint>().is_err(), true);
+    fn allow_a_texture_index_without_a_normal() {
+        let p : PolygonPoint = "8/7".parse::<PolygonPoint>().unwrap();
+        assert_eq!(p.vertex_index, 8);
+        assert_eq!(p.texture_index, Some(7));
+        assert_eq!(p.normal_index, None);
+    }
+
+    #[allow(non_snake_case)]
+    #[test]
+    fn dont_permit_more_than_three_indices() {
+        assert_eq!("8/7/10/2".parse::<PolygonPoint>().is_err(), true);
     }
 
     #[allow(non_snake_case)]
     #[test]
-    fn dont_permit_negative_normal_indices() {
-        assert_eq!("8//-10".parse::<PolygonPoint>().is_err(), true);
+    fn permit_negative_indices() {
+        let p : PolygonPoint = "-8/-7/-10".parse::<PolygonPoint>().unwrap();
+        assert_eq!(p.vertex_index, -8);
+        assert_eq!(p.texture_index, Some(-7));
+        assert_eq!(p.normal_index, Some(-10));
     }
 
     #[allow(non_snake_case)]
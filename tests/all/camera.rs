@@ -1,5 +1,6 @@
 use bucktracer::*;
 use bucktracer::math::*;
+use bucktracer::rng::Rng;
 use std::f64::consts::*;
 use crate::almost_eq;
 
@@ -66,3 +67,127 @@ fn render_world_with_camera() {
     let image = c.render(&w, |_, _|{});
     assert_eq!(image.colour_at(5, 5), colour(0.38066, 0.47583, 0.2855))
 }
+
+#[test]
+fn parallel_render_matches_serial_render() {
+    let w = World::default();
+    let mut c = Camera::new(21, 13, FRAC_PI_2);
+    c.orient(point(0.0, 0.0, -5.0), point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+
+    let serial = c.render(&w, |_, _|{});
+    let parallel = c.render_parallel(&w, 4, |_, _|{});
+    for y in 0..serial.height() {
+        for x in 0..serial.width() {
+            assert_eq!(serial.colour_at(x, y), parallel.colour_at(x, y));
+        }
+    }
+}
+
+#[test]
+fn parallel_render_reports_every_row_as_complete() {
+    let w = World::default();
+    let c = Camera::new(5, 7, FRAC_PI_2);
+    let mut reported = vec![];
+    c.render_parallel(&w, 3, |done, total| reported.push((done, total)));
+    assert_eq!(reported, (1..=7).map(|d| (d, 7)).collect::<Vec<(u32, u32)>>());
+}
+
+#[test]
+fn world_can_be_shared_between_threads() {
+    fn assert_sync<T: Send + Sync>() {}
+    assert_sync::<World>();
+    assert_sync::<Object>();
+}
+
+#[test]
+fn camera_samples_pixel_centres_by_default() {
+    let c = Camera::new(160, 120, FRAC_PI_2);
+    assert_eq!(c.sampling(), Sampling::Centre);
+}
+
+#[test]
+fn ray_through_pixel_centre_is_the_same_as_ray_for_pixel() {
+    let c = Camera::new(201, 101, FRAC_PI_2);
+    let r = c.ray_through_pixel(0, 0, 0.5, 0.5);
+    assert_eq!(r.direction(), c.ray_for_pixel(0, 0).direction());
+}
+
+#[test]
+fn grid_sampling_spreads_offsets_evenly_over_the_pixel() {
+    let mut rng = Rng::seeded(1);
+    assert_eq!(Sampling::Centre.offsets(&mut rng), vec![(0.5, 0.5)]);
+    assert_eq!(
+        Sampling::Grid(2).offsets(&mut rng),
+        vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+    );
+}
+
+#[test]
+fn jittered_sampling_places_one_sample_in_each_cell() {
+    let mut rng = Rng::seeded(7);
+    let offsets = Sampling::Jittered(3).offsets(&mut rng);
+    assert_eq!(offsets.len(), 9);
+    for (k, (dx, dy)) in offsets.iter().enumerate() {
+        let (i, j) = ((k % 3) as f64, (k / 3) as f64);
+        assert!(i / 3.0 <= *dx && *dx < (i + 1.0) / 3.0);
+        assert!(j / 3.0 <= *dy && *dy < (j + 1.0) / 3.0);
+    }
+}
+
+#[test]
+fn random_sampling_is_reproducible_from_a_seed() {
+    let a = Sampling::Random(5).offsets(&mut Rng::seeded(42));
+    let b = Sampling::Random(5).offsets(&mut Rng::seeded(42));
+    assert_eq!(a.len(), 5);
+    assert_eq!(a, b);
+    for (dx, dy) in a {
+        assert!(0.0 <= dx && dx < 1.0);
+        assert!(0.0 <= dy && dy < 1.0);
+    }
+}
+
+#[test]
+fn parse_sampling_strategies() {
+    assert_eq!("centre".parse::<Sampling>().unwrap(), Sampling::Centre);
+    assert_eq!("grid:3".parse::<Sampling>().unwrap(), Sampling::Grid(3));
+    assert_eq!("jitter:4".parse::<Sampling>().unwrap(), Sampling::Jittered(4));
+    assert_eq!("random:16".parse::<Sampling>().unwrap(), Sampling::Random(16));
+    assert_eq!("grid".parse::<Sampling>().is_err(), true);
+    assert_eq!("grid:0".parse::<Sampling>().is_err(), true);
+    assert_eq!("blur:2".parse::<Sampling>().is_err(), true);
+}
+
+#[test]
+fn supersampled_pixel_on_an_edge_blends_colours() {
+    let mut objects = World::default().objects().to_vec();
+    objects.truncate(1);
+    objects[0].mut_material().set_ambient(1.0).set_diffuse(0.0).set_specular(0.0)
+        .set_pattern(Pattern::solid(RGB::white()));
+    let w = World::with(World::default().light_sources().to_vec(), objects);
+
+    // the sphere's silhouette passes through the middle of pixel (0, 1)
+    let mut c = Camera::new(2, 3, FRAC_PI_2);
+    c.orient(point(0.0, 0.0, -2.0), point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+    let aliased = c.colour_of_pixel(&w, 0, 1, &mut Rng::seeded(0));
+    c.set_sampling(Sampling::Grid(4));
+    let smoothed = c.colour_of_pixel(&w, 0, 1, &mut Rng::seeded(0));
+
+    assert_ne!(aliased, smoothed);
+    assert!(smoothed.red() > 0.0 && smoothed.red() < 1.0);
+}
+
+#[test]
+fn jittered_render_is_deterministic_and_independent_of_threads() {
+    let w = World::default();
+    let mut c = Camera::new(11, 11, FRAC_PI_2);
+    c.orient(point(0.0, 0.0, -5.0), point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+    c.set_sampling(Sampling::Jittered(2)).set_seed(99);
+
+    let serial = c.render(&w, |_, _|{});
+    let parallel = c.render_parallel(&w, 3, |_, _|{});
+    for y in 0..serial.height() {
+        for x in 0..serial.width() {
+            assert_eq!(serial.colour_at(x, y), parallel.colour_at(x, y));
+        }
+    }
+}
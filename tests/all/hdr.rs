@@ -0,0 +1,91 @@
+use bucktracer::*;
+use bucktracer::hdr;
+
+fn assert_close(actual: RGB, expected: RGB) {
+    // each channel keeps 8 bits of mantissa relative to the brightest
+    let tolerance = expected.red().max(expected.green()).max(expected.blue()) / 128.0;
+    for (a, e) in [(actual.red(), expected.red()), (actual.green(), expected.green()), (actual.blue(), expected.blue())].iter() {
+        assert!((a - e).abs() <= tolerance, "{:?} is not close to {:?}", actual, expected);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn encode___writes_header_then_four_bytes_per_pixel() {
+    let cvs = canvas(3, 2);
+    let mut bytes: Vec<u8> = vec![];
+    hdr::encode(&cvs, &mut bytes).unwrap();
+
+    let header = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 3\n";
+    assert_eq!(String::from_utf8_lossy(&bytes[..header.len()]), header);
+    assert_eq!(&bytes[header.len()..], &[0u8; 3 * 2 * 4][..]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___reads_back_values_beyond_one() {
+    let mut cvs = canvas(3, 2);
+    cvs.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+    cvs.set_colour_at(1, 0, colour(12.5, 3.0, 0.25));
+    cvs.set_colour_at(2, 0, colour(0.001, 0.002, 0.003));
+    cvs.set_colour_at(0, 1, colour(1000.0, 1000.0, 1000.0));
+    cvs.set_colour_at(2, 1, colour(-1.0, 0.5, 0.5));
+    let mut bytes: Vec<u8> = vec![];
+    hdr::encode(&cvs, &mut bytes).unwrap();
+
+    let decoded = hdr::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    assert_close(decoded.colour_at(0, 0), colour(1.0, 0.0, 0.0));
+    assert_close(decoded.colour_at(1, 0), colour(12.5, 3.0, 0.25));
+    assert_close(decoded.colour_at(2, 0), colour(0.001, 0.002, 0.003));
+    assert_close(decoded.colour_at(0, 1), colour(1000.0, 1000.0, 1000.0));
+    assert_eq!(decoded.colour_at(1, 1), colour(0.0, 0.0, 0.0));
+    // negative values can't be stored
+    assert_close(decoded.colour_at(2, 1), colour(0.0, 0.5, 0.5));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___reads_run_length_encoded_scanlines() {
+    let mut bytes = b"#?RADIANCE\n# made by hand\nFORMAT=32-bit_rle_rgbe\nEXPOSURE=1.0\n\n-Y 1 +X 8\n".to_vec();
+    bytes.extend_from_slice(&[2, 2, 0, 8]);
+    // red: a run of 8
+    bytes.extend_from_slice(&[128 + 8, 128]);
+    // green: 2 literal values then a run of 6
+    bytes.extend_from_slice(&[2, 0, 64, 128 + 6, 0]);
+    // blue: a run of 8
+    bytes.extend_from_slice(&[128 + 8, 0]);
+    // exponent: a run of 8, making the red channel 1.0
+    bytes.extend_from_slice(&[128 + 8, 129]);
+
+    let decoded = hdr::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (8, 1));
+    assert_close(decoded.colour_at(0, 0), colour(1.0, 0.0, 0.0));
+    assert_close(decoded.colour_at(1, 0), colour(1.0, 0.5, 0.0));
+    assert_close(decoded.colour_at(7, 0), colour(1.0, 0.0, 0.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___rejects_other_files() {
+    assert!(hdr::decode(&mut "P3\n1 1\n255\n0 0 0\n".as_bytes()).is_err());
+    assert!(hdr::decode(&mut "#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0".as_bytes()).is_err());
+    assert!(hdr::decode(&mut "#?RADIANCE\n\n-Y 2 +X 1\n\0\0\0\0".as_bytes()).is_err());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___rejects_sizes_too_large_to_hold() {
+    let header = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", usize::MAX / 2, 3);
+    let e = hdr::decode(&mut header.as_bytes()).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___rejects_sizes_larger_than_its_pixels_could_fill() {
+    let mut file = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1000000 +X 1000000\n".to_vec();
+    file.extend_from_slice(&[2, 2, 0x0f, 0x42]);
+    let e = hdr::decode(&mut file.as_slice()).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,235 @@
+use bucktracer::*;
+use bucktracer::math::*;
+
+fn example_area_light() -> LightSource {
+    area_light(point(0.0, 0.0, 0.0), vector(2.0, 0.0, 0.0), 4, vector(0.0, 0.0, 1.0), 2, RGB::white())
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn area_light___divides_its_edges_into_cells() {
+    let light = example_area_light();
+    assert_eq!(light.kind(), LightKind::Area {
+        corner: point(0.0, 0.0, 0.0),
+        uvec: vector(0.5, 0.0, 0.0),
+        usteps: 4,
+        vvec: vector(0.0, 0.0, 0.5),
+        vsteps: 2,
+    });
+    assert_eq!(light.intensity(), RGB::white());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn area_light___position_is_its_centre() {
+    assert_eq!(example_area_light().position(), point(1.0, 0.0, 0.5));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn point_light___has_a_single_sample_at_its_position() {
+    let light = point_light(point(1.0, 2.0, 3.0), RGB::white());
+    assert_eq!(light.sample_points(point(0.0, 0.0, 0.0)), vec![point(1.0, 2.0, 3.0)]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn area_light___samples_once_within_each_cell() {
+    let light = example_area_light();
+    let samples = light.sample_points(point(0.3, 4.0, -2.0));
+    assert_eq!(samples.len(), 8);
+    for (k, p) in samples.iter().enumerate() {
+        let (i, j) = ((k % 4) as f64, (k / 4) as f64);
+        assert!(i * 0.5 <= p.x() && p.x() < (i + 1.0) * 0.5);
+        assert!(j * 0.5 <= p.z() && p.z() < (j + 1.0) * 0.5);
+        assert_eq!(p.y(), 0.0);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn area_light___samples_are_the_same_for_the_same_point() {
+    let light = example_area_light();
+    let p = point(0.3, 4.0, -2.0);
+    assert_eq!(light.sample_points(p), light.sample_points(p));
+    assert_ne!(light.sample_points(p), light.sample_points(point(0.3, 4.0, -2.5)));
+}
+
+fn world_with_area_light() -> World {
+    let light = area_light(
+        point(-0.5, -0.5, -5.0), vector(1.0, 0.0, 0.0), 4, vector(0.0, 1.0, 0.0), 4, RGB::white()
+    );
+    World::with(vec![light], World::default().objects().to_vec())
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn area_light___light_factor_ranges_from_shadow_through_penumbra_to_lit() {
+    let w = world_with_area_light();
+    let light = w.light_sources()[0];
+
+    // directly behind the spheres
+    assert_eq!(w.light_factor(point(0.0, 0.0, 2.0), &light), 0.0);
+    // beside the spheres, nothing in the way
+    assert_eq!(w.light_factor(point(3.0, 0.0, 2.0), &light), 1.0);
+    // in front of the spheres
+    assert_eq!(w.light_factor(point(0.0, 0.0, -2.0), &light), 1.0);
+
+    // partially hidden from the light by the edge of the outer sphere
+    let penumbra = w.light_factor(point(1.6, 0.0, 3.0), &light);
+    assert!(penumbra > 0.0 && penumbra < 1.0, "light factor was {}", penumbra);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn area_light___lighting_averages_over_samples() {
+    let light = area_light(
+        point(-0.5, -0.5, -5.0), vector(1.0, 0.0, 0.0), 2, vector(0.0, 1.0, 0.0), 2, RGB::white()
+    );
+    let s = unit_sphere();
+    let pos = point(0.0, 0.0, -1.0);
+    let eyev = (point(0.0, 0.0, -5.0) - pos).normalize();
+    let normalv = vector(0.0, 0.0, -1.0);
+
+    let lit = lighting(&light, pos, normalv, &s, eyev, 1.0);
+    let half_lit = lighting(&light, pos, normalv, &s, eyev, 0.5);
+    let unlit = lighting(&light, pos, normalv, &s, eyev, 0.0);
+
+    assert_eq!(unlit, colour(0.1, 0.1, 0.1));
+    assert!(lit.red() > half_lit.red() && half_lit.red() > unlit.red());
+    // every sample is nearly straight ahead, so the diffuse term is
+    // only a touch less than a point light at the centre would give
+    let centred = lighting(&point_light(point(0.0, 0.0, -5.0), RGB::white()), pos, normalv, &s, eyev, 1.0);
+    assert!(lit.red() < centred.red() && lit.red() > 0.95);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn directional_light___is_found_back_along_its_direction() {
+    let light = directional_light(vector(0.0, -2.0, 0.0), RGB::white());
+    let at = point(5.0, 0.0, 3.0);
+    assert_eq!(light.sample_points(at), vec![point(5.0, 1.0, 3.0)]);
+    assert_eq!(light.towards(at, light.sample_points(at)[0]), (vector(0.0, 1.0, 0.0), f64::INFINITY));
+}
+
+#[allow(non_snake_case)]
+#[test]
+#[should_panic(expected = "a directional light has no position")]
+fn directional_light___has_no_position() {
+    directional_light(vector(0.0, -2.0, 0.0), RGB::white()).position();
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn directional_light___lights_every_point_from_the_same_direction() {
+    let light = directional_light(vector(0.0, 0.0, 1.0), RGB::white());
+    let s = unit_sphere();
+    let eyev = vector(0.0, 0.0, -1.0);
+    let normalv = vector(0.0, 0.0, -1.0);
+
+    let near = lighting(&light, point(0.0, 0.0, -1.0), normalv, &s, eyev, 1.0);
+    let far = lighting(&light, point(100.0, -30.0, 50.0), normalv, &s, eyev, 1.0);
+    assert_eq!(near, colour(1.9, 1.9, 1.9));
+    assert_eq!(far, colour(1.9, 1.9, 1.9));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn directional_light___casts_shadows() {
+    let light = directional_light(vector(0.0, 0.0, 1.0), RGB::white());
+    let w = World::with(vec![light], World::default().objects().to_vec());
+
+    assert_eq!(w.light_factor(point(0.0, 0.0, 10.0), &light), 0.0);
+    assert_eq!(w.light_factor(point(3.0, 0.0, 10.0), &light), 1.0);
+    assert_eq!(w.light_factor(point(0.0, 0.0, -10.0), &light), 1.0);
+}
+
+fn example_spot_light() -> LightSource {
+    spot_light(
+        point(0.0, 0.0, -10.0),
+        vector(0.0, 0.0, 1.0),
+        10f64.to_radians(),
+        20f64.to_radians(),
+        RGB::white(),
+    )
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn spot_light___cone_factor_falls_off_between_inner_and_outer_angles() {
+    let light = example_spot_light();
+    let from_angle = |deg: f64| {
+        let a = deg.to_radians();
+        -vector(a.sin(), 0.0, a.cos())
+    };
+    assert_eq!(light.cone_factor(from_angle(0.0)), 1.0);
+    assert_eq!(light.cone_factor(from_angle(9.0)), 1.0);
+    assert_eq!(light.cone_factor(from_angle(21.0)), 0.0);
+    assert_eq!(light.cone_factor(from_angle(90.0)), 0.0);
+
+    let mid = light.cone_factor(from_angle(15.0));
+    assert!(mid > 0.0 && mid < 1.0, "cone factor was {}", mid);
+    assert!(light.cone_factor(from_angle(12.0)) > mid);
+    assert!(light.cone_factor(from_angle(18.0)) < mid);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn spot_light___lights_only_within_its_cone() {
+    let light = example_spot_light();
+    let s = unit_sphere();
+    let eyev = vector(0.0, 0.0, -1.0);
+    let normalv = vector(0.0, 0.0, -1.0);
+
+    let on_axis = lighting(&light, point(0.0, 0.0, 0.0), normalv, &s, eyev, 1.0);
+    let point_lit = lighting(&point_light(point(0.0, 0.0, -10.0), RGB::white()),
+                             point(0.0, 0.0, 0.0), normalv, &s, eyev, 1.0);
+    assert_eq!(on_axis, point_lit);
+
+    let outside = lighting(&light, point(10.0, 0.0, 0.0), normalv, &s, eyev, 1.0);
+    assert_eq!(outside, colour(0.1, 0.1, 0.1));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn spot_light___casts_shadows() {
+    let light = spot_light(
+        point(0.0, 0.0, -10.0), vector(0.0, 0.0, 1.0), 0.5, 0.6, RGB::white()
+    );
+    let w = World::with(vec![light], World::default().objects().to_vec());
+
+    assert_eq!(w.light_factor(point(0.0, 0.0, 10.0), &light), 0.0);
+    assert_eq!(w.light_factor(point(0.0, 0.0, -2.0), &light), 1.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn attenuation___defaults_to_none() {
+    let light = point_light(point(0.0, 0.0, -10.0), RGB::white());
+    assert_eq!(light.attenuation(), Attenuation::new(1.0, 0.0, 0.0));
+    assert_eq!(light.attenuation().factor(1000.0), 1.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn attenuation___factor_combines_coefficients() {
+    assert_eq!(Attenuation::inverse_square().factor(2.0), 0.25);
+    assert_eq!(Attenuation::new(1.0, 0.5, 0.25).factor(2.0), 1.0 / 3.0);
+    // directional lights are infinitely far away, yet undiminished
+    assert_eq!(Attenuation::inverse_square().factor(f64::INFINITY), 1.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn attenuation___scales_diffuse_and_specular_but_not_ambient() {
+    let mut light = point_light(point(0.0, 0.0, -10.0), RGB::white());
+    light.set_attenuation(Attenuation::new(0.0, 0.0, 0.02));
+    let s = unit_sphere();
+    let pos = point(0.0, 0.0, 0.0);
+    let eyev = vector(0.0, 0.0, -1.0);
+    let normalv = vector(0.0, 0.0, -1.0);
+
+    // 10 units away, the light is at half strength: 0.1 + (0.9 + 0.9) / 2
+    assert_eq!(lighting(&light, pos, normalv, &s, eyev, 1.0), colour(1.0, 1.0, 1.0));
+    assert_eq!(lighting(&light, pos, normalv, &s, eyev, 0.0), colour(0.1, 0.1, 0.1));
+}
@@ -1,3 +1,4 @@
 mod matrices;
 mod tuple4;
 mod transformations;
+mod solvers;
@@ -0,0 +1,62 @@
+use bucktracer::math::*;
+use crate::almost_eq;
+
+fn assert_roots(actual: Vec<f64>, expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len(), "roots {:?}", actual);
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(almost_eq(*a, *e), "roots {:?}, expected {:?}", actual, expected);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn quadratic___finds_real_roots_in_order() {
+    assert_roots(solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+    assert_roots(solve_quadratic(-1.0, 3.0, -2.0), &[1.0, 2.0]);
+    assert_roots(solve_quadratic(1.0, -2.0, 1.0), &[1.0, 1.0]);
+    assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+    assert_roots(solve_quadratic(0.0, 2.0, -1.0), &[0.5]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn quadratic___small_root_keeps_its_precision_next_to_a_large_one() {
+    let roots = solve_quadratic(1.0, 1e8, 1.0);
+    assert!(((roots[1] + 1e-8) / 1e-8).abs() < 1e-9);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn cubic___finds_real_roots_in_order() {
+    assert_roots(solve_cubic(1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+    assert_roots(solve_cubic(2.0, -12.0, 22.0, -12.0), &[1.0, 2.0, 3.0]);
+    assert_roots(solve_cubic(1.0, 0.0, 0.0, -8.0), &[2.0]);
+    assert_roots(solve_cubic(0.0, 1.0, -3.0, 2.0), &[1.0, 2.0]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn quartic___finds_real_roots_in_order() {
+    assert_roots(solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0), &[1.0, 2.0, 3.0, 4.0]);
+    assert_roots(solve_quartic(3.0, -30.0, 105.0, -150.0, 72.0), &[1.0, 2.0, 3.0, 4.0]);
+    assert_roots(solve_quartic(1.0, 0.0, -5.0, 0.0, 4.0), &[-2.0, -1.0, 1.0, 2.0]);
+    // (x² + 1)(x - 1)(x + 2)
+    assert_roots(solve_quartic(1.0, 1.0, -1.0, 1.0, -2.0), &[-2.0, 1.0]);
+    assert_roots(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), &[]);
+    assert_roots(solve_quartic(0.0, 1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn quartic___roots_close_together_and_far_apart___are_accurate() {
+    // (x - 1)(x - 1.001)(x + 300)(x - 500)
+    let (a, b, c, d) = (1.0, 1.001, -300.0, 500.0);
+    let roots = solve_quartic(
+        1.0,
+        -(a + b + c + d),
+        a * b + a * c + a * d + b * c + b * d + c * d,
+        -(a * b * c + a * b * d + a * c * d + b * c * d),
+        a * b * c * d,
+    );
+    assert_roots(roots, &[-300.0, 1.0, 1.001, 500.0]);
+}
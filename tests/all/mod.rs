@@ -5,7 +5,16 @@ mod canvas;
 mod patterns;
 mod material;
 mod ppm;
+mod hdr;
+mod pfm;
 mod rays;
 mod wavefront;
+mod stl;
+mod ply;
+mod model;
 mod world;
 mod partitioning;
+mod scene;
+mod lights;
+mod textures;
+mod tonemap;
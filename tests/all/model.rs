@@ -0,0 +1,70 @@
+use bucktracer::*;
+use std::path::{Path, PathBuf};
+
+const SQUARE_STL: &str = r##"solid square
+  facet normal 0 0 -1
+    outer loop
+      vertex -1 1 0
+      vertex -1 0 0
+      vertex 1 0 0
+    endloop
+  endfacet
+  facet normal 0 0 -1
+    outer loop
+      vertex -1 1 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+endsolid square
+"##;
+
+const SQUARE_OBJ: &str = r##"v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+f 1 2 3 4
+"##;
+
+fn model_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("bucktracer_models");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn model_format___goes_by_the_extension() {
+    assert_eq!(ModelFormat::of(Path::new("teapot.STL")), ModelFormat::Stl);
+    assert_eq!(ModelFormat::of(Path::new("models/bunny.ply")), ModelFormat::Ply);
+    assert_eq!(ModelFormat::of(Path::new("teapot.obj")), ModelFormat::Obj);
+    assert_eq!(ModelFormat::of(Path::new("teapot")), ModelFormat::Obj);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_model___reads_stl_and_obj_files_alike() {
+    let stl = read_model(&model_file("square.stl", SQUARE_STL)).unwrap();
+    let obj = read_model(&model_file("square.obj", SQUARE_OBJ)).unwrap();
+    assert_eq!(stl.len(), 2);
+    assert_eq!(obj.len(), 2);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_model_mesh___reads_a_single_mesh() {
+    let objects = read_model_mesh(&model_file("square_mesh.obj", SQUARE_OBJ)).unwrap();
+    assert_eq!(objects.len(), 1);
+    assert!(matches!(objects[0].shape(), Shape::Mesh(_)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_model___names_the_file_in_errors() {
+    let path = model_file("broken.stl", "solid broken\n  facet\n");
+    let e = read_model(&path).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert!(e.to_string().starts_with(&format!("{}: ", path.display())));
+}
@@ -311,3 +311,99 @@ f 2 4 8 6
 }
 
 // TODO test when the shapes are in one plane (one of the axes has exactly one value)
+
+fn small_sphere_at(x: f64, y: f64, z: f64) -> Object {
+    unit_sphere().set_object_to_world_spc(translation(x, y, z) * scaling(0.1, 0.1, 0.1)).clone()
+}
+
+/// A row of small spheres along the x axis
+fn sphere_row(count: usize) -> Vec<Object> {
+    (0..count).map(|i| small_sphere_at(i as f64, 0.0, 0.0)).collect()
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bvh___no_objects___generates_empty_group() {
+    let group = bvh_partition(vec![]);
+    assert_eq!(group.children().len(), 0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bvh___one_shape___generates_a_group_of_one() {
+    let tri = triangle(point(1.0, 0.0, 0.0), point(0.0, 1.0, 0.0), point(0.0, 0.0, 1.0));
+    assert_eq!(bvh_partition(vec![tri.clone()]), group(vec![tri]));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bvh___separate_clusters___are_split_into_separate_groups() {
+    let left: Vec<Object> = (0..4).map(|i| small_sphere_at(-10.0, i as f64 * 0.25, 0.0)).collect();
+    let right: Vec<Object> = (0..4).map(|i| small_sphere_at(10.0, i as f64 * 0.25, 0.0)).collect();
+    let mut scene = left.clone();
+    scene.extend(right.iter().cloned());
+
+    let root = bvh_partition(scene);
+    assert_eq!(root.children().len(), 2);
+    assert_eq!(flatten(&[root.children()[0].clone()]), left);
+    assert_eq!(flatten(&[root.children()[1].clone()]), right);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bvh___keeps_every_object() {
+    let scene = sphere_row(100);
+    let root = bvh_partition(scene.clone());
+    let mut kept = flatten(&[root]);
+    kept.sort_by(|a, b| a.bounds().min().x().partial_cmp(&b.bounds().min().x()).unwrap());
+    assert_eq!(kept, scene);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bvh___unbounded_objects___are_placed_in_the_root() {
+    let mut scene = sphere_row(10);
+    scene.push(plane());
+
+    let root = bvh_partition(scene);
+    assert_eq!(root.children().len(), 2);
+    assert_eq!(root.children()[0], plane());
+    assert_eq!(flatten(&[root.children()[1].clone()]).len(), 10);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bvh___objects_at_the_same_place___stay_together() {
+    let scene: Vec<Object> = (0..20).map(|_| small_sphere_at(1.0, 2.0, 3.0)).collect();
+    assert_eq!(bvh_partition(scene.clone()), group(scene));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bvh___finds_the_same_hits_as_unpartitioned_scene() {
+    let scene = sphere_row(50);
+    let flat = World::with(vec![], vec![group(scene.clone())]);
+    let partitioned = World::with(vec![], vec![bvh_partition(scene)]);
+
+    for i in 0..50 {
+        let r = ray(point(i as f64 * 0.97, 0.05, -5.0), vector(0.0, 0.0, 1.0));
+        let expected: Vec<f64> = flat.intersect(&r).iter().map(|i| i.t_value()).collect();
+        let actual: Vec<f64> = partitioned.intersect(&r).iter().map(|i| i.t_value()).collect();
+        assert_eq!(actual, expected);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn traversal_cost___of_a_single_object___is_one_intersection() {
+    assert_eq!(traversal_cost(&unit_sphere()), 1.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn traversal_cost___bvh_is_cheaper_than_a_flat_group() {
+    let scene = sphere_row(1000);
+    let flat = traversal_cost(&group(scene.clone()));
+    let bvh = traversal_cost(&bvh_partition(scene));
+    assert!(bvh * 10.0 < flat, "bvh cost {}, flat cost {}", bvh, flat);
+}
@@ -309,3 +309,33 @@ fn checkers_pattern_combinations() {
     assert_eq!(p.colour_at(point(0.0, -1.0, -1.0)), blue());
     assert_eq!(p.colour_at(point(-1.0, -1.0, -1.0)), green());
 }
+
+#[allow(non_snake_case)]
+#[test]
+fn vertex_colours___blend_by_barycentric_coordinates() {
+    let red = colour(1.0, 0.0, 0.0);
+    let p = Pattern::vertex_colours(red, green(), blue());
+    let origin = point(0.0, 0.0, 0.0);
+    assert_eq!(p.colour_at_surface(origin, None, Some((0.0, 0.0))), red);
+    assert_eq!(p.colour_at_surface(origin, None, Some((1.0, 0.0))), green());
+    assert_eq!(p.colour_at_surface(origin, None, Some((0.0, 1.0))), blue());
+    assert_eq!(p.colour_at_surface(origin, None, Some((0.25, 0.5))), colour(0.25, 0.25, 0.5));
+    // without knowing where on the triangle, the centre colour
+    assert_eq!(p.colour_at(origin), colour(1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn vertex_colours___shade_the_point_hit_on_a_triangle() {
+    let mut tri = triangle(point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0), point(-1.0, 2.0, 0.0));
+    tri.mut_material()
+        .set_pattern(Pattern::vertex_colours(colour(1.0, 0.0, 0.0), green(), blue()))
+        .set_ambient(1.0)
+        .set_diffuse(0.0)
+        .set_specular(0.0);
+    let w = World::with(vec![point_light(point(0.0, 0.0, -10.0), RGB::white())], vec![tri]);
+
+    // a quarter of the way along both edges from the first corner
+    let c = w.colour_at_intersect(&ray(point(-0.5, 0.5, -5.0), vector(0.0, 0.0, 1.0)), 1);
+    assert_eq!(c, colour(0.5, 0.25, 0.25));
+}
@@ -0,0 +1,61 @@
+use bucktracer::*;
+use bucktracer::pfm;
+
+#[allow(non_snake_case)]
+#[test]
+fn encode___writes_rows_bottom_up_as_little_endian_floats() {
+    let mut cvs = canvas(1, 2);
+    cvs.set_colour_at(0, 0, colour(1.0, 2.0, 3.0));
+    cvs.set_colour_at(0, 1, colour(4.0, 5.0, 6.0));
+    let mut bytes: Vec<u8> = vec![];
+    pfm::encode(&cvs, &mut bytes).unwrap();
+
+    let header = "PF\n1 2\n-1.0\n";
+    assert_eq!(String::from_utf8_lossy(&bytes[..header.len()]), header);
+    let values: Vec<f32> = bytes[header.len()..].chunks(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    assert_eq!(values, vec![4.0, 5.0, 6.0, 1.0, 2.0, 3.0]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___reads_back_encoded_canvas() {
+    let mut cvs = canvas(3, 2);
+    cvs.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+    cvs.set_colour_at(1, 0, colour(12.5, 3.0, 0.25));
+    cvs.set_colour_at(2, 1, colour(-1.0, 0.5, 1000.0));
+    let mut bytes: Vec<u8> = vec![];
+    pfm::encode(&cvs, &mut bytes).unwrap();
+
+    assert_eq!(pfm::decode(&mut bytes.as_slice()).unwrap(), cvs);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___reads_big_endian_greyscale() {
+    let mut bytes = b"Pf 2 1 1.0\n".to_vec();
+    bytes.extend_from_slice(&0.5f32.to_be_bytes());
+    bytes.extend_from_slice(&8.0f32.to_be_bytes());
+
+    let decoded = pfm::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!(decoded.colour_at(0, 0), colour(0.5, 0.5, 0.5));
+    assert_eq!(decoded.colour_at(1, 0), colour(8.0, 8.0, 8.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___rejects_truncated_data() {
+    let mut bytes = b"PF\n2 2\n-1.0\n".to_vec();
+    bytes.extend_from_slice(&[0; 12 * 3]);
+    assert!(pfm::decode(&mut bytes.as_slice()).is_err());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___rejects_sizes_too_large_to_hold() {
+    let mut bytes = format!("PF\n{} {}\n-1.0\n", usize::MAX / 2, 3).into_bytes();
+    bytes.extend_from_slice(&[0; 12]);
+    let e = pfm::decode(&mut bytes.as_slice()).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,135 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use bucktracer::ply;
+use bucktracer::ply::{ParseError, Reason};
+
+const ASCII: &str = r##"ply
+format ascii 1.0
+comment a square, coloured at the corners
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+-1 1 0 0 0 -1 255 0 0
+-1 0 0 0 0 -1 0 255 0
+1 0 0 0 0 -1 0 0 255
+1 1 0 0 0 -1 255 255 255
+4 0 1 2 3
+"##;
+
+fn square() -> Vec<Object> {
+    let (red, green, blue, white) = (colour(1.0, 0.0, 0.0), colour(0.0, 1.0, 0.0), colour(0.0, 0.0, 1.0), RGB::white());
+    let n = vector(0.0, 0.0, -1.0);
+    let mut t1 = smooth_triangle(point(-1.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0), n, n, n);
+    t1.mut_material().set_pattern(Pattern::vertex_colours(red, green, blue));
+    let mut t2 = smooth_triangle(point(-1.0, 1.0, 0.0), point(1.0, 0.0, 0.0), point(1.0, 1.0, 0.0), n, n, n);
+    t2.mut_material().set_pattern(Pattern::vertex_colours(red, blue, white));
+    vec![t1, t2]
+}
+
+/// The same square as ASCII, with its body in binary.
+fn binary(big_endian: bool) -> Vec<u8> {
+    let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+    let mut out = ASCII[..ASCII.find("end_header\n").unwrap() + "end_header\n".len()]
+        .replace("format ascii", &format!("format {}", format))
+        .into_bytes();
+    let rows: [([f32; 6], [u8; 3]); 4] = [
+        ([-1.0, 1.0, 0.0, 0.0, 0.0, -1.0], [255, 0, 0]),
+        ([-1.0, 0.0, 0.0, 0.0, 0.0, -1.0], [0, 255, 0]),
+        ([1.0, 0.0, 0.0, 0.0, 0.0, -1.0], [0, 0, 255]),
+        ([1.0, 1.0, 0.0, 0.0, 0.0, -1.0], [255, 255, 255]),
+    ];
+    for (xs, rgb) in rows.iter() {
+        for x in xs.iter() {
+            out.extend_from_slice(&if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+        }
+        out.extend_from_slice(rgb);
+    }
+    out.push(4);
+    for i in 0..4i32 {
+        out.extend_from_slice(&if big_endian { i.to_be_bytes() } else { i.to_le_bytes() });
+    }
+    out
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_ascii___produces_smooth_coloured_triangles() {
+    assert_eq!(ply::read_object_vec(&mut ASCII.as_bytes()).unwrap(), square());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_binary___in_either_byte_order() {
+    assert_eq!(ply::read_object_vec(&mut binary(false).as_slice()).unwrap(), square());
+    assert_eq!(ply::read_object_vec(&mut binary(true).as_slice()).unwrap(), square());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn without_normals_or_colours___produces_plain_triangles() {
+    let mut input = r##"ply
+format ascii 1.0
+element vertex 3
+property double x
+property double y
+property double z
+property float confidence
+element face 1
+property list uchar uint vertex_index
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 1 0 0.5
+-1 0 0 0.5
+1 0 0 0.5
+3 0 1 2
+0 1
+"##.as_bytes();
+    assert_eq!(ply::read_object_vec(&mut input).unwrap(), vec![
+        triangle(point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0)),
+    ]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bad_header___reports_line_and_reason() {
+    let bad = |text: &str| ply::read_object_vec(&mut text.as_bytes()).unwrap_err();
+    let header = |line: usize, text: &str, reason: Reason| {
+        ParseError::BadHeader { line, text: text.to_string(), reason }
+    };
+
+    assert_eq!(bad("solid\n"), header(1, "solid", Reason::NotPly));
+    assert_eq!(bad("ply\nformat binary_middle_endian 1.0\n"),
+               header(2, "format binary_middle_endian 1.0", Reason::UnknownFormat("binary_middle_endian".to_string())));
+    assert_eq!(bad("ply\nformat ascii 1.0\nelement vertex 1\nproperty real x\n"),
+               header(4, "property real x", Reason::UnknownType("real".to_string())));
+    assert_eq!(bad("ply\nformat ascii 1.0\nproperty float x\n"),
+               header(3, "property float x", Reason::PropertyOutsideElement));
+    assert_eq!(bad("ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n"),
+               header(5, "end_header", Reason::MissingProperty("y".to_string())));
+    assert_eq!(bad("ply\nelement vertex 0\nend_header\n"), header(3, "end_header", Reason::MissingFormat));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bad_body___is_an_error() {
+    let head = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                element face 1\nproperty list uchar int vertex_indices\nend_header\n";
+    let bad = |body: &str| ply::read_object_vec(&mut format!("{}{}", head, body).as_bytes()).unwrap_err();
+
+    assert_eq!(bad("0 1 0\n-1 0 0\n1 0 0\n3 0 1 3\n"), ParseError::IndexOutOfRange(3));
+    assert_eq!(bad("0 1 0\n-1 0 0\n1 0 0\n2 0 1\n"), ParseError::TooFewVertices);
+    assert_eq!(bad("0 1 0\n-1 0 0\n1 0 0\n"), ParseError::UnexpectedEnd);
+    assert_eq!(bad("0 1 0\n-1 zero 0\n1 0 0\n3 0 1 2\n"), ParseError::BadNumber("zero".to_string()));
+}
@@ -1,4 +1,5 @@
 use bucktracer::*;
+use bucktracer::math::*;
 use bucktracer::ppm;
 
 use std::io::Result as IOResult;
@@ -97,3 +98,104 @@ fn print_ppm_output_max_70_chars_per_line() -> IOResult<()> {
 
     Ok(())
 }
+
+#[allow(non_snake_case)]
+#[test]
+fn encode_binary___writes_header_then_a_byte_per_channel() -> IOResult<()> {
+    let mut cvs = canvas(2, 1);
+    cvs.set_colour_at(0, 0, colour(1.5, 0.5, -0.5));
+    cvs.set_colour_at(1, 0, colour(0.0, 0.8, 1.0));
+    let mut bytes: Vec<u8> = vec![];
+    ppm::encode_binary(&cvs, &mut bytes)?;
+
+    let mut expected = b"P6\n2 1\n255\n".to_vec();
+    expected.extend_from_slice(&[255, 128, 0, 0, 204, 255]);
+    assert_eq!(bytes, expected);
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___reads_plain_and_binary_ppm_alike() -> IOResult<()> {
+    let mut cvs = canvas(5, 3);
+    cvs.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+    cvs.set_colour_at(2, 1, colour(0.0, 0.2, 0.0));
+    cvs.set_colour_at(4, 2, colour(0.0, 0.0, 1.0));
+
+    let mut plain: Vec<u8> = vec![];
+    ppm::encode(&cvs, &mut plain)?;
+    let mut binary: Vec<u8> = vec![];
+    ppm::encode_binary(&cvs, &mut binary)?;
+
+    assert_eq!(ppm::decode(&mut plain.as_slice())?, cvs);
+    assert_eq!(ppm::decode(&mut binary.as_slice())?, cvs);
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___skips_comments_and_scales_by_the_maximum() -> IOResult<()> {
+    let mut input = "P3\n# made by hand\n2 1 # two pixels\n4\n4 2 0\n0 1 4\n".as_bytes();
+    let cvs = ppm::decode(&mut input)?;
+    assert_eq!(cvs.colour_at(0, 0), colour(1.0, 0.5, 0.0));
+    assert_eq!(cvs.colour_at(1, 0), colour(0.0, 0.25, 1.0));
+
+    let mut sixteen_bit = b"P6 1 1 65535\n".to_vec();
+    sixteen_bit.extend_from_slice(&[0xff, 0xff, 0x00, 0x00, 0x80, 0x00]);
+    let cvs = ppm::decode(&mut sixteen_bit.as_slice())?;
+    assert_eq!(cvs.colour_at(0, 0), colour(1.0, 0.0, 32768.0 / 65535.0));
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___rejects_bad_images() {
+    assert!(ppm::decode(&mut "P5\n1 1\n255\n\0".as_bytes()).is_err());
+    assert!(ppm::decode(&mut "P3\n1 one\n255\n0 0 0\n".as_bytes()).is_err());
+    assert!(ppm::decode(&mut "P3\n2 1\n255\n0 0 0\n".as_bytes()).is_err());
+    assert!(ppm::decode(&mut "P6\n2 1\n255\n\0\0\0".as_bytes()).is_err());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn decode___rejects_sizes_too_large_to_hold() {
+    for magic in ["P3", "P6"].iter() {
+        let image = format!("{}\n{} 3\n255\n0 0 0\n", magic, usize::MAX / 2);
+        let e = ppm::decode(&mut image.as_bytes()).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+/// Compares a render with a golden image, allowing for the rounding
+/// of each channel to 8 bits.  Run with BUCKTRACER_BLESS set to
+/// replace the golden image with the render instead.
+fn assert_matches_golden(image: &Canvas, name: &str) -> IOResult<()> {
+    let path = std::path::Path::new("tests/golden").join(name);
+    if std::env::var_os("BUCKTRACER_BLESS").is_some() {
+        std::fs::create_dir_all("tests/golden")?;
+        return ppm::encode_binary(image, &mut std::fs::File::create(&path)?);
+    }
+
+    let golden = ppm::decode(&mut std::fs::File::open(&path)?)?;
+    assert_eq!((golden.width(), golden.height()), (image.width(), image.height()));
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let (a, b) = (image.colour_at(x, y), golden.colour_at(x, y));
+            let differs = |a: f64, b: f64| (a.min(1.0).max(0.0) - b).abs() > 1.0 / 255.0;
+            if differs(a.red(), b.red()) || differs(a.green(), b.green()) || differs(a.blue(), b.blue()) {
+                panic!("pixel ({}, {}) is {:?}, but {:?} in {}", x, y, a, b, path.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn default_world_render___matches_golden_image() -> IOResult<()> {
+    let w = World::default();
+    let mut c = Camera::new(32, 24, std::f64::consts::FRAC_PI_2);
+    c.orient(point(0.0, 1.0, -3.0), point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+    let image = c.render(&w, |_, _|{});
+    assert_matches_golden(&image, "default_world.ppm")
+}
@@ -84,6 +84,24 @@ fn create_intersection() {
     assert_eq!(3.5, i.t_value());
 }
 
+#[allow(non_snake_case)]
+#[test]
+fn intersection___borrows_the_object_hit() {
+    let s = unit_sphere();
+    let i = intersection(3.5, &s);
+    assert!(std::ptr::eq(i.object(), &s));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn same_object___compares_identity_not_equality() {
+    let a = unit_sphere();
+    let b = unit_sphere();
+    assert_eq!(a, b);
+    assert!(intersection(1.0, &a).same_object(&intersection(2.0, &a)));
+    assert!(!intersection(1.0, &a).same_object(&intersection(1.0, &b)));
+}
+
 #[test]
 fn finding_hits_when_t_values_are_positive() {
     let s = unit_sphere();
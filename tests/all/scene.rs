@@ -0,0 +1,359 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use bucktracer::scene::{read_scene, SceneError};
+use std::path::Path;
+
+const CAMERA: &str = r##"
+[camera]
+width = 11
+height = 11
+field_of_view = 90.0
+from = [0.0, 0.0, -5.0]
+to = [0.0, 0.0, 0.0]
+"##;
+
+fn read(text: &str) -> Result<(World, Camera), SceneError> {
+    read_scene(text, Path::new("tests"))
+}
+
+fn error_location(text: &str) -> (usize, usize) {
+    match read(text) {
+        Err(SceneError::Invalid { line, column, .. }) => (line, column),
+        other => panic!("expected an invalid scene, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn camera_only___produces_empty_world() {
+    let (world, camera) = read(CAMERA).unwrap();
+    assert_eq!(world.objects().len(), 0);
+    assert_eq!(world.light_sources().len(), 0);
+    assert_eq!(camera.hsize(), 11);
+    assert_eq!(camera.vsize(), 11);
+    assert_eq!(camera.field_of_view(), std::f64::consts::FRAC_PI_2);
+    assert_eq!(camera.view_transform(), view_transform(
+        point(0.0, 0.0, -5.0), point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn default_world_described_in_toml___renders_same_as_default_world() {
+    let text = format!("{}{}", CAMERA, r##"
+[[lights]]
+position = [-10, 10, -10]
+
+[materials.outer]
+pattern = { Solid = [0.8, 1.0, 0.6] }
+diffuse = 0.7
+specular = 0.2
+
+[[objects]]
+shape = "sphere"
+material = "outer"
+
+[[objects]]
+shape = "sphere"
+transform = [{ scale = [0.5, 0.5, 0.5] }]
+"##);
+    let (world, camera) = read(&text).unwrap();
+    let image = camera.render(&world, |_, _|{});
+    assert_eq!(image.colour_at(5, 5), colour(0.38066, 0.47583, 0.2855));
+    assert_eq!(world.objects(), World::default().objects());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn transforms___are_applied_in_the_order_written() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "cube"
+transform = [{ scale = [2, 2, 2] }, { rotate_y = 90 }, { translate = [1, 0, 0] }]
+"##);
+    let (world, _) = read(&text).unwrap();
+    let expected = translation(1.0, 0.0, 0.0)
+        * rotation_y(std::f64::consts::FRAC_PI_2)
+        * scaling(2.0, 2.0, 2.0);
+    assert_eq!(world.objects()[0].object_to_world_spc(), expected);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn shapes_with_parameters___are_built() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "cylinder"
+min = 1.0
+max = 2.0
+closed = true
+
+[[objects]]
+shape = "cone"
+min = -1.0
+max = 0.0
+
+[[objects]]
+shape = "torus"
+minor = 0.5
+
+[[objects]]
+shape = "quad"
+points = [[0, 0, 0], [2, 0, 0], [0, 1, 0]]
+
+[[objects]]
+shape = "disc"
+radius = 3.0
+
+[[objects]]
+shape = "group"
+
+[[objects.children]]
+shape = "triangle"
+points = [[0, 1, 0], [-1, 0, 0], [1, 0, 0]]
+
+[[objects.children]]
+shape = "cube"
+"##);
+    let (world, _) = read(&text).unwrap();
+    assert_eq!(world.objects()[0], cylinder(CylKind::Closed, 1.0, 2.0));
+    assert_eq!(world.objects()[1], cone(CylKind::Open, -1.0, 0.0));
+    assert_eq!(world.objects()[2], torus(1.0, 0.5));
+    assert_eq!(world.objects()[3], quad(point(0.0, 0.0, 0.0), vector(2.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)));
+    assert_eq!(world.objects()[4], disc(3.0));
+    assert_eq!(world.objects()[5], group(vec![
+        triangle(point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0)),
+        cube(),
+    ]));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn material_on_a_group___applies_to_its_children() {
+    let text = format!("{}{}", CAMERA, r##"
+[materials.shiny]
+reflective = 0.5
+
+[[objects]]
+shape = "group"
+material = "shiny"
+children = [{ shape = "sphere" }, { shape = "cube" }]
+"##);
+    let (world, _) = read(&text).unwrap();
+    for c in world.objects()[0].children() {
+        assert_eq!(c.material().reflective(), 0.5);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn misspelt_key___reports_line_and_column() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "sphere"
+transfrom = []
+"##);
+    assert_eq!(error_location(&text), (11, 1));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn unknown_shape___reports_line_and_column() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "dodecahedron"
+"##);
+    assert_eq!(error_location(&text), (10, 1));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn misspelt_transform___reports_line_and_column() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "sphere"
+transform = [{ scale = [1, 1, 1] },
+    { transalte = [1, 2, 3] }]
+"##);
+    assert_eq!(error_location(&text), (12, 7));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn value_of_wrong_type___reports_line_and_column() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "cylinder"
+min = "low"
+"##);
+    assert_eq!(error_location(&text), (11, 1));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn unknown_material___reports_line_and_column() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "sphere"
+  material = "missing"
+"##);
+    assert_eq!(error_location(&text), (11, 14));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn shape_missing_required_key___reports_line_and_column() {
+    let text = format!("{}{}", CAMERA, r##"
+[[objects]]
+shape = "triangle"
+"##);
+    assert_eq!(error_location(&text), (10, 9));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn area_light___is_built() {
+    let text = format!("{}{}", CAMERA, r##"
+[[lights]]
+kind = "area"
+corner = [-1, 5, -1]
+uvec = [2, 0, 0]
+usteps = 4
+vvec = [0, 0, 2]
+vsteps = 2
+intensity = [0.5, 0.5, 0.5]
+"##);
+    let (world, _) = read(&text).unwrap();
+    let expected = area_light(
+        point(-1.0, 5.0, -1.0),
+        vector(2.0, 0.0, 0.0), 4,
+        vector(0.0, 0.0, 2.0), 2,
+        colour(0.5, 0.5, 0.5),
+    );
+    assert_eq!(world.light_sources(), &vec![expected]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn spot_and_directional_lights___are_built() {
+    let text = format!("{}{}", CAMERA, r##"
+[[lights]]
+kind = "spot"
+position = [0, 5, 0]
+direction = [0, -1, 0]
+inner_angle = 20
+outer_angle = 30
+
+[[lights]]
+kind = "directional"
+direction = [1, -1, 1]
+"##);
+    let (world, _) = read(&text).unwrap();
+    let expected = vec![
+        spot_light(
+            point(0.0, 5.0, 0.0), vector(0.0, -1.0, 0.0),
+            20f64.to_radians(), 30f64.to_radians(), RGB::white(),
+        ),
+        directional_light(vector(1.0, -1.0, 1.0), RGB::white()),
+    ];
+    assert_eq!(world.light_sources(), &expected);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn light_attenuation___is_set() {
+    let text = format!("{}{}", CAMERA, r##"
+[[lights]]
+position = [0, 5, 0]
+attenuation = [0.5, 0.25, 0.125]
+"##);
+    let (world, _) = read(&text).unwrap();
+    assert_eq!(world.light_sources()[0].attenuation(), Attenuation::new(0.5, 0.25, 0.125));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn light_missing_required_key___reports_line_and_column() {
+    let text = format!("{}{}", CAMERA, r##"
+[[lights]]
+kind = "area"
+corner = [-1, 5, -1]
+"##);
+    assert_eq!(error_location(&text), (10, 8));
+
+    let text = format!("{}{}", CAMERA, r##"
+[[lights]]
+position = [0, 0, 0]
+
+[[lights]]
+intensity = [1, 1, 1]
+"##);
+    assert_eq!(error_location(&text), (12, 1));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn missing_camera___is_an_error() {
+    assert_eq!(read("").is_err(), true);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn obj_include___is_read_relative_to_scene_directory() {
+    let dir = std::env::temp_dir().join("bucktracer_scene_obj_include");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tri.obj"), "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\n").unwrap();
+    let text = format!("{}{}", CAMERA, r##"
+[materials.red]
+pattern = { Solid = [1, 0, 0] }
+
+[[objects]]
+shape = "obj"
+file = "tri.obj"
+material = "red"
+"##);
+    let (world, _) = read_scene(&text, &dir).unwrap();
+    let mut expected = triangle(point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0));
+    expected.mut_material().set_pattern(Pattern::solid(colour(1.0, 0.0, 0.0)));
+    assert_eq!(world.objects()[0].children(), &[expected]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn material_texture___is_read_relative_to_scene_directory() {
+    let dir = std::env::temp_dir().join("bucktracer_scene_texture");
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut image = canvas(2, 1);
+    image.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+    image.set_colour_at(1, 0, colour(1.0, 0.0, 0.0));
+    let mut f = std::fs::File::create(dir.join("red.png")).unwrap();
+    bucktracer::png::encode(&image, &mut f).unwrap();
+    let text = format!("{}{}", CAMERA, r##"
+[materials.red]
+texture = { file = "red.png", mapping = "spherical" }
+
+[[objects]]
+shape = "sphere"
+material = "red"
+"##);
+    let (world, _) = read_scene(&text, &dir).unwrap();
+    let c = world.objects()[0].material_colour_at(point(0.0, 0.0, -1.0));
+    assert_eq!(c, colour(1.0, 0.0, 0.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn missing_texture_file___is_an_error() {
+    let text = format!("{}{}", CAMERA, r##"
+[materials.red]
+texture = { file = "no_such_file.png", mapping = "planar" }
+"##);
+    assert_eq!(read(&text).is_err(), true);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn example_scene_file___is_valid() {
+    let (world, camera) = bucktracer::scene::load_scene(Path::new("scenes/room.toml")).unwrap();
+    assert_eq!(world.objects().len(), 6);
+    assert_eq!(camera.hsize(), 1280);
+}
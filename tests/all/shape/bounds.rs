@@ -255,3 +255,27 @@ fn format_bounds() {
         "Bounds { min: (-1, -2, -3, -4), max: (1, 2, 3, 4) }"
     );
 }
+
+#[allow(non_snake_case)]
+#[test]
+fn union___encloses_both_boxes() {
+    let a = Bounds::new(point(-1.0, 0.0, 2.0), point(0.0, 1.0, 3.0));
+    let b = Bounds::new(point(4.0, -2.0, 2.5), point(5.0, 0.5, 2.75));
+    assert_eq!(a.union(&b), Bounds::new(point(-1.0, -2.0, 2.0), point(5.0, 1.0, 3.0)));
+    assert_eq!(Bounds::empty().union(&a), a);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn surface_area___sums_the_faces() {
+    assert_eq!(Bounds::unit().surface_area(), 24.0);
+    assert_eq!(Bounds::new(point(0.0, 0.0, 0.0), point(1.0, 2.0, 3.0)).surface_area(), 22.0);
+    assert_eq!(Bounds::empty().surface_area(), 0.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn plane_bounds___are_not_finite() {
+    assert_eq!(plane().bounds().is_finite(), false);
+    assert_eq!(unit_sphere().bounds().is_finite(), true);
+}
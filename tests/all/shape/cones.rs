@@ -0,0 +1,122 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use bucktracer::CylKind::{Open, Closed};
+use std::f64::consts::SQRT_2;
+use std::f64::{INFINITY, NEG_INFINITY};
+use crate::almost_eq;
+
+fn intersects(c: &Object, origin: Tuple4, direction: Tuple4) -> Vec<f64> {
+    let r = ray(origin, direction.normalize());
+    let mut v = vec![];
+    append_intersects(&r, c, &mut v);
+    v.iter().map(|i| i.t_value()).collect()
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn ray_hits_a_cone() {
+    scenario_ray_hits_a_cone(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 5.0, 5.0);
+    scenario_ray_hits_a_cone(point(0.0, 0.0, -5.0), vector(1.0, 1.0, 1.0), 8.66025, 8.66025);
+    scenario_ray_hits_a_cone(point(1.0, 1.0, -5.0), vector(-0.5, -1.0, 1.0), 4.55006, 49.44994);
+}
+
+fn scenario_ray_hits_a_cone(origin: Tuple4, direction: Tuple4, t0: f64, t1: f64) {
+    let ts = intersects(&inf_cone(), origin, direction);
+    assert_eq!(2, ts.len());
+    assert!(almost_eq(t0, ts[0]));
+    assert!(almost_eq(t1, ts[1]));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn ray_parallel_to_one_half___hits_the_other_once() {
+    let ts = intersects(&inf_cone(), point(0.0, 0.0, -1.0), vector(0.0, 1.0, 1.0));
+    assert_eq!(1, ts.len());
+    assert!(almost_eq(0.35355, ts[0]));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn ray_passing_beside_the_apex___misses() {
+    assert_eq!(0, intersects(&inf_cone(), point(2.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)).len());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn closed_cone___caps_are_intersected() {
+    count_intersects(point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0), 0);
+    count_intersects(point(0.0, 0.0, -0.25), vector(0.0, 1.0, 1.0), 2);
+    count_intersects(point(0.0, 0.0, -0.25), vector(0.0, 1.0, 0.0), 4);
+}
+
+fn count_intersects(pos: Tuple4, direction: Tuple4, count: usize) {
+    let c = cone(Closed, -0.5, 0.5);
+    assert_eq!(count, intersects(&c, pos, direction).len());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn open_cone___between_limits___is_intersected() {
+    let c = cone(Open, 1.0, 2.0);
+    assert_eq!(0, intersects(&c, point(0.0, 0.5, -5.0), vector(0.0, 0.0, 1.0)).len());
+    assert_eq!(0, intersects(&c, point(0.0, 3.0, -5.0), vector(0.0, 0.0, 1.0)).len());
+    assert_eq!(2, intersects(&c, point(0.0, 1.5, -5.0), vector(0.0, 0.0, 1.0)).len());
+    // no caps, so a ray up the middle misses
+    assert_eq!(0, intersects(&c, point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)).len());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn normal_on_a_cone() {
+    scenario_normal_on_a_cone(inf_cone(), point(1.0, 1.0, 1.0), vector(1.0, -SQRT_2, 1.0));
+    scenario_normal_on_a_cone(inf_cone(), point(-1.0, -1.0, 0.0), vector(-1.0, 1.0, 0.0));
+    scenario_normal_on_a_cone(inf_cone(), point(0.0, 2.0, -2.0), vector(0.0, -1.0, -1.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn normal_on_the_caps_of_a_closed_cone() {
+    let c = cone(Closed, -1.0, 2.0);
+    scenario_normal_on_a_cone(c.clone(), point(0.5, 2.0, 0.0), vector(0.0, 1.0, 0.0));
+    scenario_normal_on_a_cone(c.clone(), point(0.0, 2.0, 1.5), vector(0.0, 1.0, 0.0));
+    scenario_normal_on_a_cone(c.clone(), point(0.2, -1.0, 0.1), vector(0.0, -1.0, 0.0));
+    scenario_normal_on_a_cone(c, point(0.0, 1.0, 1.0), vector(0.0, -1.0, 1.0));
+}
+
+fn scenario_normal_on_a_cone(c: Object, pos: Tuple4, expected: Tuple4) {
+    let n = c.normal_at(pos, &intersection(0.0, &c));
+    assert_eq!(n, expected.normalize());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn cone___default_extents_are_infinite() {
+    assert_eq!(inf_cone(), cone(Open, NEG_INFINITY, INFINITY));
+    assert_ne!(inf_cone(), cone(Closed, NEG_INFINITY, INFINITY));
+    assert_ne!(inf_cone(), inf_cylinder());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn cone___bounds_are_as_wide_as_the_widest_end() {
+    assert_eq!(cone(Open, -1.0, 2.0).bounds(), Bounds::new(point(-2.0, -1.0, -2.0), point(2.0, 2.0, 2.0)));
+    assert_eq!(cone(Closed, -3.0, -1.0).bounds(), Bounds::new(point(-3.0, -3.0, -3.0), point(3.0, -1.0, 3.0)));
+    assert!(!inf_cone().bounds().is_finite());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn cone___can_be_partitioned() {
+    let mut c = cone(Closed, 0.0, 1.0);
+    c.set_object_to_world_spc(translation(5.0, 0.0, 0.0));
+    let groups = binary_partition(1, vec![c.clone(), unit_sphere()]);
+    let leaves = flatten(&[groups]);
+    assert_eq!(leaves.len(), 2);
+    assert!(leaves.contains(&c));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn format_cone_object() {
+    assert_eq!(format!("{}", cone(Open, 0.0, 1.0)), "Object { Cone, Bounds { min: (-1, 0, -1, 1), max: (1, 1, 1, 1) } }");
+}
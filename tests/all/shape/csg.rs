@@ -0,0 +1,112 @@
+use bucktracer::*;
+use bucktracer::math::*;
+
+fn sphere_and_cube(op: CsgOp) -> (Object, Object, Object) {
+    let s = unit_sphere();
+    let mut c = cube();
+    c.set_object_to_world_spc(translation(0.0, 0.0, 0.5));
+    (csg(op, s.clone(), c.clone()), s, c)
+}
+
+fn hits(obj: &Object, r: &Ray) -> Vec<(f64, Object)> {
+    let mut xs = vec![];
+    append_intersects(r, obj, &mut xs);
+    xs.iter().map(|i| (i.t_value(), i.intersected())).collect()
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn union___keeps_the_outside_surfaces_of_both_operands() {
+    let (u, s, c) = sphere_and_cube(CsgOp::Union);
+    let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(hits(&u, &r), vec![(4.0, s), (6.5, c)]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn intersection___keeps_the_surfaces_inside_the_other_operand() {
+    let (i, s, c) = sphere_and_cube(CsgOp::Intersection);
+    let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(hits(&i, &r), vec![(4.5, c), (6.0, s)]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn difference___removes_the_right_operand_from_the_left() {
+    let (d, s, c) = sphere_and_cube(CsgOp::Difference);
+    let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(hits(&d, &r), vec![(4.0, s), (4.5, c)]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn difference___of_operands_that_do_not_overlap___is_the_left_operand() {
+    let s = unit_sphere();
+    let mut c = cube();
+    c.set_object_to_world_spc(translation(5.0, 0.0, 0.0));
+    let d = csg(CsgOp::Difference, s.clone(), c);
+    let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(hits(&d, &r), vec![(4.0, s.clone()), (6.0, s)]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn ray_that_misses_the_bounds___produces_no_intersections() {
+    let (u, _, _) = sphere_and_cube(CsgOp::Union);
+    let r = ray(point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(hits(&u, &r), vec![]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bounds___depend_on_the_operation() {
+    let s = unit_sphere();
+    let mut c = cube();
+    c.set_object_to_world_spc(translation(1.0, 0.0, 0.0));
+
+    assert_eq!(csg(CsgOp::Union, s.clone(), c.clone()).bounds(),
+               Bounds::new(point(-1.0, -1.0, -1.0), point(2.0, 1.0, 1.0)));
+    assert_eq!(csg(CsgOp::Intersection, s.clone(), c.clone()).bounds(),
+               Bounds::new(point(0.0, -1.0, -1.0), point(1.0, 1.0, 1.0)));
+    assert_eq!(csg(CsgOp::Difference, s.clone(), c.clone()).bounds(), s.bounds());
+
+    c.set_object_to_world_spc(translation(5.0, 0.0, 0.0));
+    // an empty box has its min above its max
+    let b = csg(CsgOp::Intersection, s, c).bounds();
+    assert!(b.min().x() > b.max().x());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn flatten___keeps_csg_operands_together() {
+    let (u, _, _) = sphere_and_cube(CsgOp::Union);
+    let v = flatten(&[group(vec![u.clone(), unit_sphere()])]);
+    assert_eq!(v, vec![u, unit_sphere()]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn csg_in_transformed_group___intersections_and_normals_are_in_world_space() {
+    let mut c = cube();
+    c.set_object_to_world_spc(translation(0.0, 0.0, 1.0));
+    let mut d = csg(CsgOp::Difference, unit_sphere(), c);
+    d.set_object_to_world_spc(translation(0.0, 0.0, 5.0));
+    let mut g = group(vec![d]);
+    g.set_object_to_world_spc(scaling(2.0, 2.0, 2.0));
+
+    let w = World::with(vec![], vec![g]);
+    let r = ray(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
+    let xs = w.intersect(&r);
+
+    let ts: Vec<f64> = xs.iter().map(|i| i.t_value()).collect();
+    assert_eq!(ts, vec![8.0, 10.0]);
+    assert_eq!(xs[0].normal_at(point(0.0, 0.0, 8.0)), vector(0.0, 0.0, -1.0));
+    assert_eq!(xs[1].normal_at(point(0.0, 0.0, 10.0)), vector(0.0, 0.0, -1.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn format_csg_object() {
+    let (u, _, _) = sphere_and_cube(CsgOp::Union);
+    assert_eq!(format!("{}", u), "Object { Csg (Union), Bounds { min: (-1, -1, -1, 1), max: (1, 1, 1.5, 1) } }");
+}
@@ -0,0 +1,130 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use std::sync::Arc;
+
+fn flat(c: RGB) -> Material {
+    *Material::default()
+        .set_pattern(Pattern::solid(c))
+        .set_ambient(1.0)
+        .set_diffuse(0.0)
+        .set_specular(0.0)
+}
+
+fn lit(objects: Vec<Object>) -> World {
+    World::with(vec![point_light(point(-10.0, 10.0, -10.0), RGB::white())], objects)
+}
+
+fn placed(prototype: &Arc<Object>, material: Option<Material>, m: Matrix) -> Object {
+    let mut inst = instance(prototype, material);
+    inst.set_object_to_world_spc(m);
+    inst
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn instances___share_their_prototype() {
+    let prototype = Arc::new(group(vec![unit_sphere(), cube()]));
+    let copies: Vec<Object> = (0..500)
+        .map(|i| placed(&prototype, None, translation(3.0 * i as f64, 0.0, 0.0)))
+        .collect();
+
+    assert_eq!(Arc::strong_count(&prototype), 501);
+    assert!(copies.iter().all(|c| Arc::ptr_eq(c.prototype().unwrap(), &prototype)));
+    let w = World::with(vec![], copies);
+    assert!(w.objects().iter().all(|c| Arc::ptr_eq(c.prototype().unwrap(), &prototype)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn instance___is_intersected_where_it_is_placed() {
+    let mut s = unit_sphere();
+    s.set_object_to_world_spc(translation(0.0, 0.0, 1.0));
+    let prototype = Arc::new(group(vec![s.clone()]));
+    let w = World::with(vec![], vec![
+        placed(&prototype, None, translation(5.0, 0.0, 0.0)),
+        placed(&prototype, None, translation(-5.0, 0.0, 0.0) * scaling(2.0, 2.0, 2.0)),
+    ]);
+
+    let xs = w.intersect(&ray(point(5.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)));
+    let ts: Vec<f64> = xs.iter().map(|i| i.t_value()).collect();
+    assert_eq!(ts, vec![5.0, 7.0]);
+    assert_eq!(xs[0].intersected(), s);
+
+    let xs = w.intersect(&ray(point(-5.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)));
+    let ts: Vec<f64> = xs.iter().map(|i| i.t_value()).collect();
+    assert_eq!(ts, vec![5.0, 9.0]);
+    assert_eq!(xs[0].normal_at(point(-5.0, 0.0, 0.0)), vector(0.0, 0.0, -1.0));
+
+    assert_eq!(w.intersect(&ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0))).len(), 0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn instance___bounds_are_the_prototypes_where_it_is_placed() {
+    let prototype = Arc::new(group(vec![unit_sphere()]));
+    let inst = placed(&prototype, None, translation(5.0, 0.0, 0.0) * scaling(2.0, 1.0, 1.0));
+    assert_eq!(inst.bounds(), Bounds::new(point(3.0, -1.0, -1.0), point(7.0, 1.0, 1.0)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn instance_material___replaces_the_prototypes_only_when_given() {
+    let mut s = unit_sphere();
+    s.set_material(flat(colour(0.0, 1.0, 0.0)));
+    let prototype = Arc::new(s);
+    let red = flat(colour(1.0, 0.0, 0.0));
+    let blue = flat(colour(0.0, 0.0, 1.0));
+    let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+    let own = instance(&prototype, None);
+    let w = lit(vec![own]);
+    assert_eq!(w.intersect(&r)[0].material(), prototype.material());
+    assert_eq!(w.colour_at_intersect(&r, 5), colour(0.0, 1.0, 0.0));
+
+    let w = lit(vec![instance(&prototype, Some(red))]);
+    assert_eq!(w.intersect(&r)[0].material(), red);
+    assert_eq!(w.colour_at_intersect(&r, 5), colour(1.0, 0.0, 0.0));
+
+    // the outermost instance's material wins
+    let inner = Arc::new(instance(&prototype, Some(red)));
+    let w = lit(vec![instance(&inner, Some(blue))]);
+    assert_eq!(w.colour_at_intersect(&r, 5), colour(0.0, 0.0, 1.0));
+    let w = lit(vec![instance(&inner, None)]);
+    assert_eq!(w.colour_at_intersect(&r, 5), colour(1.0, 0.0, 0.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn patterns___move_with_the_instance() {
+    let mut s = unit_sphere();
+    let mut m = flat(RGB::white());
+    m.set_pattern(Pattern::stripes(RGB::white(), RGB::black()));
+    s.set_material(m);
+    let prototype = Arc::new(s);
+    let w = lit(vec![
+        placed(&prototype, None, identity()),
+        placed(&prototype, None, translation(3.5, 0.0, 0.0)),
+    ]);
+
+    for x in [-0.75, -0.25, 0.25, 0.75].iter() {
+        let here = w.colour_at_intersect(&ray(point(*x, 0.0, -5.0), vector(0.0, 0.0, 1.0)), 5);
+        let there = w.colour_at_intersect(&ray(point(*x + 3.5, 0.0, -5.0), vector(0.0, 0.0, 1.0)), 5);
+        assert_eq!(here, there);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn flatten___keeps_instances_whole() {
+    let prototype = Arc::new(group(vec![unit_sphere(), cube()]));
+    let inst = instance(&prototype, None);
+    assert_eq!(flatten(&[group(vec![inst.clone()])]), vec![inst]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn format_instance_object() {
+    let prototype = Arc::new(group(vec![unit_sphere(), cube()]));
+    assert_eq!(format!("{}", instance(&prototype, None)),
+               "Object { Instance (Group (2)), Bounds { min: (-1, -1, -1, 1), max: (1, 1, 1, 1) } }");
+}
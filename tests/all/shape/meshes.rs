@@ -0,0 +1,118 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use crate::almost_eq;
+
+fn face(vertices: [u32; 3]) -> MeshFace {
+    MeshFace { vertices, normals: None, texture_coords: None }
+}
+
+/// A grid of n x n unit squares in the z = 0 plane, two triangles each.
+fn grid(n: u32) -> Mesh {
+    let mut vertices = vec![];
+    for y in 0..=n {
+        for x in 0..=n {
+            vertices.push(point(x as f64, y as f64, 0.0));
+        }
+    }
+    let mut faces = vec![];
+    for y in 0..n {
+        for x in 0..n {
+            let i = y * (n + 1) + x;
+            faces.push(face([i, i + n + 1, i + 1]));
+            faces.push(face([i + 1, i + n + 1, i + n + 2]));
+        }
+    }
+    Mesh::new(vertices, vec![], vec![], faces)
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mesh___hits_where_the_separate_triangles_do() {
+    let m = grid(8);
+    let triangles: Vec<Object> = m.faces().iter().map(|f| {
+        let [a, b, c] = f.vertices;
+        let vs = m.vertices();
+        triangle(vs[a as usize], vs[b as usize], vs[c as usize])
+    }).collect();
+    let with_mesh = World::with(vec![], vec![mesh(m)]);
+    let with_triangles = World::with(vec![], triangles);
+
+    for (x, y) in [(0.3, 0.6), (4.5, 7.25), (7.9, 0.1), (-0.5, 2.0), (3.0, 9.0)].iter() {
+        let r = ray(point(*x, *y, -5.0), vector(0.1, -0.05, 1.0));
+        let ts = |w: &World| w.intersect(&r).iter().map(|i| i.t_value()).collect::<Vec<f64>>();
+        assert_eq!(ts(&with_mesh), ts(&with_triangles));
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mesh___hit_records_the_face() {
+    let m = grid(2);
+    let w = World::with(vec![], vec![mesh(m)]);
+    let r = ray(point(1.75, 1.5, -5.0), vector(0.0, 0.0, 1.0));
+    let ints = w.intersect(&r);
+    assert_eq!(ints.len(), 1);
+
+    let obj = ints[0].object();
+    match obj.shape() {
+        Shape::Mesh(m) => {
+            let [a, b, c] = m.faces()[ints[0].face().unwrap()].vertices;
+            let mut corners: Vec<u32> = vec![a, b, c];
+            corners.sort();
+            assert_eq!(corners, vec![5, 7, 8]);
+        },
+        other => panic!("expected a mesh, got {}", other),
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mesh_without_normals___has_flat_normals_as_triangles_do() {
+    let m = grid(2);
+    let w = World::with(vec![], vec![mesh(m)]);
+    let r = ray(point(0.5, 0.5, -5.0), vector(0.0, 0.0, 1.0));
+    let ints = w.intersect(&r);
+    let tri = triangle(point(0.0, 0.0, 0.0), point(0.0, 1.0, 0.0), point(1.0, 0.0, 0.0));
+    assert_eq!(ints[0].normal_at(point(0.5, 0.5, 0.0)), tri.normal_at(point(0.5, 0.5, 0.0), &intersection(5.0, &tri)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mesh_with_normals___interpolates_them() {
+    let m = Mesh::new(
+        vec![point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0)],
+        vec![vector(0.0, 1.0, 0.0), vector(-1.0, 0.0, 0.0), vector(1.0, 0.0, 0.0)],
+        vec![(0.5, 1.0), (0.0, 0.0), (1.0, 0.0)],
+        vec![MeshFace { vertices: [0, 1, 2], normals: Some([0, 1, 2]), texture_coords: Some([0, 1, 2]) }],
+    );
+    let w = World::with(vec![], vec![mesh(m)]);
+    let r = ray(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0));
+    let ints = w.intersect(&r);
+    assert!(almost_eq(ints[0].u().unwrap(), 0.45));
+    assert!(almost_eq(ints[0].v().unwrap(), 0.25));
+
+    // as for the equivalent smooth triangle
+    let n = ints[0].normal_at(point(-0.2, 0.3, 0.0));
+    assert_eq!(n, vector(-0.5547, 0.83205, 0.0));
+
+    let (u, v) = ints[0].object().texture_coords_at(&ints[0]).unwrap();
+    assert!(almost_eq(u, 0.5 * 0.3 + 0.0 * 0.45 + 1.0 * 0.25));
+    assert!(almost_eq(v, 1.0 * 0.3));
+}
+
+#[allow(non_snake_case)]
+#[test]
+#[should_panic(expected = "face refers to a missing vertex")]
+fn mesh_face_beyond_the_vertices___panics() {
+    Mesh::new(vec![point(0.0, 0.0, 0.0)], vec![], vec![], vec![face([0, 1, 2])]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mesh___bounds_enclose_its_vertices() {
+    let mut m = mesh(grid(3));
+    m.set_object_to_world_spc(translation(1.0, 0.0, 0.0));
+    let w = World::with(vec![], vec![m]);
+    assert_eq!(w.intersect(&ray(point(3.9, 2.9, -1.0), vector(0.0, 0.0, 1.0))).len(), 1);
+    assert_eq!(w.intersect(&ray(point(0.9, 2.9, -1.0), vector(0.0, 0.0, 1.0))).len(), 0);
+}
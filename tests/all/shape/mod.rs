@@ -1,8 +1,14 @@
 mod cubes;
 mod cylinders;
+mod cones;
+mod tori;
+mod planar;
 mod groups;
 mod planes;
 mod spheres;
 mod bounds;
 mod triangles;
 mod smooth_triangles;
+mod meshes;
+mod csg;
+mod instances;
@@ -0,0 +1,113 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use std::f64::consts::FRAC_PI_2;
+use crate::almost_eq;
+
+fn hits(obj: &Object, origin: Tuple4, direction: Tuple4) -> Vec<Intersection<'_>> {
+    let mut v = vec![];
+    append_intersects(&ray(origin, direction), obj, &mut v);
+    v
+}
+
+fn assert_uv(obj: &Object, hit: &Intersection, u: f64, v: f64) {
+    let (a, b) = obj.texture_coords_at(hit).unwrap();
+    assert!(almost_eq(a, u) && almost_eq(b, v), "uv ({}, {}), expected ({}, {})", a, b, u, v);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn disc___is_hit_within_its_radius() {
+    let d = disc(2.0);
+    let xs = hits(&d, point(1.0, 1.0, 1.0), vector(0.0, -1.0, 0.0));
+    assert_eq!(xs.len(), 1);
+    assert_eq!(xs[0].t_value(), 1.0);
+    assert_eq!(hits(&d, point(1.5, -1.0, 1.5), vector(0.0, 1.0, 0.0)).len(), 0);
+    assert_eq!(hits(&d, point(0.0, 1.0, 0.0), vector(1.0, 0.0, 0.0)).len(), 0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn disc___normal_faces_up() {
+    let d = disc(1.0);
+    assert_eq!(d.normal_at(point(0.5, 0.0, 0.0), &intersection(0.0, &d)), vector(0.0, 1.0, 0.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn disc___texture_coords_span_its_width() {
+    let d = disc(2.0);
+    let down = vector(0.0, -1.0, 0.0);
+    assert_uv(&d, &hits(&d, point(0.0, 1.0, 0.0), down)[0], 0.5, 0.5);
+    assert_uv(&d, &hits(&d, point(-2.0, 1.0, 0.0), down)[0], 0.0, 0.5);
+    assert_uv(&d, &hits(&d, point(0.0, 1.0, -2.0), down)[0], 0.5, 1.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn rect___is_hit_within_its_extents() {
+    let r = rect(4.0, 2.0);
+    let down = vector(0.0, -1.0, 0.0);
+    assert_eq!(hits(&r, point(1.9, 1.0, 0.9), down).len(), 1);
+    assert_eq!(hits(&r, point(2.1, 1.0, 0.0), down).len(), 0);
+    assert_eq!(hits(&r, point(0.0, 1.0, 1.1), down).len(), 0);
+    assert_eq!(hits(&r, point(0.0, 1.0, 0.0), vector(0.0, 0.0, 1.0)).len(), 0);
+    assert_eq!(r.normal_at(point(0.0, 0.0, 0.0), &intersection(0.0, &r)), vector(0.0, 1.0, 0.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn rect___texture_coords_match_a_disc() {
+    let r = rect(4.0, 2.0);
+    let down = vector(0.0, -1.0, 0.0);
+    assert_uv(&r, &hits(&r, point(-2.0, 1.0, 1.0), down)[0], 0.0, 0.0);
+    assert_uv(&r, &hits(&r, point(0.0, 1.0, 0.0), down)[0], 0.5, 0.5);
+    assert_uv(&r, &hits(&r, point(1.0, 1.0, -1.0), down)[0], 0.75, 1.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn quad___is_hit_within_its_edges() {
+    // a parallelogram leaning over in the x-y plane, facing -z
+    let q = quad(point(0.0, 0.0, 0.0), vector(0.0, 2.0, 0.0), vector(1.0, 1.0, 0.0));
+    let xs = hits(&q, point(1.0, 2.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(xs.len(), 1);
+    assert_eq!(xs[0].t_value(), 5.0);
+    assert_uv(&q, &xs[0], 0.5, 1.0);
+    assert_eq!(hits(&q, point(1.0, 0.5, -5.0), vector(0.0, 0.0, 1.0)).len(), 0);
+    assert_eq!(q.normal_at(point(0.5, 1.0, 0.0), &intersection(0.0, &q)), vector(0.0, 0.0, -1.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn planar_shapes___have_finite_bounds() {
+    assert_eq!(disc(2.0).bounds(), Bounds::new(point(-2.0, 0.0, -2.0), point(2.0, 0.0, 2.0)));
+    assert_eq!(rect(4.0, 2.0).bounds(), Bounds::new(point(-2.0, 0.0, -1.0), point(2.0, 0.0, 1.0)));
+    let q = quad(point(0.0, 0.0, 0.0), vector(0.0, 2.0, 0.0), vector(1.0, 1.0, 0.0));
+    assert_eq!(q.bounds(), Bounds::new(point(0.0, 0.0, 0.0), point(1.0, 3.0, 0.0)));
+
+    let mut wall = rect(4.0, 2.0);
+    wall.set_object_to_world_spc(rotation_x(FRAC_PI_2));
+    assert_eq!(wall.bounds(), Bounds::new(point(-2.0, -1.0, 0.0), point(2.0, 1.0, 0.0)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn floor___has_finite_partition_bounds_unlike_a_plane() {
+    let small = unit_sphere().set_object_to_world_spc(translation(-0.5, -0.5, -0.5) * scaling(0.2, 0.2, 0.2)).clone();
+    let mut floor = rect(2.0, 2.0);
+    floor.set_object_to_world_spc(translation(0.0, -1.0, 0.0));
+    let mut infinite_floor = plane();
+    infinite_floor.set_object_to_world_spc(translation(0.0, -1.0, 0.0));
+
+    let groups = binary_partition(2, vec![floor, small.clone()]);
+    assert_eq!(groups.bounds(), Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, -0.3, 1.0)));
+    let groups = binary_partition(2, vec![infinite_floor, small]);
+    assert!(!groups.bounds().is_finite());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn format_planar_objects() {
+    assert_eq!(format!("{}", disc(1.0)), "Object { Disc, Bounds { min: (-1, 0, -1, 1), max: (1, 0, 1, 1) } }");
+    assert_eq!(format!("{}", rect(2.0, 2.0)), "Object { Quad, Bounds { min: (-1, 0, -1, 1), max: (1, 0, 1, 1) } }");
+}
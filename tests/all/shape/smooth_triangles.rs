@@ -35,8 +35,8 @@ fn an_intersection_with_smooth_triangle_stores_u_v() {
     let r = ray(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0));
     let mut vec = vec![];
     append_intersects(&r, &tri, &mut vec);
-    almost_eq(vec[0].u().unwrap(), 0.45);
-    almost_eq(vec[0].v().unwrap(), 0.25);
+    assert!(almost_eq(vec[0].u().unwrap(), 0.45));
+    assert!(almost_eq(vec[0].v().unwrap(), 0.25));
 }
 
 #[allow(non_snake_case)]
@@ -0,0 +1,151 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use std::f64::consts::PI;
+use crate::almost_eq;
+
+fn intersects(t: &Object, origin: Tuple4, direction: Tuple4) -> Vec<f64> {
+    let r = ray(origin, direction);
+    let mut v = vec![];
+    append_intersects(&r, t, &mut v);
+    v.iter().map(|i| i.t_value()).collect()
+}
+
+fn assert_ts(actual: Vec<f64>, expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len(), "hits {:?}", actual);
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(almost_eq(*a, *e), "hits {:?}, expected {:?}", actual, expected);
+    }
+}
+
+/// How far the point is from the surface of the torus.
+fn distance_from_surface(major: f64, minor: f64, p: Tuple4) -> f64 {
+    let rho = (p.x().powi(2) + p.z().powi(2)).sqrt();
+    ((rho - major).powi(2) + p.y().powi(2)).sqrt() - minor
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn ray_through_both_sides_of_the_tube___hits_four_times() {
+    let t = torus(1.0, 0.25);
+    assert_ts(intersects(&t, point(-5.0, 0.0, 0.0), vector(1.0, 0.0, 0.0)), &[3.75, 4.25, 5.75, 6.25]);
+    assert_ts(intersects(&t, point(0.0, 0.0, -5.0), vector(0.0, 0.0, 2.0)), &[1.875, 2.125, 2.875, 3.125]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn ray_through_the_tube___hits_twice() {
+    let t = torus(1.0, 0.25);
+    assert_ts(intersects(&t, point(1.0, 5.0, 0.0), vector(0.0, -1.0, 0.0)), &[4.75, 5.25]);
+    assert_ts(intersects(&t, point(1.0, 0.0, 0.0), vector(0.0, -1.0, 0.0)), &[-0.25, 0.25]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn rays_through_the_hole_or_outside___miss() {
+    let t = torus(1.0, 0.25);
+    assert_ts(intersects(&t, point(0.0, 5.0, 0.0), vector(0.0, -1.0, 0.0)), &[]);
+    assert_ts(intersects(&t, point(-5.0, 0.5, 0.0), vector(1.0, 0.0, 0.0)), &[]);
+    assert_ts(intersects(&t, point(-5.0, 0.0, 2.0), vector(1.0, 0.0, 0.0)), &[]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn normal_on_a_torus() {
+    let t = torus(1.0, 0.25);
+    let normal = |p| t.normal_at(p, &intersection(0.0, &t));
+    assert_eq!(normal(point(1.25, 0.0, 0.0)), vector(1.0, 0.0, 0.0));
+    assert_eq!(normal(point(0.75, 0.0, 0.0)), vector(-1.0, 0.0, 0.0));
+    assert_eq!(normal(point(0.0, 0.25, 1.0)), vector(0.0, 1.0, 0.0));
+    assert_eq!(normal(point(0.0, -0.25, -1.0)), vector(0.0, -1.0, 0.0));
+    let a = PI / 4.0;
+    assert_eq!(normal(point(0.0, 0.25 * a.sin(), -1.0 - 0.25 * a.cos())), vector(0.0, a.sin(), -a.cos()));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn torus___bounds_enclose_the_ring() {
+    assert_eq!(torus(2.0, 0.5).bounds(), Bounds::new(point(-2.5, -0.5, -2.5), point(2.5, 0.5, 2.5)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn texture_coords___go_around_the_ring_and_the_tube() {
+    let t = torus(1.0, 0.25);
+    let uv_of_first_hit = |origin, direction| {
+        let r = ray(origin, direction);
+        let mut v = vec![];
+        append_intersects(&r, &t, &mut v);
+        t.texture_coords_at(&v[0]).unwrap()
+    };
+    let (u, v) = uv_of_first_hit(point(0.0, 0.0, 5.0), vector(0.0, 0.0, -1.0));
+    assert!(almost_eq(u, 0.5) && almost_eq(v, 0.0));
+    let (u, v) = uv_of_first_hit(point(0.0, 5.0, 1.0), vector(0.0, -1.0, 0.0));
+    assert!(almost_eq(u, 0.5) && almost_eq(v, 0.25));
+    let (u, v) = uv_of_first_hit(point(5.0, 0.0, 0.0), vector(-1.0, 0.0, 0.0));
+    assert!(almost_eq(u, 0.25) && almost_eq(v, 0.0));
+    let (u, v) = uv_of_first_hit(point(1.0, -5.0, 0.0), vector(0.0, 1.0, 0.0));
+    assert!(almost_eq(u, 0.25) && almost_eq(v, 0.75));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn grazing_rays_from_far_away___hit_the_surface_accurately() {
+    let t = torus(1.0, 0.25);
+    for i in 0..200 {
+        // rays close to the top of the tube, and close to its inner
+        // and outer edges
+        let y = 0.25 - (i as f64) * 1e-4;
+        let z = 1.25 - (i as f64) * 1e-4;
+        for (origin, direction) in [
+            (point(-1000.0, y, 1.0), vector(1.0, 0.0, 0.0)),
+            (point(-1000.0, 0.0, z), vector(1.0, 0.0, 0.0)),
+            (point(-1000.0, 0.0, 0.75 + (i as f64) * 1e-4), vector(1.0, 0.0, 0.0)),
+            (point(-700.0, 700.0 + y, 1.0), vector(1.0, -1.0, 0.0)),
+        ].iter() {
+            let r = ray(*origin, *direction);
+            for t_value in intersects(&t, *origin, *direction) {
+                let d = distance_from_surface(1.0, 0.25, r.position(t_value));
+                assert!(d.abs() < 1e-7, "{:?} is {} from the surface", r.position(t_value), d);
+            }
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn rays_leaving_the_surface___do_not_hit_it_again_nearby() {
+    let t = torus(1.0, 0.25);
+    for i in 0..36 {
+        for j in 0..36 {
+            let (theta, phi) = (i as f64 * PI / 18.0, j as f64 * PI / 18.0);
+            let rho = 1.0 + 0.25 * phi.cos();
+            let p = point(rho * theta.cos(), 0.25 * phi.sin(), rho * theta.sin());
+            let n = t.normal_at(p, &intersection(0.0, &t));
+            // almost along the surface, as light arrives at a grazing angle
+            let across = n.cross(vector(0.0, 1.0, 0.0) + n.scale(0.1)).normalize();
+            let direction = (across + n.scale(1e-3)).normalize();
+            let over = p + n.scale(1e-5);
+            for t_value in intersects(&t, over, direction) {
+                assert!(t_value < 0.0 || t_value > 1e-3, "hit at {} leaving {:?}", t_value, p);
+            }
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn torus_in_a_world___is_shaded() {
+    let mut t = torus(1.0, 0.25);
+    t.set_object_to_world_spc(rotation_x(PI / 2.0));
+    let w = World::with(vec![point_light(point(-10.0, 10.0, -10.0), colour(1.0, 1.0, 1.0))], vec![t]);
+    let r = ray(point(1.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = w.intersect(&r);
+    assert_ts(xs.iter().map(|i| i.t_value()).collect(), &[4.75, 5.25]);
+    assert_ne!(w.colour_at_intersect(&r, 5), RGB::black());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn format_torus_object() {
+    assert_eq!(format!("{}", torus(1.0, 0.5)), "Object { Torus, Bounds { min: (-1.5, -0.5, -1.5, 1), max: (1.5, 0.5, 1.5, 1) } }");
+}
@@ -0,0 +1,117 @@
+use bucktracer::*;
+use bucktracer::math::*;
+use bucktracer::stl;
+use bucktracer::stl::{ParseError, Reason};
+
+const ASCII: &str = r##"solid square
+  facet normal 0 0 -1
+    outer loop
+      vertex -1 1 0
+      vertex -1 0 0
+      vertex 1 0 0
+    endloop
+  endfacet
+  facet normal 0 0 0
+    outer loop
+      vertex -1 1 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+endsolid square
+"##;
+
+fn square() -> Vec<Object> {
+    vec![
+        triangle(point(-1.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0)),
+        triangle(point(-1.0, 1.0, 0.0), point(1.0, 0.0, 0.0), point(1.0, 1.0, 0.0)),
+    ]
+}
+
+/// Encodes facets as a binary stl file, with the given header.
+fn binary(header: &[u8], facets: &[[[f32; 3]; 3]]) -> Vec<u8> {
+    let mut out = header.to_vec();
+    out.resize(80, 0);
+    out.extend_from_slice(&(facets.len() as u32).to_le_bytes());
+    for f in facets {
+        for x in [0.0f32; 3].iter().chain(f.iter().flatten()) {
+            out.extend_from_slice(&x.to_le_bytes());
+        }
+        out.extend_from_slice(&[0, 0]);
+    }
+    out
+}
+
+const SQUARE: [[[f32; 3]; 3]; 2] = [
+    [[-1.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+    [[-1.0, 1.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+];
+
+#[allow(non_snake_case)]
+#[test]
+fn read_ascii___produces_triangles() {
+    assert_eq!(stl::read_object_vec(&mut ASCII.as_bytes()).unwrap(), square());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_empty_file___produces_no_objects() {
+    assert_eq!(stl::read_object_vec(&mut "".as_bytes()).unwrap(), vec![]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_binary___produces_triangles() {
+    let bytes = binary(b"made by hand", &SQUARE);
+    assert_eq!(stl::read_object_vec(&mut bytes.as_slice()).unwrap(), square());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_binary_with_solid_header___is_still_binary() {
+    let bytes = binary(b"solid square", &SQUARE);
+    assert_eq!(stl::read_object_vec(&mut bytes.as_slice()).unwrap(), square());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn truncated_binary___is_an_error() {
+    let mut bytes = binary(b"", &SQUARE);
+    bytes.truncate(bytes.len() - 10);
+    assert_eq!(stl::read_object_vec(&mut bytes.as_slice()),
+               Err(ParseError::Truncated { declared: 2, found: 1 }));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn bad_ascii_lines___report_line_and_reason() {
+    let bad = |text: &str| stl::read_object_vec(&mut text.as_bytes()).unwrap_err();
+    let instruction = |line: usize, text: &str, reason: Reason| {
+        ParseError::BadInstruction { line, text: text.to_string(), reason }
+    };
+
+    assert_eq!(bad("solid\nfacet normal 0 0 1\nvertex 1 x 0\n"),
+               instruction(3, "vertex 1 x 0", Reason::BadNumber("x".to_string())));
+    assert_eq!(bad("solid\nfacet normal 0 0 1\nvertex 1 0\n"),
+               instruction(3, "vertex 1 0", Reason::MissingValues));
+    assert_eq!(bad("solid\nvertex 1 0 0\n"),
+               instruction(2, "vertex 1 0 0", Reason::OutOfPlace("vertex".to_string())));
+    assert_eq!(bad("solid\nfacet normal 0 0 1\nvertex 1 0 0\nendfacet\n"),
+               instruction(4, "endfacet", Reason::NotATriangle(1)));
+    assert_eq!(bad("solid\ncolour 1 0 0\n"),
+               instruction(2, "colour 1 0 0", Reason::UnknownInstruction("colour".to_string())));
+    assert_eq!(bad("solid\nfacet normal 0 0 1\nvertex 1 0 0\n"), ParseError::UnexpectedEnd);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_mesh___has_a_face_per_facet() {
+    let obj = stl::read_mesh(&mut ASCII.as_bytes()).unwrap();
+    match obj.shape() {
+        Shape::Mesh(m) => {
+            assert_eq!(m.len(), 2);
+            assert_eq!(m.vertices().len(), 6);
+        },
+        other => panic!("expected a mesh, got {}", other),
+    }
+}
@@ -0,0 +1,178 @@
+use bucktracer::*;
+use bucktracer::math::*;
+
+fn assert_uv_eq(actual: (f64, f64), expected: (f64, f64)) {
+    let close = |a: f64, b: f64| (a - b).abs() < 0.0001;
+    assert!(close(actual.0, expected.0) && close(actual.1, expected.1),
+            "expected {:?}, got {:?}", expected, actual);
+}
+
+fn two_by_two() -> Canvas {
+    let mut c = canvas(2, 2);
+    c.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+    c.set_colour_at(1, 0, colour(0.0, 1.0, 0.0));
+    c.set_colour_at(0, 1, colour(0.0, 0.0, 1.0));
+    c.set_colour_at(1, 1, colour(1.0, 1.0, 1.0));
+    c
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn spherical_map___on_a_unit_sphere() {
+    let cases = [
+        (point(0.0, 0.0, -1.0), (0.0, 0.5)),
+        (point(1.0, 0.0, 0.0), (0.25, 0.5)),
+        (point(0.0, 0.0, 1.0), (0.5, 0.5)),
+        (point(-1.0, 0.0, 0.0), (0.75, 0.5)),
+        (point(0.0, 1.0, 0.0), (0.5, 1.0)),
+        (point(0.0, -1.0, 0.0), (0.5, 0.0)),
+        (point(2f64.sqrt() / 2.0, 2f64.sqrt() / 2.0, 0.0), (0.25, 0.75)),
+    ];
+    for (p, uv) in cases.iter() {
+        assert_uv_eq(spherical_map(*p), *uv);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn planar_map___repeats_every_unit() {
+    assert_uv_eq(planar_map(point(0.25, 0.0, 0.5)), (0.25, 0.5));
+    assert_uv_eq(planar_map(point(0.25, 0.0, -0.25)), (0.25, 0.75));
+    assert_uv_eq(planar_map(point(0.25, 0.5, -0.25)), (0.25, 0.75));
+    assert_uv_eq(planar_map(point(1.25, 0.0, 0.5)), (0.25, 0.5));
+    assert_uv_eq(planar_map(point(-0.25, 0.0, -1.75)), (0.75, 0.25));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn cylindrical_map___wraps_around_the_y_axis() {
+    assert_uv_eq(cylindrical_map(point(0.0, 0.0, -1.0)), (0.0, 0.0));
+    assert_uv_eq(cylindrical_map(point(0.0, 0.5, -1.0)), (0.0, 0.5));
+    assert_uv_eq(cylindrical_map(point(0.0, 1.0, -1.0)), (0.0, 0.0));
+    assert_uv_eq(cylindrical_map(point(1.0, 0.25, 0.0)), (0.25, 0.25));
+    assert_uv_eq(cylindrical_map(point(0.0, -0.25, 1.0)), (0.5, 0.75));
+    assert_uv_eq(cylindrical_map(point(-1.0, 1.25, 0.0)), (0.75, 0.25));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn cubic_map___puts_each_face_in_its_own_part_of_the_texture() {
+    // the centre of each face lands on the centre of its cell in the
+    // 4 x 3 grid
+    assert_uv_eq(cubic_map(point(-1.0, 0.0, 0.0)), (0.125, 0.5));
+    assert_uv_eq(cubic_map(point(0.0, 0.0, 1.0)), (0.375, 0.5));
+    assert_uv_eq(cubic_map(point(1.0, 0.0, 0.0)), (0.625, 0.5));
+    assert_uv_eq(cubic_map(point(0.0, 0.0, -1.0)), (0.875, 0.5));
+    assert_uv_eq(cubic_map(point(0.0, 1.0, 0.0)), (0.375, 5.0 / 6.0));
+    assert_uv_eq(cubic_map(point(0.0, -1.0, 0.0)), (0.375, 1.0 / 6.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn cubic_map___stays_inside_the_face() {
+    let (u, v) = cubic_map(point(1.0, 1.0, 1.0));
+    assert!(u > 0.5 && u < 0.75, "u = {}", u);
+    assert!(v > 1.0 / 3.0 && v < 2.0 / 3.0, "v = {}", v);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn uv_mapping___dispatches_to_the_mapping_function() {
+    let p = point(0.3, -0.4, 0.5);
+    assert_eq!(UvMapping::Spherical.map(p), spherical_map(p));
+    assert_eq!(UvMapping::Planar.map(p), planar_map(p));
+    assert_eq!(UvMapping::Cylindrical.map(p), cylindrical_map(p));
+    assert_eq!(UvMapping::Cubic.map(p), cubic_map(p));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn sample_bilinear___at_pixel_centres_gives_the_pixel_colour() {
+    let c = two_by_two();
+    assert_eq!(sample_bilinear(&c, 0.25, 0.75), colour(1.0, 0.0, 0.0));
+    assert_eq!(sample_bilinear(&c, 0.75, 0.75), colour(0.0, 1.0, 0.0));
+    assert_eq!(sample_bilinear(&c, 0.25, 0.25), colour(0.0, 0.0, 1.0));
+    assert_eq!(sample_bilinear(&c, 0.75, 0.25), colour(1.0, 1.0, 1.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn sample_bilinear___blends_between_pixels() {
+    let c = two_by_two();
+    assert_eq!(sample_bilinear(&c, 0.5, 0.75), colour(0.5, 0.5, 0.0));
+    assert_eq!(sample_bilinear(&c, 0.25, 0.5), colour(0.5, 0.0, 0.5));
+    assert_eq!(sample_bilinear(&c, 0.5, 0.5), colour(0.5, 0.5, 0.5));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn sample_bilinear___wraps_horizontally_and_clamps_vertically() {
+    let c = two_by_two();
+    // halfway between the right hand column and the left hand one
+    assert_eq!(sample_bilinear(&c, 0.0, 0.75), colour(0.5, 0.5, 0.0));
+    // beyond the top row
+    assert_eq!(sample_bilinear(&c, 0.25, 0.99), colour(1.0, 0.0, 0.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn image_pattern___colours_a_sphere_from_the_texture() {
+    let texture = Texture::new(two_by_two());
+    let mut s = unit_sphere();
+    s.mut_material().set_pattern(Pattern::image(texture, UvMapping::Spherical));
+    // the north pole is in the top row, the south pole in the bottom row
+    let top = s.material_colour_at(point(0.0, 0.9, -0.1));
+    let bottom = s.material_colour_at(point(0.0, -0.9, -0.1));
+    assert_eq!(top.blue(), 0.0);
+    assert_eq!(bottom.blue() > 0.5, true);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn vertices_mapping___uses_the_texture_coordinates_of_the_point_hit() {
+    let mut image = canvas(2, 1);
+    image.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+    image.set_colour_at(1, 0, colour(0.0, 0.0, 1.0));
+    let texture = Texture::new(image);
+
+    let mut tri = triangle(point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0), point(-1.0, 2.0, 0.0));
+    tri.set_texture_coords([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+    tri.mut_material()
+        .set_pattern(Pattern::image(texture, UvMapping::Vertices))
+        .set_ambient(1.0)
+        .set_diffuse(0.0)
+        .set_specular(0.0);
+    let w = World::with(vec![point_light(point(0.0, 0.0, -10.0), RGB::white())], vec![tri]);
+
+    let left = w.colour_at_intersect(&ray(point(-0.5, 0.1, -5.0), vector(0.0, 0.0, 1.0)), 1);
+    let right = w.colour_at_intersect(&ray(point(0.5, 0.1, -5.0), vector(0.0, 0.0, 1.0)), 1);
+    assert_eq!(left, colour(1.0, 0.0, 0.0));
+    assert_eq!(right, colour(0.0, 0.0, 1.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn load_texture___reads_ppm_images_by_extension() {
+    let dir = std::env::temp_dir().join("bucktracer_ppm_texture");
+    std::fs::create_dir_all(&dir).unwrap();
+    let image = two_by_two();
+    let path = dir.join("two_by_two.PPM");
+    bucktracer::ppm::encode_binary(&image, &mut std::fs::File::create(&path).unwrap()).unwrap();
+
+    assert_eq!(*load_texture(&path).unwrap().image(), image);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn image_pattern___copies_share_one_image() {
+    let texture = Texture::new(two_by_two());
+    let mut m = Material::default();
+    m.set_pattern(Pattern::image(texture, UvMapping::Planar));
+    let copy = m;
+    match (m.pattern(), copy.pattern()) {
+        (Pattern::Image { texture: a, .. }, Pattern::Image { texture: b, .. }) => {
+            assert!(std::sync::Arc::ptr_eq(&a.image(), &b.image()));
+        },
+        other => panic!("expected image patterns, got {:?}", other),
+    }
+}
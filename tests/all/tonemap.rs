@@ -0,0 +1,78 @@
+use bucktracer::*;
+use crate::almost_eq;
+
+#[allow(non_snake_case)]
+#[test]
+fn default_tone_mapping___leaves_the_canvas_alone() {
+    let unmapped = || {
+        let mut cvs = canvas(2, 1);
+        cvs.set_colour_at(0, 0, colour(3.5, -0.25, 0.5));
+        cvs.set_colour_at(1, 0, colour(0.1, 0.2, 0.3));
+        cvs
+    };
+    let mut cvs = unmapped();
+
+    let tm = ToneMapping::default();
+    assert!(tm.is_identity());
+    tm.apply(&mut cvs);
+    assert_eq!(cvs, unmapped());
+    assert_eq!(tm.map_colour(colour(3.5, -0.25, 0.5)), colour(3.5, -0.25, 0.5));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn exposure___doubles_the_brightness_each_stop() {
+    let mut tm = ToneMapping::default();
+    tm.set_exposure(2.0);
+    assert_eq!(tm.map_colour(colour(0.1, 0.2, 0.25)), colour(0.4, 0.8, 1.0));
+    tm.set_exposure(-1.0);
+    assert_eq!(tm.map_colour(colour(0.1, 0.2, 0.25)), colour(0.05, 0.1, 0.125));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn reinhard___compresses_highlights_below_one() {
+    let mut tm = ToneMapping::default();
+    tm.set_operator(ToneOperator::Reinhard);
+    assert_eq!(tm.map_colour(colour(0.0, 1.0, 3.0)), colour(0.0, 0.5, 0.75));
+    assert!(tm.map_colour(colour(1000.0, 0.0, 0.0)).red() < 1.0);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn aces___never_falls_and_stays_in_range() {
+    let mut tm = ToneMapping::default();
+    tm.set_operator(ToneOperator::Aces);
+    let mut last = -1.0;
+    for i in 0..100 {
+        let v = tm.map_colour(colour(i as f64 * 0.2, 0.0, 0.0)).red();
+        assert!(v >= last && (0.0..=1.0).contains(&v), "{} at {}", v, i);
+        last = v;
+    }
+    assert_eq!(tm.map_colour(colour(-1.0, 0.0, 100.0)), colour(0.0, 0.0, 1.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn srgb___encodes_with_the_transfer_curve() {
+    assert!(almost_eq(srgb_encode(0.0), 0.0));
+    assert!(almost_eq(srgb_encode(0.002), 0.02584));
+    assert!(almost_eq(srgb_encode(0.5), 0.73536));
+    assert!(almost_eq(srgb_encode(1.0), 1.0));
+    assert!(almost_eq(srgb_encode(4.0), 1.0));
+
+    let mut tm = ToneMapping::default();
+    tm.set_srgb(true);
+    let c = tm.map_colour(colour(0.5, 0.0, 1.0));
+    assert!(almost_eq(c.red(), 0.73536));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn tone_operators___parse_from_their_names() {
+    assert_eq!("clamp".parse::<ToneOperator>().unwrap(), ToneOperator::Clamp);
+    assert_eq!("none".parse::<ToneOperator>().unwrap(), ToneOperator::Clamp);
+    assert_eq!("reinhard".parse::<ToneOperator>().unwrap(), ToneOperator::Reinhard);
+    assert_eq!("aces".parse::<ToneOperator>().unwrap(), ToneOperator::Aces);
+    assert!("filmic".parse::<ToneOperator>().is_err());
+}
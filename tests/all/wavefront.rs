@@ -2,6 +2,11 @@ use bucktracer::*;
 use bucktracer::math::*;
 use bucktracer::wavefront;
 use bucktracer::wavefront::ParseError;
+use bucktracer::wavefront::Reason;
+
+fn bad_instruction(line: usize, text: &str, reason: Reason) -> Result<Vec<Object>, ParseError> {
+    Err(ParseError::BadInstruction { line, text: text.to_string(), reason })
+}
 
 
 #[allow(non_snake_case)]
@@ -42,8 +47,8 @@ f 1 3 4
 fn bad_instruction_if_vertex_is_not_triple() {
     let mut input1 = "v -1".as_bytes();
     let mut input2 = "v -1 1".as_bytes();
-    assert_eq!(wavefront::read_object_vec(&mut input1), Err(ParseError::BadInstruction));
-    assert_eq!(wavefront::read_object_vec(&mut input2), Err(ParseError::BadInstruction));
+    assert_eq!(wavefront::read_object_vec(&mut input1), bad_instruction(1, "v -1", Reason::MissingValues));
+    assert_eq!(wavefront::read_object_vec(&mut input2), bad_instruction(1, "v -1 1", Reason::MissingValues));
 }
 
 #[allow(non_snake_case)]
@@ -55,7 +60,7 @@ v 1 0 0
 v 1 1 0
 f 0 2 3
 "##.as_bytes();
-    assert_eq!(wavefront::read_object_vec(&mut input), Err(ParseError::BadInstruction));
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(5, "f 0 2 3", Reason::BadIndex("0".to_string())));
 }
 
 #[allow(non_snake_case)]
@@ -73,8 +78,8 @@ v 1 0 0
 v 1 1 0
 f 1
 "##.as_bytes();
-    assert_eq!(wavefront::read_object_vec(&mut input1), Err(ParseError::BadInstruction));
-    assert_eq!(wavefront::read_object_vec(&mut input2), Err(ParseError::BadInstruction));
+    assert_eq!(wavefront::read_object_vec(&mut input1), bad_instruction(5, "f 1 2", Reason::TooFewVertices));
+    assert_eq!(wavefront::read_object_vec(&mut input2), bad_instruction(5, "f 1", Reason::TooFewVertices));
 }
 
 #[allow(non_snake_case)]
@@ -190,6 +195,19 @@ f 1/ 2//1 3//2
     assert_eq!(wavefront::read_object_vec(&mut input).is_err(), true);
 }
 
+#[allow(non_snake_case)]
+#[test]
+fn when_every_point_has_an_empty_index___error() {
+    let mut input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1/ 2/ 3/\n".as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(4, "f 1/ 2/ 3/", Reason::BadIndex("".to_string())));
+
+    let mut input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1// 2// 3//\n".as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(4, "f 1// 2// 3//", Reason::BadIndex("".to_string())));
+
+    let mut input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nvt 0 0\nf 1/1/ 2/1/ 3/1/\n".as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(5, "f 1/1/ 2/1/ 3/1/", Reason::BadIndex("".to_string())));
+}
+
 #[allow(non_snake_case)]
 #[test]
 fn when_face_specifies_one_vertex_normal___all_must_specify_normals() {
@@ -205,3 +223,538 @@ f 1//3 2 3
 "##.as_bytes();
     assert_eq!(wavefront::read_object_vec(&mut input).is_err(), true);
 }
+
+#[allow(non_snake_case)]
+#[test]
+fn texture_coordinates___are_recorded_on_triangles() {
+    let mut input = r##"v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0.5 1
+vt 0 0
+vt 1 0 0
+f 1/1 2/2 3/3
+"##.as_bytes();
+    let out = wavefront::read_object_vec(&mut input).unwrap();
+    let mut expected = triangle(point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0));
+    expected.set_texture_coords([(0.5, 1.0), (0.0, 0.0), (1.0, 0.0)]);
+    assert_eq!(out, vec![expected]);
+
+    let hit = intersection_with_uv(1.0, &out[0], 0.5, 0.25);
+    assert_eq!(out[0].texture_coords_at(&hit), Some((0.375, 0.25)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn texture_coordinates___are_ignored_unless_every_vertex_has_them() {
+    let mut input = r##"v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0.5 1
+f 1/1 2 3
+"##.as_bytes();
+    let out = wavefront::read_object_vec(&mut input).unwrap();
+    let hit = intersection_with_uv(1.0, &out[0], 0.5, 0.25);
+    assert_eq!(out[0].texture_coords_at(&hit), None);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn texture_coordinates___can_accompany_normals() {
+    let mut input = r##"v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0 0
+vn 0 0 -1
+f 1/1/1 2/1/1 3/1/1
+"##.as_bytes();
+    let out = wavefront::read_object_vec(&mut input).unwrap();
+    let hit = intersection_with_uv(1.0, &out[0], 0.5, 0.25);
+    assert_eq!(out[0].texture_coords_at(&hit), Some((0.0, 0.0)));
+}
+
+fn temp_model_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const TWO_TRIANGLES_WITH_MATERIALS: &str = r##"mtllib shiny.mtl
+v 0 1 0
+v -1 0 0
+v 1 0 0
+usemtl red
+f 1 2 3
+usemtl unknown
+f 1 2 3
+"##;
+
+#[allow(non_snake_case)]
+#[test]
+fn usemtl___assigns_materials_from_the_library() {
+    let dir = temp_model_dir("bucktracer_obj_mtl");
+    std::fs::write(dir.join("shiny.mtl"), r##"# a comment
+newmtl red
+Kd 1 0 0
+Ks 0.25 0.5 0.75
+Ns 50
+d 0.25
+Ni 1.5
+"##).unwrap();
+    let out = wavefront::read_object_vec_from(&mut TWO_TRIANGLES_WITH_MATERIALS.as_bytes(), &dir).unwrap();
+
+    let mut red = Material::default();
+    red.set_pattern(Pattern::solid(colour(1.0, 0.0, 0.0)))
+        .set_specular(0.5)
+        .set_shininess(50.0)
+        .set_transparency(0.75)
+        .set_refractive_index(1.5);
+    assert_eq!(out[0].material(), red);
+    assert_eq!(out[1].material(), Material::default());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mtllib___is_ignored_without_a_directory() {
+    let out = wavefront::read_object_vec(&mut TWO_TRIANGLES_WITH_MATERIALS.as_bytes()).unwrap();
+    assert_eq!(out[0].material(), Material::default());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn missing_material_library___leaves_the_default_material() {
+    let dir = temp_model_dir("bucktracer_obj_no_mtl");
+    let _ = std::fs::remove_file(dir.join("shiny.mtl"));
+    let out = wavefront::read_object_vec_from(&mut TWO_TRIANGLES_WITH_MATERIALS.as_bytes(), &dir).unwrap();
+    assert_eq!(out.len(), 2);
+    assert_eq!(out[0].material(), Material::default());
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mtllib___reads_every_library_named() {
+    let dir = temp_model_dir("bucktracer_obj_two_mtl");
+    std::fs::write(dir.join("reds.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+    std::fs::write(dir.join("blues.mtl"), "newmtl blue\nKd 0 0 1\n").unwrap();
+    let mut input = r##"mtllib reds.mtl  blues.mtl
+v 0 1 0
+v -1 0 0
+v 1 0 0
+usemtl red
+f 1 2 3
+usemtl blue
+f 1 2 3
+"##.as_bytes();
+    let out = wavefront::read_object_vec_from(&mut input, &dir).unwrap();
+
+    assert_eq!(out[0].material().pattern(), Pattern::solid(colour(1.0, 0.0, 0.0)));
+    assert_eq!(out[1].material().pattern(), Pattern::solid(colour(0.0, 0.0, 1.0)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn map_Kd___colours_the_triangle_using_its_texture_coordinates() {
+    let dir = temp_model_dir("bucktracer_obj_map_kd");
+    let mut image = canvas(2, 1);
+    image.set_colour_at(0, 0, colour(1.0, 0.0, 0.0));
+    image.set_colour_at(1, 0, colour(0.0, 0.0, 1.0));
+    let mut f = std::fs::File::create(dir.join("halves.png")).unwrap();
+    bucktracer::png::encode(&image, &mut f).unwrap();
+    let mut mtl = "newmtl halves\nmap_Kd halves.png\nKd 0 1 0\n".as_bytes();
+
+    let materials = wavefront::read_materials(&mut mtl, &dir).unwrap();
+    let pattern = materials["halves"].pattern();
+    assert_eq!(pattern.colour_at_uv(point(0.0, 0.0, 0.0), Some((0.25, 0.5))), colour(1.0, 0.0, 0.0));
+    assert_eq!(pattern.colour_at_uv(point(0.0, 0.0, 0.0), Some((0.75, 0.5))), colour(0.0, 0.0, 1.0));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn parse_handler___is_told_about_texture_coordinates_and_materials() {
+    #[derive(Default)]
+    struct Counter { texture_coords: usize, libraries: Vec<String>, materials: Vec<String> }
+    impl wavefront::ParseHandler for Counter {
+        fn handle_vertex(&mut self, _x: f64, _y: f64, _z: f64) -> Result<(), Reason> { Ok(()) }
+        fn handle_normal(&mut self, _x: f64, _y: f64, _z: f64) -> Result<(), Reason> { Ok(()) }
+        fn handle_polygon(&mut self, _points: &[wavefront::PolygonPoint]) -> Result<(), Reason> { Ok(()) }
+        fn declare_group(&mut self, _name: &str) -> Result<(), Reason> { Ok(()) }
+        fn handle_texture_coord(&mut self, _u: f64, _v: f64) -> Result<(), Reason> {
+            self.texture_coords += 1;
+            Ok(())
+        }
+        fn use_material_library(&mut self, name: &str) -> Result<(), Reason> {
+            self.libraries.push(name.to_string());
+            Ok(())
+        }
+        fn use_material(&mut self, name: &str) -> Result<(), Reason> {
+            self.materials.push(name.to_string());
+            Ok(())
+        }
+    }
+
+    let mut input = "mtllib a.mtl b.mtl\nvt 0 0\nvt 1 0\nusemtl red\nusemtl blue\n".as_bytes();
+    let mut c = Counter::default();
+    wavefront::parse(&mut c, &mut input).unwrap();
+    assert_eq!(c.texture_coords, 2);
+    assert_eq!(c.libraries, vec!["a.mtl", "b.mtl"]);
+    assert_eq!(c.materials, vec!["red", "blue"]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn face_index_out_of_range___is_an_error() {
+    let mut input = r##"v -1 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 4
+"##.as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(4, "f 1 2 4", Reason::IndexOutOfRange(4)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn normal_index_out_of_range___is_an_error() {
+    let mut input = r##"v -1 1 0
+v -1 0 0
+v 1 0 0
+vn 0 0 1
+f 1//1 2//1 3//2
+"##.as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(5, "f 1//1 2//1 3//2", Reason::IndexOutOfRange(2)));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn unparseable_number___is_reported_with_its_line() {
+    let mut input = "v 0 0 0\n\nv 1 0 1e\n".as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(3, "v 1 0 1e", Reason::BadNumber("1e".to_string())));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn mixed_normals___are_reported() {
+    let mut input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 0 1\nf 1//1 2 3\n".as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(5, "f 1//1 2 3", Reason::MixedNormals));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn free_form_geometry___is_unsupported() {
+    let mut input = "v 0 1 0\ncstype bspline\n".as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(2, "cstype bspline", Reason::Unsupported("cstype".to_string())));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn error_in_material_library___names_the_library_and_line() {
+    let dir = temp_model_dir("bucktracer_obj_bad_mtl");
+    std::fs::write(dir.join("shiny.mtl"), "newmtl red\nKd 1 0\n").unwrap();
+    let result = wavefront::read_object_vec_from(&mut TWO_TRIANGLES_WITH_MATERIALS.as_bytes(), &dir);
+    let inner = ParseError::BadInstruction { line: 2, text: "Kd 1 0".to_string(), reason: Reason::MissingValues };
+    assert_eq!(result, bad_instruction(1, "mtllib shiny.mtl", Reason::InLibrary("shiny.mtl".to_string(), Box::new(inner))));
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn parse_error___displays_line_reason_and_text() {
+    let mut input = "v 0 1 0\nf 1 2 3\n".as_bytes();
+    let e = wavefront::read_object_vec(&mut input).unwrap_err();
+    assert_eq!(e.to_string(), "line 2: index 2 is out of range: f 1 2 3");
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn negative_indices___count_back_from_the_latest_vertex() {
+    let mut relative = r##"v -1 1 0
+v -1 0 0
+v 1 0 0
+f -3 -2 -1
+v 1 1 0
+f -4 -2 -1
+"##.as_bytes();
+    let mut absolute = r##"v -1 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 3
+v 1 1 0
+f 1 3 4
+"##.as_bytes();
+    assert_eq!(
+        wavefront::read_object_vec(&mut relative).unwrap(),
+        wavefront::read_object_vec(&mut absolute).unwrap(),
+    );
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn negative_indices___apply_to_normals_and_texture_coordinates() {
+    let mut relative = r##"v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0 0
+vt 1 0
+vn 0 0 -1
+vn 0 1 0
+f -3/-2/-1 -2/-1/-2 -1/-1/-2
+"##.as_bytes();
+    let mut absolute = r##"v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0 0
+vt 1 0
+vn 0 0 -1
+vn 0 1 0
+f 1/1/2 2/2/1 3/2/1
+"##.as_bytes();
+    assert_eq!(
+        wavefront::read_object_vec(&mut relative).unwrap(),
+        wavefront::read_object_vec(&mut absolute).unwrap(),
+    );
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn negative_index_before_the_first_vertex___is_out_of_range() {
+    let mut input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nf -4 -2 -1\n".as_bytes();
+    assert_eq!(wavefront::read_object_vec(&mut input), bad_instruction(4, "f -4 -2 -1", Reason::IndexOutOfRange(-4)));
+}
+
+fn round_trip(objects: &[Object], tessellation: wavefront::Tessellation) -> Vec<Object> {
+    let mut out: Vec<u8> = vec![];
+    wavefront::write_object_vec(objects, &mut out, tessellation).unwrap();
+    wavefront::read_object_vec(&mut out.as_slice()).unwrap()
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn written_triangles___read_back_the_same() {
+    let mut textured = triangle(point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0));
+    textured.set_texture_coords([(0.5, 1.0), (0.0, 0.0), (1.0, 0.0)]);
+    let objects = vec![
+        triangle(point(-1.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0)),
+        smooth_triangle(point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0),
+                        vector(0.0, 1.0, 0.0), vector(-1.0, 0.0, 0.0), vector(1.0, 0.0, 0.0)),
+        textured,
+    ];
+    assert_eq!(round_trip(&objects, wavefront::Tessellation::Skip), objects);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn written_groups___read_back_as_groups_in_world_space() {
+    let mut inner = group(vec![triangle(point(0.0, 1.0, 0.0), point(0.0, 0.0, 0.0), point(1.0, 0.0, 0.0))]);
+    inner.set_object_to_world_spc(translation(0.0, 0.0, 4.0));
+    let mut outer = group(vec![
+        triangle(point(0.0, 1.0, 0.0), point(0.0, 0.0, 0.0), point(1.0, 0.0, 0.0)),
+        inner,
+    ]);
+    outer.set_object_to_world_spc(scaling(2.0, 2.0, 2.0));
+    let loose = triangle(point(5.0, 1.0, 0.0), point(5.0, 0.0, 0.0), point(6.0, 0.0, 0.0));
+
+    let out = round_trip(&[outer, loose.clone()], wavefront::Tessellation::Skip);
+    assert_eq!(out, vec![
+        loose,
+        group(vec![triangle(point(0.0, 2.0, 0.0), point(0.0, 0.0, 0.0), point(2.0, 0.0, 0.0))]),
+        group(vec![triangle(point(0.0, 2.0, 8.0), point(0.0, 0.0, 8.0), point(2.0, 0.0, 8.0))]),
+    ]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn analytic_shapes___are_skipped_unless_tessellated() {
+    let objects = vec![unit_sphere(), cube(), cylinder(CylKind::Closed, 0.0, 1.0), plane()];
+    assert_eq!(round_trip(&objects, wavefront::Tessellation::Skip), vec![]);
+
+    let out = round_trip(&objects, wavefront::Tessellation::Segments(8));
+    // 8 * 4 sphere bands, less one triangle per segment at each pole;
+    // 2 per cube face; 4 per cylinder segment
+    assert_eq!(out.len(), (8 * 4 * 2 - 16) + 12 + 8 * 4);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn tessellated_sphere___lies_on_the_transformed_sphere() {
+    let mut s = unit_sphere();
+    s.set_object_to_world_spc(translation(1.0, 2.0, 3.0) * scaling(2.0, 2.0, 2.0));
+    let out = round_trip(&[s], wavefront::Tessellation::Segments(12));
+    let centre = point(1.0, 2.0, 3.0);
+    assert_eq!(out.is_empty(), false);
+    for tri in out.iter() {
+        match tri.shape() {
+            Shape::SmoothTri { p1, p2, p3, n1, n2, n3, .. } => {
+                for (p, n) in [(p1, n1), (p2, n2), (p3, n3)].iter() {
+                    let radial = **p - centre;
+                    assert!((radial.magnitude() - 2.0).abs() < 1e-9);
+                    assert!((radial.normalize() - **n).magnitude() < 1e-9);
+                }
+            },
+            other => panic!("expected smooth triangles, got {}", other),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_mesh___shares_the_buffers_between_faces() {
+    let mut input = r##"v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+vt 0 1
+vt 0 0
+vt 1 0
+vt 1 1
+g ignored
+f 1/1 2/2 3/3 -1/-1
+"##.as_bytes();
+    let obj = wavefront::read_mesh(&mut input).unwrap();
+    match obj.shape() {
+        Shape::Mesh(m) => {
+            assert_eq!(m.len(), 2);
+            assert_eq!(m.vertices().len(), 4);
+            assert_eq!(m.texture_coords().len(), 4);
+            assert_eq!(m.normals().len(), 0);
+            let mut faces: Vec<[u32; 3]> = m.faces().iter().map(|f| f.vertices).collect();
+            faces.sort();
+            assert_eq!(faces, vec![[0, 1, 2], [0, 2, 3]]);
+            assert!(m.faces().iter().all(|f| Some(f.vertices) == f.texture_coords));
+        },
+        other => panic!("expected a mesh, got {}", other),
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn read_mesh___reports_bad_faces() {
+    let mut input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 0 1\nf 1 2 4\n".as_bytes();
+    assert_eq!(wavefront::read_mesh(&mut input).unwrap_err(),
+               ParseError::BadInstruction { line: 5, text: "f 1 2 4".to_string(), reason: Reason::IndexOutOfRange(4) });
+
+    let mut input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 0 1\nf 1//1 2 3\n".as_bytes();
+    assert_eq!(wavefront::read_mesh(&mut input).unwrap_err(),
+               ParseError::BadInstruction { line: 5, text: "f 1//1 2 3".to_string(), reason: Reason::MixedNormals });
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn written_mesh___reads_back_as_its_triangles() {
+    let text = "v 0 1 0\nv -1 0 0\nv 1 0 0\nv 0 -1 0\nvn 0 0 -1\nf 1//1 2//1 3//1\nf 3//1 2//1 4//1\n";
+    let mut m = wavefront::read_mesh(&mut text.as_bytes()).unwrap();
+    m.set_object_to_world_spc(translation(0.0, 0.0, 2.0));
+
+    let out = round_trip(&[m], wavefront::Tessellation::Skip);
+    // written in world space
+    let moved = "v 0 1 2\nv -1 0 2\nv 1 0 2\nv 0 -1 2\nvn 0 0 -1\nf 1//1 2//1 3//1\nf 3//1 2//1 4//1\n";
+    let expected = wavefront::read_object_vec(&mut moved.as_bytes()).unwrap();
+    // the mesh may keep its faces in a different order
+    assert_eq!(out.len(), expected.len());
+    for tri in expected.iter() {
+        assert!(out.contains(tri), "{:?} is missing", tri);
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn written_quad___reads_back_as_two_textured_triangles() {
+    let q = quad(point(0.0, 0.0, 0.0), vector(2.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+    let out = round_trip(&[q], wavefront::Tessellation::Skip);
+
+    let mut first = triangle(point(0.0, 0.0, 0.0), point(2.0, 0.0, 0.0), point(2.0, 1.0, 0.0));
+    first.set_texture_coords([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+    let mut second = triangle(point(0.0, 0.0, 0.0), point(2.0, 1.0, 0.0), point(0.0, 1.0, 0.0));
+    second.set_texture_coords([(0.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+    assert_eq!(out, vec![first, second]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn disc___is_tessellated_into_a_fan() {
+    assert_eq!(round_trip(&[disc(1.0)], wavefront::Tessellation::Skip), vec![]);
+    let out = round_trip(&[disc(1.0)], wavefront::Tessellation::Segments(8));
+    assert_eq!(out.len(), 8);
+    for tri in out.iter() {
+        match tri.shape() {
+            Shape::SmoothTri { n1, n2, n3, .. } => {
+                assert!([n1, n2, n3].iter().all(|n| **n == vector(0.0, 1.0, 0.0)));
+            },
+            other => panic!("expected smooth triangles, got {}", other),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn tessellated_cone___lies_on_the_cone_and_its_caps() {
+    let double = cone(CylKind::Closed, -1.0, 2.0);
+    assert_eq!(round_trip(&[double.clone()], wavefront::Tessellation::Skip), vec![]);
+    assert_eq!(round_trip(&[inf_cone()], wavefront::Tessellation::Segments(8)), vec![]);
+
+    let out = round_trip(&[double], wavefront::Tessellation::Segments(8));
+    // 1 per segment on each nappe, as they narrow to the apex, and 1
+    // per segment on each cap
+    assert_eq!(out.len(), 8 * 4);
+    let (up, down) = (vector(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+    for tri in out.iter() {
+        match tri.shape() {
+            Shape::SmoothTri { p1, p2, p3, n1, n2, n3, .. } => {
+                for (p, n) in [(p1, n1), (p2, n2), (p3, n3)].iter() {
+                    let radius = (p.x().powi(2) + p.z().powi(2)).sqrt();
+                    assert!(-1.0 - 1e-9 <= p.y() && p.y() <= 2.0 + 1e-9);
+                    if **n == up || **n == down {
+                        assert!((p.y().abs() - 1.0).abs() < 1e-9 || (p.y() - 2.0).abs() < 1e-9);
+                        assert!(radius <= p.y().abs() + 1e-9);
+                    } else {
+                        assert!((radius - p.y().abs()).abs() < 1e-9);
+                        assert!(n.dot(**p - point(0.0, 0.0, 0.0)).abs() < 1e-9);
+                    }
+                }
+            },
+            other => panic!("expected smooth triangles, got {}", other),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn tessellated_torus___lies_on_the_torus() {
+    assert_eq!(round_trip(&[torus(2.0, 0.5)], wavefront::Tessellation::Skip), vec![]);
+    let out = round_trip(&[torus(2.0, 0.5)], wavefront::Tessellation::Segments(12));
+    // 2 per segment around the axis, for each of 6 around the tube
+    assert_eq!(out.len(), 12 * 6 * 2);
+    for tri in out.iter() {
+        match tri.shape() {
+            Shape::SmoothTri { p1, p2, p3, n1, n2, n3, .. } => {
+                for (p, n) in [(p1, n1), (p2, n2), (p3, n3)].iter() {
+                    let rho = (p.x().powi(2) + p.z().powi(2)).sqrt();
+                    let centre = point(p.x() * 2.0 / rho, 0.0, p.z() * 2.0 / rho);
+                    let radial = **p - centre;
+                    assert!((radial.magnitude() - 0.5).abs() < 1e-9);
+                    assert!((radial.normalize() - **n).magnitude() < 1e-9);
+                }
+            },
+            other => panic!("expected smooth triangles, got {}", other),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn csg_objects___are_left_out() {
+    let lens = csg(CsgOp::Intersection, unit_sphere(), cube());
+    assert_eq!(round_trip(&[lens], wavefront::Tessellation::Segments(8)), vec![]);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn written_instances___are_each_written_in_world_space() {
+    let tri = triangle(point(0.0, 1.0, 0.0), point(0.0, 0.0, 0.0), point(1.0, 0.0, 0.0));
+    let prototype = std::sync::Arc::new(tri.clone());
+    let mut moved = instance(&prototype, None);
+    moved.set_object_to_world_spc(translation(0.0, 0.0, 4.0));
+
+    let out = round_trip(&[instance(&prototype, None), moved], wavefront::Tessellation::Skip);
+    assert_eq!(out, vec![
+        tri,
+        triangle(point(0.0, 1.0, 4.0), point(0.0, 0.0, 4.0), point(1.0, 0.0, 4.0)),
+    ]);
+}
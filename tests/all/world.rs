@@ -109,3 +109,89 @@ fn point_not_in_shadow_when_point_between_light_source_and_object() {
     let l = w.light_sources()[0];
     assert_eq!(w.light_factor(p, &l), 1.0);
 }
+
+/// Finds the intersections by testing every object in turn, without
+/// any help from the world's acceleration structure.
+fn brute_force_intersect<'a>(objects: &'a [Object], r: &Ray) -> Vec<Intersection<'a>> {
+    let mut v = vec![];
+    for o in objects {
+        append_intersects(r, o, &mut v);
+    }
+    v.sort_by(|a, b| a.t_value().partial_cmp(&b.t_value()).unwrap());
+    v
+}
+
+fn nested_groups() -> Vec<Object> {
+    let mut inner = group(vec![
+        unit_sphere().set_object_to_world_spc(translation(2.0, 0.0, 0.0)).clone(),
+        smooth_triangle(
+            point(0.0, 1.0, 0.0), point(-1.0, 0.0, 0.0), point(1.0, 0.0, 0.0),
+            vector(0.0, 1.0, 0.0), vector(-1.0, 0.0, 0.0), vector(1.0, 0.0, 0.0),
+        ),
+    ]);
+    inner.set_object_to_world_spc(scaling(0.5, 2.0, 1.0));
+    let mut outer = group(vec![inner, cube().set_object_to_world_spc(translation(-3.0, 0.0, 1.0)).clone()]);
+    outer.set_object_to_world_spc(rotation_y(0.3) * translation(0.5, 0.0, 2.0));
+    vec![outer, plane().set_object_to_world_spc(translation(0.0, -1.0, 0.0)).clone()]
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn intersect___nested_transformed_groups___finds_same_hits_as_testing_every_object() {
+    let objects = nested_groups();
+    let w = World::with(vec![], objects.clone());
+
+    for i in -8..=8 {
+        for j in -4..=4 {
+            let r = ray(point(f64::from(i) * 0.5, f64::from(j) * 0.5, -10.0), vector(0.05, -0.02, 1.0));
+            let expected = brute_force_intersect(&objects, &r);
+            let actual = w.intersect(&r);
+            assert_eq!(actual, expected);
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn intersect___normals_of_hits_within_groups___match_testing_every_object() {
+    let objects = nested_groups();
+    let w = World::with(vec![], objects.clone());
+    let r = ray(point(1.2, 0.4, -10.0), vector(0.0, 0.0, 1.0));
+
+    let expected = brute_force_intersect(&objects, &r);
+    let actual = w.intersect(&r);
+    assert!(!actual.is_empty());
+    for (a, e) in actual.iter().zip(expected.iter()) {
+        let p = r.position(a.t_value());
+        assert_eq!(a.normal_at(p), e.normal_at(p));
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn prepare___does_not_change_the_hits() {
+    let w = World::default();
+    let r = ray(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let unprepared = World::default();
+    let before = unprepared.intersect(&r);
+    w.prepare();
+    w.prepare();
+    assert_eq!(w.intersect(&r), before);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn intersect___object_within_group___hit_refers_to_the_worlds_own_object() {
+    let w = World::with(vec![], nested_groups());
+    let r = ray(point(1.2, 0.4, -10.0), vector(0.0, 0.0, 1.0));
+    let hits = w.intersect(&r);
+
+    let (outer, floor) = (&w.objects()[0], &w.objects()[1]);
+    let in_world = |o: &Object| {
+        outer.children()[1..].iter().chain(outer.children()[0].children())
+            .chain(std::iter::once(floor))
+            .any(|c| std::ptr::eq(c, o))
+    };
+    assert!(!hits.is_empty());
+    assert!(hits.iter().all(|i| in_world(i.object())));
+}